@@ -0,0 +1,350 @@
+//! ## gfd
+//!
+//! Command-line tool for greenfield images. It can:
+//!
+//! - Convert images between greenfield and common formats (`gfd convert`).
+//! - Print the header of greenfield images (`gfd info`).
+//! - Re-quantize greenfield images (`gfd quantize`).
+//! - Convert whole directory trees in parallel (`gfd batch`).
+//! - Preview images in the terminal (`gfd view`).
+//!
+//! Errors are printed to stderr, and the exit code tells what went wrong (see [`exit_code`]).
+//!
+//! ## Examples
+//!
+//! ```bash
+//! gfd convert Lenna.png Lenna.gfd --bits 5,6,5
+//! gfd info Lenna.gfd
+//! gfd quantize Lenna.gfd Lenna.332.gfd --bits 3,3,2
+//! gfd convert Lenna.332.gfd Lenna.332.png
+//! gfd batch assets/ --output out/ --bits 5,6,5 --bits 3,3,2
+//! gfd view Lenna.gfd --width 60 --backend kitty
+//! ```
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use greenfield::image::{Header, TerminalBackend};
+use greenfield::io::batch::{BatchConversion, BatchStatus};
+use greenfield::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0   Success
+  2   Usage error: unknown, missing or malformed arguments, e.g. `--bits` levels out of 1 to 8
+  64  Quantization parameters rejected by the codec while processing an image
+  65  Invalid or corrupted image data
+  66  Input file not found
+  74  IO error";
+
+/// Command-line tool for greenfield images
+#[derive(Debug, Parser)]
+#[command(name = "gfd", version, after_help = EXIT_CODES)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Convert images between greenfield and common formats
+    ///
+    /// The formats are inferred from the file extensions, unknown extensions are treated as
+    /// greenfield images. The quantization is only used when converting from common formats.
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Quantization tuple, as the bits of the red, green and blue components
+        #[arg(short, long, value_name = "R,G,B", value_parser = parse_bits, default_value = "8,8,8")]
+        bits: UniformQuantization,
+    },
+
+    /// Print the header and payload size of greenfield images
+    Info {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+    },
+
+    /// Re-quantize a greenfield image
+    Quantize {
+        input: PathBuf,
+        output: PathBuf,
+        /// Quantization tuple, as the bits of the red, green and blue components
+        #[arg(short, long, value_name = "R,G,B", value_parser = parse_bits)]
+        bits: UniformQuantization,
+    },
+
+    /// Convert files and directory trees in parallel
+    ///
+    /// Directories are walked recursively and mirrored into the output directory. With more than
+    /// one quantization, each one gets its own subdirectory (e.g. `565/`). Files whose outputs
+    /// are newer are skipped.
+    Batch {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Output directory
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Quantization tuples, as the bits of the red, green and blue components
+        #[arg(short, long, value_name = "R,G,B", value_parser = parse_bits, default_value = "8,8,8")]
+        bits: Vec<UniformQuantization>,
+        /// Extension, and so format, of the outputs
+        #[arg(short, long, default_value = "gfd")]
+        extension: String,
+        /// Convert the files even if their outputs are up to date
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Preview images in the terminal
+    View {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Width of the preview, in terminal columns
+        #[arg(short, long, default_value_t = 80)]
+        width: usize,
+        /// Terminal graphics protocol
+        #[arg(short, long, value_enum, default_value_t = Backend::HalfBlock)]
+        backend: Backend,
+    },
+}
+
+/// Terminal graphics protocols of `gfd view`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Backend {
+    /// Unicode half blocks with 24-bit colors, works in any truecolor terminal
+    HalfBlock,
+    /// DEC sixel graphics
+    Sixel,
+    /// Kitty graphics protocol
+    Kitty,
+}
+
+impl From<Backend> for TerminalBackend {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::HalfBlock => Self::HalfBlock,
+            Backend::Sixel => Self::Sixel,
+            Backend::Kitty => Self::Kitty,
+        }
+    }
+}
+
+/// ## An error of a command, with the file it happened on
+#[derive(Debug)]
+struct Failure {
+    path: PathBuf,
+    error: Arc<GreenfieldError>,
+}
+
+/// Attaches the file an error happened on.
+trait Context<T> {
+    fn on(self, path: &Path) -> Result<T, Failure>;
+}
+
+impl<T, E: Into<GreenfieldError>> Context<T> for Result<T, E> {
+    fn on(self, path: &Path) -> Result<T, Failure> {
+        self.map_err(|e| Failure {
+            path: path.to_path_buf(),
+            error: Arc::new(e.into()),
+        })
+    }
+}
+
+/// ## Parses a `R,G,B` quantization tuple
+fn parse_bits(bits: &str) -> Result<UniformQuantization, String> {
+    let levels = bits
+        .split(',')
+        .map(|b| b.trim().parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|e| format!("{e}, expected R,G,B"))?;
+
+    match levels[..] {
+        [r, g, b] => UniformQuantization::new(r, g, b).map_err(|e| e.to_string()),
+        _ => Err(format!("expected 3 levels (R,G,B), found {}", levels.len())),
+    }
+}
+
+/// ## Maps an error to the exit code of the process
+///
+/// The codes follow the BSD `sysexits.h` conventions.
+fn exit_code(error: &GreenfieldError) -> u8 {
+    const USAGE: u8 = 64;
+    const DATA: u8 = 65;
+    const NO_INPUT: u8 = 66;
+    const IO: u8 = 74;
+
+    let io = |e: &std::io::Error| match e.kind() {
+        std::io::ErrorKind::NotFound => NO_INPUT,
+        _ => IO,
+    };
+
+    match error {
+        GreenfieldError::InvalidQuantizationLevel(..)
+        | GreenfieldError::InvalidPaletteSize(..)
+        | GreenfieldError::InvalidDitheringKernel(..)
+        | GreenfieldError::InvalidLookupTable(..)
+        | GreenfieldError::InvalidCurve(..)
+        | GreenfieldError::InvalidBounds(..)
+        | GreenfieldError::InvalidLevelCount(..)
+        | GreenfieldError::MismatchedDimensions(..) => USAGE,
+        GreenfieldError::InvalidDataSize(..)
+        | GreenfieldError::InvalidHexColor(..)
+        | GreenfieldError::InvalidImageDimension(..)
+        | GreenfieldError::ImageTooLarge(..)
+        | GreenfieldError::DekuError(..) => DATA,
+        GreenfieldError::IoError(e) => io(e),
+        GreenfieldError::ImageError(image::ImageError::IoError(e)) => io(e),
+        GreenfieldError::ImageError(..) => DATA,
+    }
+}
+
+/// ## Converts an image between formats
+fn convert(input: &Path, output: &Path, bits: UniformQuantization) -> Result<(), Failure> {
+    let image = load_image(&input.to_path_buf(), bits).on(input)?;
+    save_image(&image, &output.to_path_buf()).on(output)
+}
+
+/// ## Prints the header of greenfield images
+fn info(inputs: &[PathBuf]) -> Result<(), Failure> {
+    for input in inputs {
+        let mut bytes = Vec::new();
+        let file = File::open(input).on(input)?;
+        let size = file.metadata().on(input)?.len();
+        file.take(Header::MAX_BITS.div_ceil(8) as u64)
+            .read_to_end(&mut bytes)
+            .on(input)?;
+
+        let header = Header::deserialize(&bytes).on(input)?;
+        let bits_per_pixel = header.quantization.bits_per_pixel();
+        // Headers can claim any dimensions, even ones larger than memory
+        let (payload, expected) = header
+            .width
+            .checked_mul(header.height)
+            .and_then(|pixels| pixels.checked_mul(bits_per_pixel))
+            .and_then(|payload| Some((payload, header.bits().checked_add(payload)?)))
+            .ok_or(GreenfieldError::ImageTooLarge(header.width, header.height))
+            .on(input)?;
+
+        println!("{}:", input.display());
+        println!("  dimensions:   {}x{}", header.width, header.height);
+        println!("  quantization: {}", header.quantization);
+        println!("  bits/pixel:   {}", bits_per_pixel);
+        println!("  payload:      {} bytes", payload.div_ceil(8));
+        println!(
+            "  file:         {} bytes (expected {})",
+            size,
+            expected.div_ceil(8)
+        );
+    }
+
+    Ok(())
+}
+
+/// ## Re-quantizes a greenfield image
+fn quantize(input: &Path, output: &Path, bits: UniformQuantization) -> Result<(), Failure> {
+    let image = Image::from_file(&input.to_path_buf()).on(input)?;
+    let (width, height) = image.dimensions();
+    let colors = image.colors().cloned().collect::<Vec<Rgb>>();
+
+    let image = Image::new(width, height, bits, colors).on(input)?;
+    image.to_file(&output.to_path_buf()).on(output)
+}
+
+/// ## Prints images to the terminal
+///
+/// Both greenfield images and common formats can be previewed.
+fn view(inputs: &[PathBuf], width: usize, backend: Backend) -> Result<(), Failure> {
+    for input in inputs {
+        let image = load_image(input, UniformQuantization::default()).on(input)?;
+
+        println!("{}: {}", input.display(), image);
+        print!("{}", image.render_terminal(width, backend.into()));
+    }
+
+    Ok(())
+}
+
+/// ## Converts files and directory trees in parallel, printing a per-file report
+///
+/// All the failed files are returned, the others are printed to stdout.
+fn batch(inputs: &[PathBuf], conversion: BatchConversion) -> Result<(), Vec<Failure>> {
+    // Only fails if an input can't be read or a directory can't be walked
+    let report = conversion.run(inputs).map_err(|e| {
+        vec![Failure {
+            path: e.path,
+            error: Arc::new(e.error),
+        }]
+    })?;
+
+    let mut failures = Vec::new();
+    for entry in report.entries {
+        let (input, output) = (entry.input.display(), entry.output.display());
+        match entry.status {
+            BatchStatus::Converted => println!("converted   {input} -> {output}"),
+            BatchStatus::UpToDate => println!("up to date  {input} -> {output}"),
+            BatchStatus::Failed(error) => failures.push(Failure {
+                path: entry.input,
+                error,
+            }),
+        }
+    }
+
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(failures),
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Vec<Failure>> {
+    match cli.command {
+        Command::Convert {
+            input,
+            output,
+            bits,
+        } => convert(&input, &output, bits).map_err(|f| vec![f]),
+        Command::Info { inputs } => info(&inputs).map_err(|f| vec![f]),
+        Command::Quantize {
+            input,
+            output,
+            bits,
+        } => quantize(&input, &output, bits).map_err(|f| vec![f]),
+        Command::Batch {
+            inputs,
+            output,
+            bits,
+            extension,
+            force,
+        } => batch(
+            &inputs,
+            BatchConversion::new(output, bits)
+                .extension(extension)
+                .force(force),
+        ),
+        Command::View {
+            inputs,
+            width,
+            backend,
+        } => view(&inputs, width, backend).map_err(|f| vec![f]),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failures) => {
+            for Failure { path, error } in &failures {
+                eprintln!("gfd: {}: {}", path.display(), error);
+            }
+
+            // The first failure decides the exit code
+            ExitCode::from(exit_code(&failures[0].error))
+        }
+    }
+}
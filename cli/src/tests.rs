@@ -0,0 +1,224 @@
+use super::*;
+use std::env;
+
+/// Temporary directory for the tests, removed on drop
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("gfd-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+/// Should parse the quantization tuple
+fn cli_parse_bits() -> GreenfieldResult<()> {
+    assert_eq!(parse_bits("5,6,5"), Ok(UniformQuantization::new(5, 6, 5)?));
+    assert_eq!(
+        parse_bits("3, 3, 2"),
+        Ok(UniformQuantization::new(3, 3, 2)?)
+    );
+
+    // Invalid levels
+    assert!(parse_bits("0,6,5").is_err());
+    assert!(parse_bits("9,6,5").is_err());
+
+    // Invalid tuple
+    assert!(parse_bits("5,6").is_err());
+    assert!(parse_bits("5,6,5,1").is_err());
+    assert!(parse_bits("a,6,5").is_err());
+    assert!(parse_bits("").is_err());
+
+    Ok(())
+}
+
+#[test]
+/// Should parse the subcommands
+fn cli_parse() -> GreenfieldResult<()> {
+    let cli = Cli::try_parse_from(["gfd", "convert", "in.png", "out.gfd"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::Convert { bits, .. } if bits == UniformQuantization::new(8, 8, 8)?
+    ));
+
+    let cli = Cli::try_parse_from(["gfd", "quantize", "in.gfd", "out.gfd", "-b", "3,3,2"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::Quantize { bits, .. } if bits == UniformQuantization::new(3, 3, 2)?
+    ));
+
+    let cli = Cli::try_parse_from(["gfd", "info", "a.gfd", "b.gfd"]).unwrap();
+    assert!(matches!(cli.command, Command::Info { inputs } if inputs.len() == 2));
+
+    // Missing arguments
+    assert!(Cli::try_parse_from(["gfd", "info"]).is_err());
+    assert!(Cli::try_parse_from(["gfd", "quantize", "in.gfd", "out.gfd"]).is_err());
+
+    // Invalid quantization
+    assert!(Cli::try_parse_from(["gfd", "convert", "in.png", "out.gfd", "-b", "9,9,9"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+/// Should map errors to exit codes
+fn cli_exit_code() {
+    let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+    let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+
+    assert_eq!(
+        exit_code(&GreenfieldError::InvalidQuantizationLevel(0, 0, 0)),
+        64
+    );
+    assert_eq!(exit_code(&GreenfieldError::InvalidImageDimension(1, 2)), 65);
+    assert_eq!(
+        exit_code(&GreenfieldError::ImageTooLarge(1, usize::MAX)),
+        65
+    );
+    assert_eq!(
+        exit_code(&Header::deserialize(b"grnfld43").unwrap_err()),
+        65
+    );
+    assert_eq!(exit_code(&GreenfieldError::IoError(not_found)), 66);
+    assert_eq!(exit_code(&GreenfieldError::IoError(denied)), 74);
+}
+
+#[test]
+/// Should convert, re-quantize and inspect images
+fn cli_commands() -> GreenfieldResult<()> {
+    let dir = TempDir::new("commands");
+    let png = dir.0.join("image.png");
+    let gfd = dir.0.join("image.gfd");
+    let requantized = dir.0.join("image.332.gfd");
+    let colors = vec![
+        Rgb::new(255, 0, 0),
+        Rgb::new(0, 255, 0),
+        Rgb::new(0, 0, 255),
+        Rgb::new(255, 255, 255),
+    ];
+
+    let image = Image::new(2, 2, UniformQuantization::new(8, 8, 8)?, colors)?;
+    save_image(&image, &png)?;
+
+    // Common format -> greenfield
+    convert(&png, &gfd, UniformQuantization::new(5, 6, 5)?).unwrap();
+    let converted = Image::from_file(&gfd)?;
+    assert_eq!(converted.dimensions(), (2, 2));
+    assert_eq!(*converted.quantizer(), UniformQuantization::new(5, 6, 5)?);
+
+    // Greenfield -> greenfield
+    quantize(&gfd, &requantized, UniformQuantization::new(3, 3, 2)?).unwrap();
+    let requantized_image = Image::from_file(&requantized)?;
+    assert_eq!(
+        *requantized_image.quantizer(),
+        UniformQuantization::new(3, 3, 2)?
+    );
+
+    info(&[gfd.clone(), requantized]).unwrap();
+
+    // Missing input
+    let failure = info(&[dir.0.join("missing.gfd")]).unwrap_err();
+    assert_eq!(failure.path, dir.0.join("missing.gfd"));
+    assert_eq!(exit_code(&failure.error), 66);
+
+    // Not a greenfield image
+    let failure = info(&[png]).unwrap_err();
+    assert_eq!(exit_code(&failure.error), 65);
+
+    // Dimensions too large for the payload size
+    let mut bytes = std::fs::read(&gfd)?;
+    bytes[8..16].fill(0xff);
+    let huge = dir.0.join("huge.gfd");
+    std::fs::write(&huge, bytes)?;
+    let failure = info(&[huge]).unwrap_err();
+    assert_eq!(exit_code(&failure.error), 65);
+
+    Ok(())
+}
+
+#[test]
+/// Should convert directory trees, reporting every failed file
+fn cli_batch() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    std::fs::create_dir_all(input.join("nested"))?;
+
+    let image = Image::new(
+        1,
+        1,
+        UniformQuantization::new(8, 8, 8)?,
+        vec![Rgb::new(0, 0, 0)],
+    )?;
+    save_image(&image, &input.join("nested").join("a.png"))?;
+    std::fs::write(input.join("b.gfd"), "not a gfd")?;
+    std::fs::write(input.join("c.gfd"), "not a gfd either")?;
+
+    let cli = Cli::try_parse_from([
+        "gfd",
+        "batch",
+        input.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "-b",
+        "5,6,5",
+        "-b",
+        "3,3,2",
+    ])
+    .unwrap();
+
+    let failures = run(cli).unwrap_err();
+    assert_eq!(failures.len(), 4);
+    assert_eq!(failures[0].path, input.join("b.gfd"));
+    assert_eq!(exit_code(&failures[0].error), 65);
+    assert!(output.join("565").join("nested").join("a.gfd").exists());
+    assert!(output.join("332").join("nested").join("a.gfd").exists());
+
+    // Missing input
+    let conversion = BatchConversion::new(&output, vec![UniformQuantization::new(8, 8, 8)?]);
+    let failures = batch(&[dir.0.join("missing")], conversion).unwrap_err();
+    assert_eq!(failures[0].path, dir.0.join("missing"));
+    assert_eq!(exit_code(&failures[0].error), 66);
+
+    Ok(())
+}
+
+#[test]
+/// Should parse the terminal preview options
+fn cli_view() -> GreenfieldResult<()> {
+    let cli = Cli::try_parse_from(["gfd", "view", "a.gfd"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::View {
+            width: 80,
+            backend: Backend::HalfBlock,
+            ..
+        }
+    ));
+
+    let cli = Cli::try_parse_from(["gfd", "view", "a.gfd", "-w", "40", "-b", "kitty"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Command::View {
+            width: 40,
+            backend: Backend::Kitty,
+            ..
+        }
+    ));
+
+    assert!(Cli::try_parse_from(["gfd", "view", "a.gfd", "-b", "ascii"]).is_err());
+
+    // Missing input
+    let failure = view(&[PathBuf::from("missing.gfd")], 80, Backend::Sixel).unwrap_err();
+    assert_eq!(exit_code(&failure.error), 66);
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+#ifndef GREENFIELD_H
+#define GREENFIELD_H
+
+/* Generated by cbindgen from ffi/src/lib.rs. Do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// ## Status codes
+//
+// Returned by every function of the library. The values are stable: new codes may be added, but
+// existing ones are never changed.
+typedef enum GfdStatus {
+  // Success.
+  GFD_STATUS_OK = 0,
+  // A required pointer argument was null.
+  GFD_STATUS_NULL_POINTER = 1,
+  // The quantization levels must be between 1 and 8.
+  GFD_STATUS_INVALID_QUANTIZATION_LEVEL = 2,
+  // The color data doesn't match the quantization.
+  GFD_STATUS_INVALID_DATA_SIZE = 3,
+  // The color data doesn't match the image dimensions.
+  GFD_STATUS_INVALID_IMAGE_DIMENSION = 4,
+  // A hex color is malformed.
+  GFD_STATUS_INVALID_HEX_COLOR = 5,
+  // An IO operation failed.
+  GFD_STATUS_IO = 6,
+  // The data is not a valid greenfield image, or is truncated.
+  GFD_STATUS_DECODE = 7,
+  // Conversion from/to other image formats failed.
+  GFD_STATUS_IMAGE = 8,
+  // Palettes must have between 1 and 65536 colors.
+  GFD_STATUS_INVALID_PALETTE_SIZE = 9,
+  // A dithering kernel is malformed.
+  GFD_STATUS_INVALID_DITHERING_KERNEL = 10,
+  // A reconstruction lookup table has the wrong number of entries.
+  GFD_STATUS_INVALID_LOOKUP_TABLE = 11,
+  // A companding curve is malformed.
+  GFD_STATUS_INVALID_CURVE = 12,
+  // The minimum of a component range is above its maximum.
+  GFD_STATUS_INVALID_BOUNDS = 13,
+  // Components must have between 1 and 256 levels.
+  GFD_STATUS_INVALID_LEVEL_COUNT = 14,
+  // Compared images must have the same dimensions.
+  GFD_STATUS_MISMATCHED_DIMENSIONS = 15,
+  // The size of the image can't be represented in memory.
+  GFD_STATUS_IMAGE_TOO_LARGE = 16,
+  // Unexpected internal error.
+  GFD_STATUS_INTERNAL = 255,
+} GfdStatus;
+
+// ## Image header
+//
+// The dimensions and quantization tuple of a greenfield image. Images with other quantizers than a
+// uniform one (e.g. a palette) have a `0, 0, 0` tuple.
+typedef struct GfdHeader {
+  uint32_t width;
+  uint32_t height;
+  uint8_t bits_r;
+  uint8_t bits_g;
+  uint8_t bits_b;
+} GfdHeader;
+
+// ## Byte buffer owned by the caller
+//
+// Must be freed with [`gfd_buffer_free`].
+typedef struct GfdBuffer {
+  uint8_t *data;
+  size_t len;
+} GfdBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// ## Reads the header of a greenfield image
+//
+// Only the header is read, so `data` may be just the beginning of the image.
+//
+// ## Errors
+// * `GFD_STATUS_NULL_POINTER` if `data` or `header` is null
+// * `GFD_STATUS_DECODE` if `data` doesn't start with a valid greenfield header
+//
+// # Safety
+//
+// `data` must be valid for reads of `len` bytes and `header` must be valid for writes.
+enum GfdStatus gfd_probe(const uint8_t *data, size_t len, struct GfdHeader *header);
+
+// ## Decodes a greenfield image to RGB8
+//
+// The colors are written to `pixels` in row-major order, 3 bytes per pixel.
+//
+// ## Errors
+// * `GFD_STATUS_NULL_POINTER` if `data` or `pixels` is null
+// * `GFD_STATUS_DECODE` if `data` is not a valid greenfield image
+//
+// # Safety
+//
+// `data` must be valid for reads of `len` bytes, `pixels` must be valid for writes, and `header`
+// must be null or valid for writes. On success, `pixels` must be freed with [`gfd_buffer_free`].
+enum GfdStatus gfd_decode(const uint8_t *data,
+                          size_t len,
+                          struct GfdHeader *header,
+                          struct GfdBuffer *pixels);
+
+// ## Encodes RGB8 pixels as a greenfield image
+//
+// `pixels` holds `width * height` colors in row-major order, 3 bytes per pixel, which are
+// quantized to `(bits_r, bits_g, bits_b)`.
+//
+// ## Errors
+// * `GFD_STATUS_NULL_POINTER` if `pixels` or `out` is null
+// * `GFD_STATUS_INVALID_QUANTIZATION_LEVEL` if the quantization tuple is invalid
+// * `GFD_STATUS_INVALID_IMAGE_DIMENSION` if `len` is not `width * height * 3`
+//
+// # Safety
+//
+// `pixels` must be valid for reads of `len` bytes and `out` must be valid for writes. On
+// success, `out` must be freed with [`gfd_buffer_free`].
+enum GfdStatus gfd_encode(const uint8_t *pixels,
+                          size_t len,
+                          uint32_t width,
+                          uint32_t height,
+                          uint8_t bits_r,
+                          uint8_t bits_g,
+                          uint8_t bits_b,
+                          struct GfdBuffer *out);
+
+// ## Frees a buffer returned by the library
+//
+// The buffer is reset to null, so freeing it twice is harmless. Null is ignored.
+//
+// # Safety
+//
+// `buffer` must be null or point to a buffer returned by this library.
+void gfd_buffer_free(struct GfdBuffer *buffer);
+
+// ## Returns a static, human-readable description of a status code
+//
+// The code is taken as an integer, so that any value is safe to pass: unknown codes are described
+// as such.
+const char *gfd_status_message(int status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GREENFIELD_H */
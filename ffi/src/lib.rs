@@ -0,0 +1,385 @@
+//! ## C ABI for the greenfield image format
+//!
+//! Exposes the greenfield codec to other languages (C, C++, Python's ctypes, ...) through a
+//! cdylib/staticlib. The C header is in `include/greenfield.h`, generated with cbindgen from this
+//! file.
+//!
+//! Every function returns a [`GfdStatus`], a stable code mapped from
+//! [`GreenfieldError`]. Buffers returned by the library are owned by the caller and must be freed
+//! with [`gfd_buffer_free`].
+//!
+//! ## Examples
+//!
+//! ```c
+//! #include <stdio.h>
+//! #include "greenfield.h"
+//!
+//! int main(void) {
+//!     const uint8_t rgb[] = {255, 0, 0, 0, 0, 255};
+//!     GfdBuffer encoded, decoded;
+//!     GfdHeader header;
+//!
+//!     GfdStatus status = gfd_encode(rgb, sizeof(rgb), 2, 1, 5, 6, 5, &encoded);
+//!     if (status != GFD_STATUS_OK) {
+//!         fprintf(stderr, "%s\n", gfd_status_message(status));
+//!         return status;
+//!     }
+//!
+//!     gfd_probe(encoded.data, encoded.len, &header);
+//!     printf("%ux%u (%u, %u, %u)\n", header.width, header.height, header.bits_r,
+//!            header.bits_g, header.bits_b);
+//!
+//!     gfd_decode(encoded.data, encoded.len, &header, &decoded);
+//!
+//!     gfd_buffer_free(&encoded);
+//!     gfd_buffer_free(&decoded);
+//!     return 0;
+//! }
+//! ```
+use std::ffi::{c_char, c_int};
+use std::panic::{self, UnwindSafe};
+use std::{ptr, slice};
+
+use greenfield::image::Header;
+use greenfield::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// ## Status codes
+///
+/// Returned by every function of the library. The values are stable: new codes may be added, but
+/// existing ones are never changed.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GfdStatus {
+    /// Success.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// The quantization levels must be between 1 and 8.
+    InvalidQuantizationLevel = 2,
+    /// The color data doesn't match the quantization.
+    InvalidDataSize = 3,
+    /// The color data doesn't match the image dimensions.
+    InvalidImageDimension = 4,
+    /// A hex color is malformed.
+    InvalidHexColor = 5,
+    /// An IO operation failed.
+    Io = 6,
+    /// The data is not a valid greenfield image, or is truncated.
+    Decode = 7,
+    /// Conversion from/to other image formats failed.
+    Image = 8,
+    /// Palettes must have between 1 and 65536 colors.
+    InvalidPaletteSize = 9,
+    /// A dithering kernel is malformed.
+    InvalidDitheringKernel = 10,
+    /// A reconstruction lookup table has the wrong number of entries.
+    InvalidLookupTable = 11,
+    /// A companding curve is malformed.
+    InvalidCurve = 12,
+    /// The minimum of a component range is above its maximum.
+    InvalidBounds = 13,
+    /// Components must have between 1 and 256 levels.
+    InvalidLevelCount = 14,
+    /// Compared images must have the same dimensions.
+    MismatchedDimensions = 15,
+    /// The size of the image can't be represented in memory.
+    ImageTooLarge = 16,
+    /// Unexpected internal error.
+    Internal = 255,
+}
+
+impl GfdStatus {
+    /// Every status, to map the codes received from C back to them.
+    const ALL: [Self; 18] = [
+        Self::Ok,
+        Self::NullPointer,
+        Self::InvalidQuantizationLevel,
+        Self::InvalidDataSize,
+        Self::InvalidImageDimension,
+        Self::InvalidHexColor,
+        Self::Io,
+        Self::Decode,
+        Self::Image,
+        Self::InvalidPaletteSize,
+        Self::InvalidDitheringKernel,
+        Self::InvalidLookupTable,
+        Self::InvalidCurve,
+        Self::InvalidBounds,
+        Self::InvalidLevelCount,
+        Self::MismatchedDimensions,
+        Self::ImageTooLarge,
+        Self::Internal,
+    ];
+}
+
+impl From<&GreenfieldError> for GfdStatus {
+    fn from(e: &GreenfieldError) -> Self {
+        match e {
+            GreenfieldError::InvalidQuantizationLevel(..) => Self::InvalidQuantizationLevel,
+            GreenfieldError::InvalidDataSize(..) => Self::InvalidDataSize,
+            GreenfieldError::InvalidHexColor(..) => Self::InvalidHexColor,
+            GreenfieldError::InvalidImageDimension(..) => Self::InvalidImageDimension,
+            GreenfieldError::IoError(..) => Self::Io,
+            GreenfieldError::DekuError(..) => Self::Decode,
+            GreenfieldError::ImageError(..) => Self::Image,
+            GreenfieldError::InvalidPaletteSize(..) => Self::InvalidPaletteSize,
+            GreenfieldError::InvalidDitheringKernel(..) => Self::InvalidDitheringKernel,
+            GreenfieldError::InvalidLookupTable(..) => Self::InvalidLookupTable,
+            GreenfieldError::InvalidCurve(..) => Self::InvalidCurve,
+            GreenfieldError::InvalidBounds(..) => Self::InvalidBounds,
+            GreenfieldError::InvalidLevelCount(..) => Self::InvalidLevelCount,
+            GreenfieldError::MismatchedDimensions(..) => Self::MismatchedDimensions,
+            GreenfieldError::ImageTooLarge(..) => Self::ImageTooLarge,
+        }
+    }
+}
+
+/// ## Image header
+///
+/// The dimensions and quantization tuple of a greenfield image. Images with other quantizers than a
+/// uniform one (e.g. a palette) have a `0, 0, 0` tuple.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GfdHeader {
+    pub width: u32,
+    pub height: u32,
+    pub bits_r: u8,
+    pub bits_g: u8,
+    pub bits_b: u8,
+}
+
+impl From<&Header> for GfdHeader {
+    fn from(header: &Header) -> Self {
+        let (bits_r, bits_g, bits_b) = match &header.quantization {
+            Quantization::Uniform(UniformQuantization {
+                bits_r,
+                bits_g,
+                bits_b,
+                ..
+            }) => (*bits_r, *bits_g, *bits_b),
+            Quantization::Palette(_) | Quantization::Levels(_) => (0, 0, 0),
+        };
+
+        // The dimensions are stored in 32 bits on disk
+        Self {
+            width: header.width as u32,
+            height: header.height as u32,
+            bits_r,
+            bits_g,
+            bits_b,
+        }
+    }
+}
+
+/// ## Byte buffer owned by the caller
+///
+/// Must be freed with [`gfd_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct GfdBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl From<Vec<u8>> for GfdBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// Runs `f`, converting its errors to status codes. Panics must not unwind into the caller.
+fn guard<F: FnOnce() -> Result<(), GfdStatus> + UnwindSafe>(f: F) -> GfdStatus {
+    match panic::catch_unwind(f) {
+        Ok(Ok(())) => GfdStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => GfdStatus::Internal,
+    }
+}
+
+/// Borrows a byte array passed by the caller.
+///
+/// # Safety
+///
+/// `data` must be null or valid for reads of `len` bytes.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], GfdStatus> {
+    match data.is_null() {
+        true if len == 0 => Ok(&[]),
+        true => Err(GfdStatus::NullPointer),
+        false => Ok(slice::from_raw_parts(data, len)),
+    }
+}
+
+/// ## Reads the header of a greenfield image
+///
+/// Only the header is read, so `data` may be just the beginning of the image.
+///
+/// ## Errors
+/// * `GFD_STATUS_NULL_POINTER` if `data` or `header` is null
+/// * `GFD_STATUS_DECODE` if `data` doesn't start with a valid greenfield header
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `header` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn gfd_probe(
+    data: *const u8,
+    len: usize,
+    header: *mut GfdHeader,
+) -> GfdStatus {
+    guard(|| {
+        let bytes = bytes(data, len)?;
+        if header.is_null() {
+            return Err(GfdStatus::NullPointer);
+        }
+
+        let probed = Header::deserialize(bytes).map_err(|e| GfdStatus::from(&e))?;
+        header.write(GfdHeader::from(&probed));
+        Ok(())
+    })
+}
+
+/// ## Decodes a greenfield image to RGB8
+///
+/// The colors are written to `pixels` in row-major order, 3 bytes per pixel.
+///
+/// ## Errors
+/// * `GFD_STATUS_NULL_POINTER` if `data` or `pixels` is null
+/// * `GFD_STATUS_DECODE` if `data` is not a valid greenfield image
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes, `pixels` must be valid for writes, and `header`
+/// must be null or valid for writes. On success, `pixels` must be freed with [`gfd_buffer_free`].
+#[no_mangle]
+pub unsafe extern "C" fn gfd_decode(
+    data: *const u8,
+    len: usize,
+    header: *mut GfdHeader,
+    pixels: *mut GfdBuffer,
+) -> GfdStatus {
+    guard(|| {
+        let bytes = bytes(data, len)?;
+        if pixels.is_null() {
+            return Err(GfdStatus::NullPointer);
+        }
+
+        let image = Image::deserialize(bytes).map_err(|e| GfdStatus::from(&e))?;
+        if !header.is_null() {
+            header.write(GfdHeader::from(&image.header()));
+        }
+        pixels.write(GfdBuffer::from(image.bytes().collect::<Vec<u8>>()));
+        Ok(())
+    })
+}
+
+/// ## Encodes RGB8 pixels as a greenfield image
+///
+/// `pixels` holds `width * height` colors in row-major order, 3 bytes per pixel, which are
+/// quantized to `(bits_r, bits_g, bits_b)`.
+///
+/// ## Errors
+/// * `GFD_STATUS_NULL_POINTER` if `pixels` or `out` is null
+/// * `GFD_STATUS_INVALID_QUANTIZATION_LEVEL` if the quantization tuple is invalid
+/// * `GFD_STATUS_INVALID_IMAGE_DIMENSION` if `len` is not `width * height * 3`
+///
+/// # Safety
+///
+/// `pixels` must be valid for reads of `len` bytes and `out` must be valid for writes. On
+/// success, `out` must be freed with [`gfd_buffer_free`].
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn gfd_encode(
+    pixels: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    bits_r: u8,
+    bits_g: u8,
+    bits_b: u8,
+    out: *mut GfdBuffer,
+) -> GfdStatus {
+    guard(|| {
+        let bytes = bytes(pixels, len)?;
+        if out.is_null() {
+            return Err(GfdStatus::NullPointer);
+        }
+        if !len.is_multiple_of(3) {
+            return Err(GfdStatus::InvalidImageDimension);
+        }
+
+        let encode = || {
+            let quantization = UniformQuantization::new(bits_r, bits_g, bits_b)?;
+            let colors = bytes
+                .chunks_exact(3)
+                .map(|c| Rgb::new(c[0], c[1], c[2]))
+                .collect::<Vec<Rgb>>();
+
+            Image::new(width as usize, height as usize, quantization, colors)?.serialize()
+        };
+
+        let encoded = encode().map_err(|e| GfdStatus::from(&e))?;
+        out.write(GfdBuffer::from(encoded));
+        Ok(())
+    })
+}
+
+/// ## Frees a buffer returned by the library
+///
+/// The buffer is reset to null, so freeing it twice is harmless. Null is ignored.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a buffer returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn gfd_buffer_free(buffer: *mut GfdBuffer) {
+    let Some(buffer) = buffer.as_mut() else {
+        return;
+    };
+
+    if !buffer.data.is_null() {
+        let data = ptr::slice_from_raw_parts_mut(buffer.data, buffer.len);
+        drop(Box::from_raw(data));
+    }
+
+    buffer.data = ptr::null_mut();
+    buffer.len = 0;
+}
+
+/// ## Returns a static, human-readable description of a status code
+///
+/// The code is taken as an integer, so that any value is safe to pass: unknown codes are described
+/// as such.
+#[no_mangle]
+pub extern "C" fn gfd_status_message(status: c_int) -> *const c_char {
+    let Some(status) = GfdStatus::ALL.into_iter().find(|s| *s as c_int == status) else {
+        return b"Unknown status\0".as_ptr() as *const c_char;
+    };
+
+    let message: &'static [u8] = match status {
+        GfdStatus::Ok => b"Success\0",
+        GfdStatus::NullPointer => b"Null pointer argument\0",
+        GfdStatus::InvalidQuantizationLevel => b"Quantization levels must be between 1 and 8\0",
+        GfdStatus::InvalidDataSize => b"Color data doesn't match the quantization\0",
+        GfdStatus::InvalidImageDimension => b"Color data doesn't match the image dimensions\0",
+        GfdStatus::InvalidHexColor => b"Invalid hex color\0",
+        GfdStatus::Io => b"IO error\0",
+        GfdStatus::Decode => b"Invalid or truncated greenfield image\0",
+        GfdStatus::Image => b"Image conversion error\0",
+        GfdStatus::InvalidPaletteSize => b"Palettes must have between 1 and 65536 colors\0",
+        GfdStatus::InvalidDitheringKernel => b"Invalid dithering kernel\0",
+        GfdStatus::InvalidLookupTable => b"Lookup tables must have 2^bits entries\0",
+        GfdStatus::InvalidCurve => b"Invalid companding curve\0",
+        GfdStatus::InvalidBounds => b"The minimum of a range can't be above its maximum\0",
+        GfdStatus::InvalidLevelCount => b"Components must have between 1 and 256 levels\0",
+        GfdStatus::MismatchedDimensions => b"Compared images must have the same dimensions\0",
+        GfdStatus::ImageTooLarge => b"The image size can't be represented in memory\0",
+        GfdStatus::Internal => b"Internal error\0",
+    };
+
+    message.as_ptr() as *const c_char
+}
@@ -0,0 +1,177 @@
+use super::*;
+use std::ffi::CStr;
+use std::path::PathBuf;
+
+use greenfield::quantization::Palette;
+
+/// Builds an empty buffer, to be filled by the library
+fn buffer() -> GfdBuffer {
+    GfdBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    }
+}
+
+#[test]
+/// Should encode, probe and decode an image
+fn ffi_encode_probe_decode() {
+    let rgb = [255, 0, 0, 0, 0, 255];
+    let mut encoded = buffer();
+    let mut decoded = buffer();
+    let mut header = GfdHeader::default();
+
+    unsafe {
+        let status = gfd_encode(rgb.as_ptr(), rgb.len(), 2, 1, 5, 6, 5, &mut encoded);
+        assert_eq!(status, GfdStatus::Ok);
+
+        let image = Image::new(
+            2,
+            1,
+            UniformQuantization::new(5, 6, 5).unwrap(),
+            vec![Rgb::new(255, 0, 0), Rgb::new(0, 0, 255)],
+        )
+        .unwrap();
+        let serialized = image.clone().serialize().unwrap();
+        assert_eq!(slice::from_raw_parts(encoded.data, encoded.len), serialized);
+
+        let status = gfd_probe(encoded.data, encoded.len, &mut header);
+        assert_eq!(status, GfdStatus::Ok);
+        assert_eq!(
+            header,
+            GfdHeader {
+                width: 2,
+                height: 1,
+                bits_r: 5,
+                bits_g: 6,
+                bits_b: 5
+            }
+        );
+
+        let mut decoded_header = GfdHeader::default();
+        let status = gfd_decode(encoded.data, encoded.len, &mut decoded_header, &mut decoded);
+        assert_eq!(status, GfdStatus::Ok);
+        assert_eq!(decoded_header, header);
+        assert_eq!(
+            slice::from_raw_parts(decoded.data, decoded.len),
+            image.bytes().collect::<Vec<u8>>()
+        );
+
+        // The header is optional when decoding
+        gfd_buffer_free(&mut decoded);
+        let status = gfd_decode(encoded.data, encoded.len, ptr::null_mut(), &mut decoded);
+        assert_eq!(status, GfdStatus::Ok);
+
+        gfd_buffer_free(&mut encoded);
+        gfd_buffer_free(&mut decoded);
+        assert!(encoded.data.is_null());
+        assert!(decoded.data.is_null());
+
+        // Freeing twice or freeing null is harmless
+        gfd_buffer_free(&mut encoded);
+        gfd_buffer_free(ptr::null_mut());
+    }
+}
+
+#[test]
+/// Should map errors to status codes
+fn ffi_errors() {
+    let rgb = [255, 0, 0, 0, 0, 255];
+    let mut out = buffer();
+    let mut header = GfdHeader::default();
+
+    unsafe {
+        // Invalid quantization
+        let status = gfd_encode(rgb.as_ptr(), rgb.len(), 2, 1, 0, 6, 5, &mut out);
+        assert_eq!(status, GfdStatus::InvalidQuantizationLevel);
+
+        // Invalid dimensions
+        let status = gfd_encode(rgb.as_ptr(), rgb.len(), 3, 1, 5, 6, 5, &mut out);
+        assert_eq!(status, GfdStatus::InvalidImageDimension);
+        let status = gfd_encode(rgb.as_ptr(), rgb.len() - 1, 2, 1, 5, 6, 5, &mut out);
+        assert_eq!(status, GfdStatus::InvalidImageDimension);
+
+        // Null pointers
+        let status = gfd_encode(ptr::null(), rgb.len(), 2, 1, 5, 6, 5, &mut out);
+        assert_eq!(status, GfdStatus::NullPointer);
+        let status = gfd_encode(rgb.as_ptr(), rgb.len(), 2, 1, 5, 6, 5, ptr::null_mut());
+        assert_eq!(status, GfdStatus::NullPointer);
+        let status = gfd_probe(rgb.as_ptr(), rgb.len(), ptr::null_mut());
+        assert_eq!(status, GfdStatus::NullPointer);
+
+        // Invalid data: lower colors than expected
+        let serialized = [
+            103, 114, 110, 102, 108, 100, 52, 50, 0, 0, 0, 1, 0, 0, 0, 1, 136, 128,
+        ];
+        let status = gfd_probe(serialized.as_ptr(), serialized.len(), &mut header);
+        assert_eq!(status, GfdStatus::Ok);
+        let status = gfd_decode(serialized.as_ptr(), serialized.len(), &mut header, &mut out);
+        assert_eq!(status, GfdStatus::Decode);
+
+        // Invalid data: invalid magic number
+        let status = gfd_probe(rgb.as_ptr(), rgb.len(), &mut header);
+        assert_eq!(status, GfdStatus::Decode);
+
+        // Nothing was allocated
+        assert!(out.data.is_null());
+    }
+}
+
+#[test]
+/// Should describe every status code
+fn ffi_status_message() {
+    let message = unsafe { CStr::from_ptr(gfd_status_message(GfdStatus::Decode as c_int)) };
+    assert_eq!(
+        message.to_str().unwrap(),
+        "Invalid or truncated greenfield image"
+    );
+
+    // Codes out of the enum
+    for status in [-1, 17, 254, c_int::MAX] {
+        let message = unsafe { CStr::from_ptr(gfd_status_message(status)) };
+        assert_eq!(message.to_str().unwrap(), "Unknown status");
+    }
+}
+
+#[test]
+/// The committed C header should match the one generated from the sources
+fn ffi_header_up_to_date() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let path = crate_dir.join("include").join("greenfield.h");
+
+    let mut generated = Vec::new();
+    cbindgen::generate(&crate_dir)
+        .expect("Unable to generate the C header")
+        .write(&mut generated);
+
+    if std::env::var_os("GREENFIELD_BLESS").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+
+    let committed = std::fs::read(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "include/greenfield.h is outdated, regenerate it with GREENFIELD_BLESS=1 cargo test"
+    );
+}
+
+#[test]
+/// Should probe images quantized to a palette, without a quantization tuple
+fn ffi_probe_palette() {
+    let palette = Palette::new(vec![Rgb::new(255, 0, 0), Rgb::new(0, 0, 255)]).unwrap();
+    let image = Image::new(2, 1, palette, vec![Rgb::new(255, 0, 0); 2]).unwrap();
+    let serialized = image.serialize().unwrap();
+
+    let mut header = GfdHeader::default();
+    let status = unsafe { gfd_probe(serialized.as_ptr(), serialized.len(), &mut header) };
+    assert_eq!(status, GfdStatus::Ok);
+    assert_eq!(
+        header,
+        GfdHeader {
+            width: 2,
+            height: 1,
+            bits_r: 0,
+            bits_g: 0,
+            bits_b: 0,
+        }
+    );
+}
@@ -1,4 +1,8 @@
 pub mod color;
+pub mod dithering;
 pub mod image;
+pub mod metrics;
+pub mod parallel;
 pub mod pixel;
 pub mod quantization;
+pub mod statistics;
@@ -5,6 +5,10 @@
 //! ## Implementations
 //!
 //! - [RGB](https://pt.wikipedia.org/wiki/RGB): Red, Green and Blue.
+//! - Other color models, converted from and to RGB: [`LinearRgb`], [`Hsv`], [`Hsl`], [`Xyz`],
+//!   [`Lab`], [`Oklab`] and [`YCbCr`].
+//! - Perceptual color differences: [`Lab::delta_e76`], [`Lab::delta_e2000`],
+//!   [`Oklab::distance`] and [`Difference`].
 //!
 //! ## Examples
 //!
@@ -61,10 +65,20 @@
 #[cfg(test)]
 mod tests;
 
-use colored::Colorize;
-use std::fmt::{Display, Formatter};
+mod models;
+pub(crate) mod space;
+
+pub use models::{Difference, Hsl, Hsv, Lab, LinearRgb, Oklab, Xyz, YCbCr};
 
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use crate::error::GreenfieldError;
+use alloc::string::ToString;
+#[cfg(feature = "colored")]
+use colored::Colorize;
 use deku::prelude::*;
+#[cfg(feature = "rand")]
 use rand::Rng;
 
 /// ## RGB color struct
@@ -120,6 +134,7 @@ impl Rgb {
     ///     let _color = Rgb::random();
     /// }
     ///  ```
+    #[cfg(feature = "rand")]
     pub fn random() -> Self {
         let (r, g, b) = rand::thread_rng().gen::<(u8, u8, u8)>();
         Self { r, g, b }
@@ -162,10 +177,71 @@ impl Display for Rgb {
     ///     println!("{}", color);
     /// }
     /// ```
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let Self { r, g, b } = *self;
-        let hex_color = format!("#{:02x}{:02x}{:02x}", r, g, b);
-        write!(f, "{}", hex_color.truecolor(r, g, b))
+        let hex_color = alloc::format!("#{:02x}{:02x}{:02x}", r, g, b);
+
+        #[cfg(feature = "colored")]
+        let hex_color = hex_color.truecolor(r, g, b);
+
+        write!(f, "{}", hex_color)
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = GreenfieldError;
+
+    /// ## Parses a color from a hex string.
+    ///
+    /// The string must be in the form `#rrggbb`, the same format used by [`Display`].
+    ///
+    /// ## Errors
+    /// - If the string is not a valid hex color.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    ///
+    /// let color = "#c8960a".parse::<Rgb>()?;
+    /// assert_eq!(color, Rgb::new(200, 150, 10));
+    ///
+    /// assert!("c8960a".parse::<Rgb>().is_err());
+    /// assert!("#c8960".parse::<Rgb>().is_err());
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GreenfieldError::InvalidHexColor(s.to_string());
+
+        let hex = s.strip_prefix('#').ok_or_else(invalid)?;
+        if hex.len() != 6 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+        Ok(Self::new(component(0)?, component(2)?, component(4)?))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Rgb {
+    /// ## Serializes the color as a hex string.
+    ///
+    /// The string is in the form `#rrggbb`, like [`Display`] but without the terminal colors.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Self { r, g, b } = *self;
+        serializer.collect_str(&format_args!("#{:02x}{:02x}{:02x}", r, g, b))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Rgb {
+    /// ## Deserializes the color from a hex string.
+    ///
+    /// See [`Rgb::from_str`] for the accepted format.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = <alloc::borrow::Cow<str>>::deserialize(deserializer)?;
+        hex.parse().map_err(serde::de::Error::custom)
     }
 }
 
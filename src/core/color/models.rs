@@ -0,0 +1,485 @@
+//! Other color models than sRGB, and perceptual color differences.
+//!
+//! Every model converts from and to [`Rgb`] with `f64` components, so converting a color back gives
+//! the same color (the components are rounded and clamped to the RGB cube on the way back):
+//!
+//! - [`LinearRgb`]: sRGB without its transfer function, in linear light (0 to 1).
+//! - [`Hsv`] and [`Hsl`]: hue (in degrees), saturation and value or lightness (0 to 1).
+//! - [`Xyz`]: CIE 1931 XYZ, for the D65 white of sRGB (`Y` from 0 to 1).
+//! - [`Lab`]: CIELAB (`L` from 0 to 100), for the D65 white.
+//! - [`Oklab`]: the perceptually uniform Oklab (`L` from 0 to 1).
+//! - [`YCbCr`]: full range BT.601 YCbCr, as in JPEG (0 to 255).
+//!
+//! Euclidean distances in RGB don't follow the differences the eye sees. [`Lab::delta_e76`],
+//! [`Lab::delta_e2000`] and [`Oklab::distance`] do better, and [`Difference`] picks one of them
+//! to compare colors (e.g. in [`Palette::nearest_by`](crate::quantization::Palette::nearest_by)).
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::color::Difference;
+//! use greenfield::prelude::*;
+//!
+//! let color = Rgb::new(255, 128, 0);
+//! let hsv = color.to_hsv();
+//! assert_eq!((hsv.h.round(), hsv.s, hsv.v), (30.0, 1.0, 1.0));
+//! assert_eq!(hsv.to_rgb(), color);
+//!
+//! let lab = color.to_lab();
+//! assert_eq!(lab.to_rgb(), color);
+//!
+//! let delta = Difference::DeltaE2000.between(&color, &Rgb::new(250, 130, 10));
+//! assert!(delta < 3.0);
+//! ```
+use super::{space, Rgb};
+
+#[cfg(test)]
+mod tests;
+
+/// Rows of the matrix from linear sRGB to XYZ (D65).
+const TO_XYZ: [[f64; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+
+/// Rows of the matrix from XYZ (D65) to linear sRGB.
+const FROM_XYZ: [[f64; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+/// The D65 white of sRGB in XYZ: the sums of the rows of [`TO_XYZ`], so that grays have no
+/// CIELAB chroma.
+const WHITE: [f64; 3] = [
+    TO_XYZ[0][0] + TO_XYZ[0][1] + TO_XYZ[0][2],
+    TO_XYZ[1][0] + TO_XYZ[1][1] + TO_XYZ[1][2],
+    TO_XYZ[2][0] + TO_XYZ[2][1] + TO_XYZ[2][2],
+];
+
+/// CIELAB constants: `(6/29)³` and `(29/3)³`.
+const EPSILON: f64 = 216.0 / 24389.0;
+const KAPPA: f64 = 24389.0 / 27.0;
+
+/// A matrix times a vector.
+fn multiply(matrix: &[[f64; 3]; 3], [x, y, z]: [f64; 3]) -> [f64; 3] {
+    matrix.map(|[a, b, c]| a * x + b * y + c * z)
+}
+
+/// Hue in degrees and the largest and smallest components (0 to 1) of a color, the common part
+/// of HSV and HSL.
+fn hue(color: &Rgb) -> (f64, f64, f64) {
+    let [r, g, b] = color.bytes().map(|c| c as f64 / 255.0);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let chroma = max - min;
+
+    let hue = match chroma {
+        0.0 => 0.0,
+        _ if max == r => 60.0 * ((g - b) / chroma),
+        _ if max == g => 60.0 * ((b - r) / chroma + 2.0),
+        _ => 60.0 * ((r - g) / chroma + 4.0),
+    };
+    (if hue < 0.0 { hue + 360.0 } else { hue }, max, min)
+}
+
+/// The color of a hue in degrees, a chroma and the smallest component (0 to 1), the common part
+/// of HSV and HSL.
+fn from_hue(hue: f64, chroma: f64, min: f64) -> Rgb {
+    let h = (hue % 360.0 + 360.0) % 360.0 / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match h as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let byte = |c: f64| space::byte((c + min) * 255.0);
+    Rgb::new(byte(r), byte(g), byte(b))
+}
+
+impl Rgb {
+    /// ## Converts the color to linear light
+    pub fn to_linear(&self) -> LinearRgb {
+        let [r, g, b] = self.bytes().map(space::to_linear);
+        LinearRgb { r, g, b }
+    }
+
+    /// ## Converts the color to HSV
+    ///
+    /// Grays have a hue of 0.
+    pub fn to_hsv(&self) -> Hsv {
+        let (h, max, min) = hue(self);
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv { h, s, v: max }
+    }
+
+    /// ## Converts the color to HSL
+    ///
+    /// Grays have a hue of 0.
+    pub fn to_hsl(&self) -> Hsl {
+        let (h, max, min) = hue(self);
+        let l = (max + min) / 2.0;
+        let s = match l {
+            0.0 | 1.0 => 0.0,
+            l => (max - min) / (1.0 - (2.0 * l - 1.0).abs()),
+        };
+        Hsl { h, s, l }
+    }
+
+    /// ## Converts the color to CIE XYZ
+    pub fn to_xyz(&self) -> Xyz {
+        self.to_linear().to_xyz()
+    }
+
+    /// ## Converts the color to CIELAB
+    pub fn to_lab(&self) -> Lab {
+        self.to_xyz().to_lab()
+    }
+
+    /// ## Converts the color to Oklab
+    pub fn to_oklab(&self) -> Oklab {
+        let [l, a, b] = space::to_oklab(self);
+        Oklab { l, a, b }
+    }
+
+    /// ## Converts the color to full range YCbCr
+    pub fn to_ycbcr(&self) -> YCbCr {
+        let [y, cb, cr] = space::to_ycbcr(self);
+        YCbCr { y, cb, cr }
+    }
+}
+
+/// ## Linear sRGB color
+///
+/// The components are linear light intensities, from 0 to 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LinearRgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl LinearRgb {
+    /// ## Creates a new linear color
+    pub fn new(r: f64, g: f64, b: f64) -> Self {
+        Self { r, g, b }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    pub fn to_rgb(&self) -> Rgb {
+        Rgb::new(
+            space::from_linear(self.r),
+            space::from_linear(self.g),
+            space::from_linear(self.b),
+        )
+    }
+
+    /// ## Converts the color to CIE XYZ
+    pub fn to_xyz(&self) -> Xyz {
+        let [x, y, z] = multiply(&TO_XYZ, [self.r, self.g, self.b]);
+        Xyz { x, y, z }
+    }
+}
+
+/// ## HSV color
+///
+/// The hue is in degrees, from 0 to 360, and the saturation and value from 0 to 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+impl Hsv {
+    /// ## Creates a new HSV color
+    pub fn new(h: f64, s: f64, v: f64) -> Self {
+        Self { h, s, v }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    ///
+    /// Hues out of 0 to 360 wrap around, and the saturation and value are clamped to 0 to 1.
+    pub fn to_rgb(&self) -> Rgb {
+        let (s, v) = (self.s.clamp(0.0, 1.0), self.v.clamp(0.0, 1.0));
+        let chroma = v * s;
+        from_hue(self.h, chroma, v - chroma)
+    }
+}
+
+/// ## HSL color
+///
+/// The hue is in degrees, from 0 to 360, and the saturation and lightness from 0 to 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+impl Hsl {
+    /// ## Creates a new HSL color
+    pub fn new(h: f64, s: f64, l: f64) -> Self {
+        Self { h, s, l }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    ///
+    /// Hues out of 0 to 360 wrap around, and the saturation and lightness are clamped to 0 to 1.
+    pub fn to_rgb(&self) -> Rgb {
+        let (s, l) = (self.s.clamp(0.0, 1.0), self.l.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_hue(self.h, chroma, l - chroma / 2.0)
+    }
+}
+
+/// ## CIE 1931 XYZ color
+///
+/// Relative to the D65 white of sRGB, whose `Y` is 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Xyz {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Xyz {
+    /// ## Creates a new XYZ color
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    /// ## Converts the color to linear sRGB
+    ///
+    /// Colors out of the sRGB gamut have components out of 0 to 1.
+    pub fn to_linear(&self) -> LinearRgb {
+        let [r, g, b] = multiply(&FROM_XYZ, [self.x, self.y, self.z]);
+        LinearRgb { r, g, b }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    pub fn to_rgb(&self) -> Rgb {
+        self.to_linear().to_rgb()
+    }
+
+    /// ## Converts the color to CIELAB
+    pub fn to_lab(&self) -> Lab {
+        let f = |t: f64| match t > EPSILON {
+            true => libm::cbrt(t),
+            false => (KAPPA * t + 16.0) / 116.0,
+        };
+        let [fx, fy, fz] = [
+            f(self.x / WHITE[0]),
+            f(self.y / WHITE[1]),
+            f(self.z / WHITE[2]),
+        ];
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+/// ## CIELAB color
+///
+/// Relative to the D65 white of sRGB. The lightness goes from 0 to 100.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Lab {
+    /// ## Creates a new CIELAB color
+    pub fn new(l: f64, a: f64, b: f64) -> Self {
+        Self { l, a, b }
+    }
+
+    /// ## Converts the color to CIE XYZ
+    pub fn to_xyz(&self) -> Xyz {
+        let fy = (self.l + 16.0) / 116.0;
+        let (fx, fz) = (fy + self.a / 500.0, fy - self.b / 200.0);
+        let f = |f: f64| match f * f * f > EPSILON {
+            true => f * f * f,
+            false => (116.0 * f - 16.0) / KAPPA,
+        };
+        let y = match self.l > KAPPA * EPSILON {
+            true => fy * fy * fy,
+            false => self.l / KAPPA,
+        };
+
+        Xyz {
+            x: f(fx) * WHITE[0],
+            y: y * WHITE[1],
+            z: f(fz) * WHITE[2],
+        }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    pub fn to_rgb(&self) -> Rgb {
+        self.to_xyz().to_rgb()
+    }
+
+    /// ## CIE 1976 color difference (ΔE*ab)
+    ///
+    /// The Euclidean distance in CIELAB. A difference of about 2.3 is just noticeable.
+    pub fn delta_e76(&self, other: &Self) -> f64 {
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        libm::sqrt(dl * dl + da * da + db * db)
+    }
+
+    /// ## CIEDE2000 color difference (ΔE00)
+    ///
+    /// Corrects the CIE 1976 difference for the perceptual non-uniformities of CIELAB, in
+    /// lightness, chroma and hue, and for blues. With `kL = kC = kH = 1`, as in Sharma et al., 2005.
+    pub fn delta_e2000(&self, other: &Self) -> f64 {
+        let radians = |degrees: f64| degrees * core::f64::consts::PI / 180.0;
+        let pow7 = |x: f64| x * x * x * x * x * x * x;
+        let chroma = |a: f64, b: f64| libm::sqrt(a * a + b * b);
+
+        let c_mean = (chroma(self.a, self.b) + chroma(other.a, other.b)) / 2.0;
+        let g = 0.5 * (1.0 - libm::sqrt(pow7(c_mean) / (pow7(c_mean) + pow7(25.0))));
+
+        // Chroma and hue (in degrees, 0 to 360) with the adjusted a
+        let prime = |lab: &Self| {
+            let a = (1.0 + g) * lab.a;
+            let hue = match (a, lab.b) {
+                (0.0, 0.0) => 0.0,
+                (a, b) => libm::atan2(b, a) * 180.0 / core::f64::consts::PI,
+            };
+            (chroma(a, lab.b), if hue < 0.0 { hue + 360.0 } else { hue })
+        };
+        let ((c1, h1), (c2, h2)) = (prime(self), prime(other));
+
+        let dl = other.l - self.l;
+        let dc = c2 - c1;
+        let dh = match h2 - h1 {
+            _ if c1 * c2 == 0.0 => 0.0,
+            d if d > 180.0 => d - 360.0,
+            d if d < -180.0 => d + 360.0,
+            d => d,
+        };
+        let dh = 2.0 * libm::sqrt(c1 * c2) * libm::sin(radians(dh / 2.0));
+
+        let l_mean = (self.l + other.l) / 2.0;
+        let c_mean = (c1 + c2) / 2.0;
+        let h_mean = match h1 + h2 {
+            sum if c1 * c2 == 0.0 => sum,
+            sum if (h1 - h2).abs() <= 180.0 => sum / 2.0,
+            sum if sum < 360.0 => (sum + 360.0) / 2.0,
+            sum => (sum - 360.0) / 2.0,
+        };
+
+        let t = 1.0 - 0.17 * libm::cos(radians(h_mean - 30.0))
+            + 0.24 * libm::cos(radians(2.0 * h_mean))
+            + 0.32 * libm::cos(radians(3.0 * h_mean + 6.0))
+            - 0.20 * libm::cos(radians(4.0 * h_mean - 63.0));
+        let d_theta = 30.0 * libm::exp(-((h_mean - 275.0) / 25.0) * ((h_mean - 275.0) / 25.0));
+        let rc = 2.0 * libm::sqrt(pow7(c_mean) / (pow7(c_mean) + pow7(25.0)));
+        let l50 = (l_mean - 50.0) * (l_mean - 50.0);
+        let sl = 1.0 + 0.015 * l50 / libm::sqrt(20.0 + l50);
+        let sc = 1.0 + 0.045 * c_mean;
+        let sh = 1.0 + 0.015 * c_mean * t;
+        let rt = -libm::sin(radians(2.0 * d_theta)) * rc;
+
+        let (l, c, h) = (dl / sl, dc / sc, dh / sh);
+        libm::sqrt(l * l + c * c + h * h + rt * c * h)
+    }
+}
+
+/// ## Oklab color
+///
+/// The lightness goes from 0 to 1, and `a` and `b` are within about ±0.4 for sRGB colors.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Oklab {
+    /// ## Creates a new Oklab color
+    pub fn new(l: f64, a: f64, b: f64) -> Self {
+        Self { l, a, b }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    pub fn to_rgb(&self) -> Rgb {
+        space::from_oklab([self.l, self.a, self.b])
+    }
+
+    /// ## Euclidean distance in Oklab
+    ///
+    /// Oklab is perceptually uniform, so it's a cheap perceptual difference. A distance of about
+    /// 0.02 is just noticeable.
+    pub fn distance(&self, other: &Self) -> f64 {
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        libm::sqrt(dl * dl + da * da + db * db)
+    }
+}
+
+/// ## Full range YCbCr color
+///
+/// BT.601, as in JPEG: every component goes from 0 to 255, and grays have `Cb` and `Cr` at 128.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct YCbCr {
+    pub y: f64,
+    pub cb: f64,
+    pub cr: f64,
+}
+
+impl YCbCr {
+    /// ## Creates a new YCbCr color
+    pub fn new(y: f64, cb: f64, cr: f64) -> Self {
+        Self { y, cb, cr }
+    }
+
+    /// ## Converts the color to the nearest sRGB color
+    pub fn to_rgb(&self) -> Rgb {
+        space::from_ycbcr([self.y, self.cb, self.cr])
+    }
+}
+
+/// ## A difference between colors
+///
+/// From the cheapest to the most perceptually accurate one: Euclidean distance in RGB, CIE 1976
+/// (ΔE*ab) and Oklab distances, and CIEDE2000. The values of each one have their own scale.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difference {
+    /// Euclidean distance of the sRGB components.
+    #[default]
+    Rgb,
+    /// See [`Lab::delta_e76`].
+    DeltaE76,
+    /// See [`Lab::delta_e2000`].
+    DeltaE2000,
+    /// See [`Oklab::distance`].
+    Oklab,
+}
+
+impl Difference {
+    /// ## The difference between two colors
+    pub fn between(&self, a: &Rgb, b: &Rgb) -> f64 {
+        match self {
+            Self::Rgb => {
+                let squares = a
+                    .bytes()
+                    .into_iter()
+                    .zip(b.bytes())
+                    .map(|(a, b)| (a.abs_diff(b) as u32).pow(2))
+                    .sum::<u32>();
+                libm::sqrt(squares as f64)
+            }
+            Self::DeltaE76 => a.to_lab().delta_e76(&b.to_lab()),
+            Self::DeltaE2000 => a.to_lab().delta_e2000(&b.to_lab()),
+            Self::Oklab => a.to_oklab().distance(&b.to_oklab()),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::*;
+
+/// A grid over the RGB cube
+fn colors() -> impl Iterator<Item = Rgb> {
+    (0..=255)
+        .step_by(15)
+        .flat_map(|r| (0..=255).step_by(15).map(move |g| (r, g)))
+        .flat_map(|(r, g)| (0..=255).step_by(15).map(move |b| Rgb::new(r, g, b)))
+}
+
+/// Every model should convert back to the same color
+#[test]
+fn models_roundtrip() {
+    for color in colors() {
+        assert_eq!(color.to_linear().to_rgb(), color);
+        assert_eq!(color.to_hsv().to_rgb(), color);
+        assert_eq!(color.to_hsl().to_rgb(), color);
+        assert_eq!(color.to_xyz().to_rgb(), color);
+        assert_eq!(color.to_lab().to_rgb(), color);
+        assert_eq!(color.to_lab().to_xyz().to_rgb(), color);
+        assert_eq!(color.to_oklab().to_rgb(), color);
+        assert_eq!(color.to_ycbcr().to_rgb(), color);
+    }
+}
+
+/// HSV and HSL should match known colors
+#[test]
+fn models_hsv_hsl() {
+    let cases = [
+        (Rgb::new(255, 0, 0), (0.0, 1.0, 1.0), (0.0, 1.0, 0.5)),
+        (Rgb::new(0, 255, 0), (120.0, 1.0, 1.0), (120.0, 1.0, 0.5)),
+        (Rgb::new(0, 0, 255), (240.0, 1.0, 1.0), (240.0, 1.0, 0.5)),
+        (Rgb::new(255, 0, 255), (300.0, 1.0, 1.0), (300.0, 1.0, 0.5)),
+        (Rgb::new(0, 0, 0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+        (Rgb::new(255, 255, 255), (0.0, 0.0, 1.0), (0.0, 0.0, 1.0)),
+        (
+            Rgb::new(51, 102, 153),
+            (210.0, 2.0 / 3.0, 0.6),
+            (210.0, 0.5, 0.4),
+        ),
+    ];
+    for (color, (h, s, v), (hl, sl, l)) in cases {
+        let hsv = color.to_hsv();
+        let hsl = color.to_hsl();
+        for (value, expected) in [
+            (hsv.h, h),
+            (hsv.s, s),
+            (hsv.v, v),
+            (hsl.h, hl),
+            (hsl.s, sl),
+            (hsl.l, l),
+        ] {
+            assert!(
+                (value - expected).abs() < 1e-9,
+                "{color:?} {value} {expected}"
+            );
+        }
+    }
+
+    // Hues wrap around, and the other components are clamped
+    assert_eq!(Hsv::new(-240.0, 1.0, 1.0).to_rgb(), Rgb::new(0, 255, 0));
+    assert_eq!(Hsv::new(480.0, 2.0, 1.0).to_rgb(), Rgb::new(0, 255, 0));
+    assert_eq!(Hsl::new(360.0, 1.0, 0.5).to_rgb(), Rgb::new(255, 0, 0));
+    assert_eq!(Hsl::new(0.0, 0.0, -1.0).to_rgb(), Rgb::new(0, 0, 0));
+}
+
+/// XYZ and CIELAB should match known values, with neutral grays
+#[test]
+fn models_xyz_lab() {
+    let white = Rgb::new(255, 255, 255).to_xyz();
+    assert!((white.y - 1.0).abs() < 1e-6);
+    assert_eq!(Rgb::new(0, 0, 0).to_lab(), Lab::new(0.0, 0.0, 0.0));
+
+    let close = |a: Lab, b: Lab| {
+        (a.l - b.l).abs() < 0.01 && (a.a - b.a).abs() < 0.01 && (a.b - b.b).abs() < 0.01
+    };
+    let red = Rgb::new(255, 0, 0).to_lab();
+    assert!(close(red, Lab::new(53.2408, 80.0925, 67.2032)), "{red:?}");
+    let blue = Rgb::new(0, 0, 255).to_lab();
+    assert!(
+        close(blue, Lab::new(32.2970, 79.1875, -107.8602)),
+        "{blue:?}"
+    );
+
+    for value in (0..=255).step_by(5) {
+        let gray = Rgb::new(value, value, value).to_lab();
+        assert!(gray.a.abs() < 1e-9 && gray.b.abs() < 1e-9, "{gray:?}");
+    }
+}
+
+/// ΔE2000 should match the test data of Sharma et al., 2005
+#[test]
+fn models_delta_e2000() {
+    let pairs = [
+        ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+        ((50.0, 3.1571, -77.2803), (50.0, 0.0, -82.7485), 2.8615),
+        ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+        ((50.0, 2.49, -0.001), (50.0, -2.49, 0.0011), 7.2195),
+        ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+        ((50.0, 2.5, 0.0), (50.0, 3.1736, 0.5854), 1.0),
+        (
+            (60.2574, -34.0099, 36.2677),
+            (60.4626, -34.1751, 39.4387),
+            1.2644,
+        ),
+        (
+            (22.7233, 20.0904, -46.694),
+            (23.0331, 14.973, -42.5619),
+            2.0373,
+        ),
+        ((2.0776, 0.0795, -1.135), (0.9033, -0.0636, -0.5514), 0.9082),
+    ];
+    for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+        let (x, y) = (Lab::new(l1, a1, b1), Lab::new(l2, a2, b2));
+        let delta = x.delta_e2000(&y);
+        assert!((delta - expected).abs() < 1e-4, "{x:?} {y:?} {delta}");
+        assert!((y.delta_e2000(&x) - delta).abs() < 1e-9);
+    }
+    assert_eq!(
+        Lab::new(50.0, 10.0, 10.0).delta_e2000(&Lab::new(50.0, 10.0, 10.0)),
+        0.0
+    );
+}
+
+/// The other differences should be Euclidean distances
+#[test]
+fn models_differences() {
+    let (a, b) = (Rgb::new(10, 20, 30), Rgb::new(13, 24, 30));
+    assert_eq!(Difference::Rgb.between(&a, &b), 5.0);
+    assert_eq!(
+        Lab::new(50.0, 0.0, 0.0).delta_e76(&Lab::new(53.0, 4.0, 0.0)),
+        5.0
+    );
+    assert_eq!(
+        Oklab::new(0.5, 0.0, 0.0).distance(&Oklab::new(0.5, 0.3, 0.4)),
+        0.5
+    );
+    assert_eq!(
+        Difference::DeltaE76.between(&a, &b),
+        a.to_lab().delta_e76(&b.to_lab())
+    );
+    assert_eq!(
+        Difference::Oklab.between(&a, &b),
+        a.to_oklab().distance(&b.to_oklab())
+    );
+
+    // Equal steps in RGB aren't equal steps for the eye: dark blues are closer than dark greens
+    for difference in [
+        Difference::DeltaE76,
+        Difference::DeltaE2000,
+        Difference::Oklab,
+    ] {
+        let blues = difference.between(&Rgb::new(0, 0, 100), &Rgb::new(0, 0, 130));
+        let greens = difference.between(&Rgb::new(0, 100, 0), &Rgb::new(0, 130, 0));
+        assert!(blues < greens, "{difference:?} {blues} {greens}");
+    }
+}
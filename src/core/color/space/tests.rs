@@ -0,0 +1,89 @@
+use super::*;
+
+/// A grid over the RGB cube
+fn colors() -> impl Iterator<Item = Rgb> {
+    (0..=255)
+        .step_by(15)
+        .flat_map(|r| (0..=255).step_by(15).map(move |g| (r, g)))
+        .flat_map(|(r, g)| (0..=255).step_by(15).map(move |b| Rgb::new(r, g, b)))
+}
+
+/// The sRGB transfer function should match its definition, and be inverted exactly
+#[test]
+fn space_linear() {
+    for value in 0..=255u8 {
+        let c = value as f64 / 255.0;
+        let expected = match c {
+            c if c <= 0.04045 => c / 12.92,
+            c => ((c + 0.055) / 1.055).powf(2.4),
+        };
+        assert!((to_linear(value) - expected).abs() < 1e-8);
+        assert_eq!(from_linear(to_linear(value)), value);
+    }
+
+    assert_eq!(from_linear(-1.0), 0);
+    assert_eq!(from_linear(2.0), 255);
+    assert_eq!(from_linear(0.5), 188);
+}
+
+/// YCbCr should match the JPEG conversion
+#[test]
+fn space_ycbcr() {
+    assert_eq!(
+        to_ycbcr(&Rgb::new(255, 255, 255)).map(byte),
+        [255, 128, 128]
+    );
+    assert_eq!(to_ycbcr(&Rgb::new(0, 0, 0)).map(byte), [0, 128, 128]);
+    assert_eq!(to_ycbcr(&Rgb::new(255, 0, 0)).map(byte), [76, 85, 255]);
+    assert_eq!(to_ycbcr(&Rgb::new(0, 0, 255)).map(byte), [29, 255, 107]);
+
+    for color in colors() {
+        assert_eq!(from_ycbcr(to_ycbcr(&color)), color);
+    }
+}
+
+/// Oklab should match the reference values
+#[test]
+fn space_oklab() {
+    let close = |a: [f64; 3], b: [f64; 3]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4);
+
+    assert!(close(to_oklab(&Rgb::new(255, 255, 255)), [1.0, 0.0, 0.0]));
+    assert!(close(to_oklab(&Rgb::new(0, 0, 0)), [0.0, 0.0, 0.0]));
+    assert!(close(
+        to_oklab(&Rgb::new(255, 0, 0)),
+        [0.627955, 0.224863, 0.125846]
+    ));
+    assert!(close(
+        to_oklab(&Rgb::new(0, 0, 255)),
+        [0.452014, -0.032457, -0.311528]
+    ));
+
+    for color in colors() {
+        assert_eq!(from_oklab(to_oklab(&color)), color);
+    }
+}
+
+/// Oklab bytes should keep neutral colors at 128, and fit the gamut without clamping
+#[test]
+fn space_oklab_bytes() {
+    assert_eq!(to_oklab_bytes(&Rgb::new(255, 255, 255)), [255, 128, 128]);
+    for value in 0..=255 {
+        let [_, a, b] = to_oklab_bytes(&Rgb::new(value, value, value));
+        assert_eq!((a, b), (128, 128));
+    }
+
+    for color in colors() {
+        let [_, a, b] = to_oklab(&color);
+        for c in [128.0 + a * OKLAB_SCALE, 128.0 + b * OKLAB_SCALE] {
+            assert!((0.0..=255.0).contains(&c), "{color:?}");
+        }
+
+        // Bytes are precise enough to get back close to the color, in linear light: near black,
+        // sRGB bytes are much further apart
+        let back = from_oklab_bytes(to_oklab_bytes(&color));
+        for (a, b) in color.bytes().into_iter().zip(back.bytes()) {
+            let error = (to_linear(a) - to_linear(b)).abs();
+            assert!(error < 0.02, "{color:?} {back:?}");
+        }
+    }
+}
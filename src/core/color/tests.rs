@@ -22,12 +22,14 @@ fn color_rgb_default() {
     assert_eq!(b, 0);
 }
 
+#[cfg(feature = "rand")]
 #[test]
 /// Should create a new random RGB color
 fn color_rgb_random() {
     let _color = Rgb::random();
 }
 
+#[cfg(feature = "rand")]
 #[test]
 /// Should Display a RGB color
 fn color_rgb_display() {
@@ -45,3 +47,36 @@ fn color_rgb_bytes() {
     assert_eq!(g, 150);
     assert_eq!(b, 10);
 }
+
+#[test]
+/// Should parse a RGB color from a hex string
+fn color_rgb_from_str() {
+    let color = "#c8960a".parse::<Rgb>().unwrap();
+    assert_eq!(color, Rgb::new(200, 150, 10));
+
+    let color = "#FFFFFF".parse::<Rgb>().unwrap();
+    assert_eq!(color, Rgb::new(255, 255, 255));
+
+    assert!("".parse::<Rgb>().is_err());
+    assert!("c8960a".parse::<Rgb>().is_err());
+    assert!("#c8960".parse::<Rgb>().is_err());
+    assert!("#c8960ab".parse::<Rgb>().is_err());
+    assert!("#c8960g".parse::<Rgb>().is_err());
+    assert!("#+8960a".parse::<Rgb>().is_err());
+    assert!("#c896é".parse::<Rgb>().is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+/// Should serialize and deserialize a RGB color as a hex string
+fn color_rgb_serde() {
+    let color = Rgb::new(200, 150, 10);
+    let serialized = serde_json::to_string(&color).unwrap();
+    assert_eq!(serialized, "\"#c8960a\"");
+
+    let deserialized = serde_json::from_str::<Rgb>(&serialized).unwrap();
+    assert_eq!(deserialized, color);
+
+    assert!(serde_json::from_str::<Rgb>("\"#c8960\"").is_err());
+    assert!(serde_json::from_str::<Rgb>("[200, 150, 10]").is_err());
+}
@@ -0,0 +1,477 @@
+//! ## Dithering of quantized images
+//!
+//! Quantizing to a few colors turns smooth gradients into bands of flat color. Dithering trades
+//! that banding for noise, which the eye averages back into the original colors: the colors of a
+//! region are spread over several quantized colors, so on average they match.
+//!
+//! Error diffusion dithering quantizes the pixels in order, and spreads the error of each one (the
+//! difference between its color and its reconstruction, the color that will be decoded) over the
+//! neighbors that weren't quantized yet, so they make up for it. The rows are scanned in a
+//! serpentine order (left to right, then right to left), which avoids the diagonal artifacts of
+//! always diffusing the error in the same direction.
+//!
+//! The error diffusion kernels decide which neighbors get the error, and how much of it:
+//!
+//! - [`Dithering::FloydSteinberg`]: Floyd–Steinberg, over the 4 next neighbors.
+//! - [`Dithering::Atkinson`]: Atkinson, which drops a quarter of the error for more contrast. The
+//!   look of retro 1 bit images.
+//! - [`Dithering::JarvisJudiceNinke`] and [`Dithering::Stucki`]: over 12 neighbors, smoother but
+//!   slower.
+//! - [`Dithering::Sierra`], [`Dithering::TwoRowSierra`] and [`Dithering::SierraLite`]: the Sierra
+//!   family, from 10 neighbors down to 3.
+//! - [`Dithering::ErrorDiffusion`]: a user-defined [`Kernel`], given as a weight matrix.
+//!
+//! Ordered dithering offsets each pixel by a threshold that only depends on its position, taken
+//! from a small map tiled over the image. As pixels don't depend on each other, they're dithered
+//! in parallel (with the `rayon` feature), and the noise of a region doesn't change between
+//! frames of an animation unless the region does (error diffusion makes noise "crawl"):
+//!
+//! - [`Dithering::Bayer2`], [`Dithering::Bayer4`], [`Dithering::Bayer8`] and
+//!   [`Dithering::Bayer16`]: Bayer matrices, with their characteristic crosshatch pattern.
+//! - [`Dithering::BlueNoise`]: A 64x64 blue noise texture, whose noise has no visible pattern.
+//!
+//! Dithering works with any [`Quantizer`], as it only needs to know the reconstruction of each
+//! color (and for ordered dithering, how far apart the reconstructions are:
+//! [`Quantizer::spacing`]).
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::dithering::Dithering;
+//! use greenfield::prelude::*;
+//!
+//! // A flat gray between the two levels of a 1 bit quantization
+//! let gray = vec![Rgb::new(128, 128, 128); 4];
+//! let quantization = UniformQuantization::new(1, 1, 1)?;
+//!
+//! // Without dithering, every pixel is quantized to the same color
+//! let image = Image::new(2, 2, quantization.clone(), gray.clone())?;
+//! assert!(image.colors().all(|c| c == &Rgb::new(192, 192, 192)));
+//!
+//! // With it, the pixels alternate between both levels
+//! let image = Image::new_dithered(2, 2, quantization, gray, Dithering::FloydSteinberg)?;
+//! assert_eq!(
+//!     image.colors().cloned().collect::<Vec<Rgb>>(),
+//!     vec![
+//!         Rgb::new(192, 192, 192),
+//!         Rgb::new(64, 64, 64),
+//!         Rgb::new(64, 64, 64),
+//!         Rgb::new(192, 192, 192),
+//!     ]
+//! );
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::color::Rgb;
+use crate::error::{GreenfieldError, GreenfieldResult};
+use crate::parallel;
+use crate::quantization::Quantizer;
+
+#[cfg(test)]
+mod tests;
+
+/// ## Dithering algorithm
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Dithering {
+    /// No dithering: each color is quantized on its own.
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion ([`Kernel::FLOYD_STEINBERG`]).
+    FloydSteinberg,
+    /// Atkinson error diffusion ([`Kernel::ATKINSON`]).
+    Atkinson,
+    /// Jarvis–Judice–Ninke error diffusion ([`Kernel::JARVIS_JUDICE_NINKE`]).
+    JarvisJudiceNinke,
+    /// Stucki error diffusion ([`Kernel::STUCKI`]).
+    Stucki,
+    /// Sierra error diffusion ([`Kernel::SIERRA`]).
+    Sierra,
+    /// Two-row Sierra error diffusion ([`Kernel::TWO_ROW_SIERRA`]).
+    TwoRowSierra,
+    /// Sierra Lite error diffusion ([`Kernel::SIERRA_LITE`]).
+    SierraLite,
+    /// Error diffusion with a user-defined kernel.
+    ErrorDiffusion(Kernel),
+    /// Ordered dithering with a 2x2 Bayer matrix.
+    Bayer2,
+    /// Ordered dithering with a 4x4 Bayer matrix.
+    Bayer4,
+    /// Ordered dithering with a 8x8 Bayer matrix.
+    Bayer8,
+    /// Ordered dithering with a 16x16 Bayer matrix.
+    Bayer16,
+    /// Ordered dithering with a 64x64 blue noise texture.
+    BlueNoise,
+}
+
+/// Side of the blue noise texture.
+const BLUE_NOISE_SIZE: usize = 64;
+
+/// The blue noise texture: the rank of each pixel (as big endian `u16`s), from the
+/// void-and-cluster method.
+const BLUE_NOISE: &[u8; BLUE_NOISE_SIZE * BLUE_NOISE_SIZE * 2] =
+    include_bytes!("dithering/blue_noise.bin");
+
+/// ## Error diffusion kernel
+///
+/// The neighbors that get a part of the error of each pixel, and their weights: a neighbor gets
+/// `error * weight / divisor`. The kernels are given for a left to right scan, and are mirrored
+/// on the rows scanned right to left.
+///
+/// Kernels are usually written as a matrix whose first row holds the current pixel, like
+/// Floyd–Steinberg's:
+///
+/// ```text
+///     *  7
+///  3  5  1    (/ 16)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kernel {
+    /// `(dx, dy, weight)` offsets from the current pixel
+    weights: Cow<'static, [(isize, usize, i32)]>,
+    divisor: i32,
+}
+
+impl Kernel {
+    /// Floyd–Steinberg: the classic 4 neighbors kernel.
+    pub const FLOYD_STEINBERG: Self =
+        Self::from_static(&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16);
+
+    /// Atkinson: only diffuses 3/4 of the error, so it keeps more contrast. Suits 1 bit images.
+    pub const ATKINSON: Self = Self::from_static(
+        &[
+            (1, 0, 1),
+            (2, 0, 1),
+            (-1, 1, 1),
+            (0, 1, 1),
+            (1, 1, 1),
+            (0, 2, 1),
+        ],
+        8,
+    );
+
+    /// Jarvis–Judice–Ninke: spreads the error over 12 neighbors, for smoother results.
+    #[rustfmt::skip]
+    pub const JARVIS_JUDICE_NINKE: Self = Self::from_static(
+        &[
+            (1, 0, 7), (2, 0, 5),
+            (-2, 1, 3), (-1, 1, 5), (0, 1, 7), (1, 1, 5), (2, 1, 3),
+            (-2, 2, 1), (-1, 2, 3), (0, 2, 5), (1, 2, 3), (2, 2, 1),
+        ],
+        48,
+    );
+
+    /// Stucki: Jarvis–Judice–Ninke's neighbors, with sharper weights.
+    #[rustfmt::skip]
+    pub const STUCKI: Self = Self::from_static(
+        &[
+            (1, 0, 8), (2, 0, 4),
+            (-2, 1, 2), (-1, 1, 4), (0, 1, 8), (1, 1, 4), (2, 1, 2),
+            (-2, 2, 1), (-1, 2, 2), (0, 2, 4), (1, 2, 2), (2, 2, 1),
+        ],
+        42,
+    );
+
+    /// Sierra (or Sierra-3): three rows, 10 neighbors.
+    #[rustfmt::skip]
+    pub const SIERRA: Self = Self::from_static(
+        &[
+            (1, 0, 5), (2, 0, 3),
+            (-2, 1, 2), (-1, 1, 4), (0, 1, 5), (1, 1, 4), (2, 1, 2),
+            (-1, 2, 2), (0, 2, 3), (1, 2, 2),
+        ],
+        32,
+    );
+
+    /// Two-row Sierra: Sierra without its last row.
+    #[rustfmt::skip]
+    pub const TWO_ROW_SIERRA: Self = Self::from_static(
+        &[
+            (1, 0, 4), (2, 0, 3),
+            (-2, 1, 1), (-1, 1, 2), (0, 1, 3), (1, 1, 2), (2, 1, 1),
+        ],
+        16,
+    );
+
+    /// Sierra Lite: the smallest kernel, as fast as it gets.
+    pub const SIERRA_LITE: Self = Self::from_static(&[(1, 0, 2), (-1, 1, 1), (0, 1, 1)], 4);
+
+    const fn from_static(weights: &'static [(isize, usize, i32)], divisor: i32) -> Self {
+        Self {
+            weights: Cow::Borrowed(weights),
+            divisor,
+        }
+    }
+
+    /// ## Creates a kernel from a weight matrix
+    ///
+    /// ## Arguments
+    /// * `matrix` - The rows of weights. The first row is the one of the current pixel
+    /// * `origin` - The column of the current pixel
+    /// * `divisor` - What the weighted errors are divided by, usually the sum of the weights
+    ///
+    /// ## Errors
+    /// - If the rows have different lengths, or `origin` is out of them.
+    /// - If the current pixel, or the ones before it on its row, have weights: they are already
+    ///   quantized.
+    /// - If all the weights are 0, or the divisor is.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::dithering::Kernel;
+    ///
+    /// let kernel = Kernel::new(&[&[0, 0, 7], &[3, 5, 1]], 1, 16)?;
+    /// assert_eq!(kernel, Kernel::FLOYD_STEINBERG);
+    /// # Ok::<(), greenfield::GreenfieldError>(())
+    /// ```
+    pub fn new(matrix: &[&[u16]], origin: usize, divisor: u16) -> GreenfieldResult<Self> {
+        let invalid = |reason: &str| Err(GreenfieldError::InvalidDitheringKernel(reason.into()));
+
+        let width = matrix.first().map_or(0, |row| row.len());
+        if origin >= width {
+            return invalid(&format!("origin {origin} out of the {width} columns"));
+        }
+        if matrix.iter().any(|row| row.len() != width) {
+            return invalid("rows have different lengths");
+        }
+        if matrix[0][..=origin].iter().any(|&w| w != 0) {
+            return invalid("weights on or before the current pixel");
+        }
+        if divisor == 0 {
+            return invalid("zero divisor");
+        }
+
+        let weights = matrix
+            .iter()
+            .enumerate()
+            .flat_map(|(dy, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, &w)| w != 0)
+                    .map(move |(x, &w)| (x as isize - origin as isize, dy, w as i32))
+            })
+            .collect::<Vec<(isize, usize, i32)>>();
+        if weights.is_empty() {
+            return invalid("all weights are zero");
+        }
+
+        Ok(Self {
+            weights: Cow::Owned(weights),
+            divisor: divisor as i32,
+        })
+    }
+}
+
+impl Dithering {
+    /// ## The error diffusion kernel of the algorithm, if it's one
+    pub fn kernel(&self) -> Option<Kernel> {
+        match self {
+            Self::None
+            | Self::Bayer2
+            | Self::Bayer4
+            | Self::Bayer8
+            | Self::Bayer16
+            | Self::BlueNoise => None,
+            Self::FloydSteinberg => Some(Kernel::FLOYD_STEINBERG),
+            Self::Atkinson => Some(Kernel::ATKINSON),
+            Self::JarvisJudiceNinke => Some(Kernel::JARVIS_JUDICE_NINKE),
+            Self::Stucki => Some(Kernel::STUCKI),
+            Self::Sierra => Some(Kernel::SIERRA),
+            Self::TwoRowSierra => Some(Kernel::TWO_ROW_SIERRA),
+            Self::SierraLite => Some(Kernel::SIERRA_LITE),
+            Self::ErrorDiffusion(kernel) => Some(kernel.clone()),
+        }
+    }
+
+    /// ## Quantizes and reconstructs the colors of an image
+    ///
+    /// Works with any quantizer: uniform quantizations and palettes alike.
+    /// Without dithering, the colors are reconstructed by chunks in parallel (see
+    /// [`crate::parallel`]).
+    ///
+    /// ## Arguments
+    /// * `width` - The width of the image, so `colors` has `height` rows of `width` colors
+    /// * `quantizer` - The quantizer whose reconstruction is dithered
+    /// * `colors` - The colors of the image, in row-major order
+    ///
+    /// ## Returns
+    /// The reconstructed colors, which are all reconstructions of `quantizer`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::dithering::Dithering;
+    /// use greenfield::prelude::*;
+    ///
+    /// let quantization = UniformQuantization::new(1, 1, 1)?;
+    /// let colors = vec![Rgb::new(128, 0, 0); 2];
+    ///
+    /// assert_eq!(
+    ///     Dithering::FloydSteinberg.apply(2, &quantization, &colors),
+    ///     vec![Rgb::new(192, 64, 64), Rgb::new(64, 64, 64)]
+    /// );
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn apply(
+        &self,
+        width: usize,
+        quantizer: &(impl Quantizer + Sync),
+        colors: &[Rgb],
+    ) -> Vec<Rgb> {
+        if let Some((map, size)) = self.threshold_map() {
+            return ordered(&map, size, width, quantizer, colors);
+        }
+
+        match self.kernel() {
+            None => parallel::map_chunks(colors.len(), |range| {
+                colors[range]
+                    .iter()
+                    .map(|c| quantizer.reconstruct(c))
+                    .collect::<Vec<_>>()
+            })
+            .concat(),
+            Some(kernel) => diffuse(&kernel, width, quantizer, colors),
+        }
+    }
+
+    /// The threshold map of ordered dithering, as the rank of each cell, and its side.
+    fn threshold_map(&self) -> Option<(Vec<u16>, usize)> {
+        match self {
+            Self::Bayer2 => Some((bayer(2), 2)),
+            Self::Bayer4 => Some((bayer(4), 4)),
+            Self::Bayer8 => Some((bayer(8), 8)),
+            Self::Bayer16 => Some((bayer(16), 16)),
+            Self::BlueNoise => Some((
+                BLUE_NOISE
+                    .chunks(2)
+                    .map(|rank| u16::from_be_bytes([rank[0], rank[1]]))
+                    .collect(),
+                BLUE_NOISE_SIZE,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Bayer matrix of side `size` (a power of 2): each 2x2 block of the next size is `4m`, `4m + 2`,
+/// `4m + 3` and `4m + 1`, clockwise from the top left.
+fn bayer(size: usize) -> Vec<u16> {
+    let mut matrix = alloc::vec![0u16];
+    let mut side = 1;
+    while side < size {
+        let next = side * 2;
+        matrix = (0..next * next)
+            .map(|i| {
+                let (x, y) = (i % next, i / next);
+                let m = 4 * matrix[(y % side) * side + x % side];
+                match (x / side, y / side) {
+                    (0, 0) => m,
+                    (1, 0) => m + 2,
+                    (0, _) => m + 3,
+                    _ => m + 1,
+                }
+            })
+            .collect();
+        side = next;
+    }
+
+    matrix
+}
+
+/// Ordered dithering: each pixel is offset by the threshold of its cell in the map, tiled over
+/// the image, from -1/2 to 1/2 of the spacing of the reconstructions.
+fn ordered(
+    map: &[u16],
+    size: usize,
+    width: usize,
+    quantizer: &(impl Quantizer + Sync),
+    colors: &[Rgb],
+) -> Vec<Rgb> {
+    if width == 0 {
+        return Vec::new();
+    }
+
+    let cells = map.len() as i64;
+    let spacing = quantizer.spacing().map(|s| s as i64);
+    let dither = |(i, color): (usize, &Rgb)| {
+        let rank = map[(i / width % size) * size + i % width % size] as i64;
+        let [r, g, b] = [0, 1, 2].map(|c| {
+            let offset = div_round(spacing[c] * (2 * rank + 1 - cells), 2 * cells);
+            (color.bytes()[c] as i64 + offset).clamp(0, 255) as u8
+        });
+        quantizer.reconstruct(&Rgb::new(r, g, b))
+    };
+
+    parallel::map_chunks(colors.len(), |chunk| {
+        colors[chunk.clone()]
+            .iter()
+            .enumerate()
+            .map(|(i, color)| dither((chunk.start + i, color)))
+            .collect::<Vec<Rgb>>()
+    })
+    .concat()
+}
+
+/// Error diffusion with a serpentine scan.
+fn diffuse(kernel: &Kernel, width: usize, quantizer: &impl Quantizer, colors: &[Rgb]) -> Vec<Rgb> {
+    if width == 0 {
+        return Vec::new();
+    }
+    let height = colors.len() / width;
+
+    // The weighted errors diffused to each pixel, per component. Wide enough for the largest
+    // weights (an error of 255 times 65535) of every neighbor of a pixel.
+    let mut errors = alloc::vec![[0i64; 3]; colors.len()];
+    let mut output = colors.to_vec();
+
+    for y in 0..height {
+        // Odd rows are scanned right to left, with the kernel mirrored
+        let reverse = y % 2 == 1;
+        for i in 0..width {
+            let x = if reverse { width - 1 - i } else { i };
+            let index = y * width + x;
+
+            let mut adjusted = [0u8; 3];
+            for (component, (value, error)) in
+                colors[index].bytes().iter().zip(errors[index]).enumerate()
+            {
+                let diffused = div_round(error, kernel.divisor as i64);
+                adjusted[component] = (*value as i64 + diffused).clamp(0, 255) as u8;
+            }
+
+            let adjusted = Rgb::new(adjusted[0], adjusted[1], adjusted[2]);
+            let reconstructed = quantizer.reconstruct(&adjusted);
+            let error =
+                [0, 1, 2].map(|c| adjusted.bytes()[c] as i64 - reconstructed.bytes()[c] as i64);
+
+            for &(dx, dy, weight) in kernel.weights.iter() {
+                let dx = if reverse { -dx } else { dx };
+                let (nx, ny) = (x as isize + dx, y + dy);
+                if nx < 0 || nx >= width as isize || ny >= height {
+                    continue;
+                }
+
+                let neighbor = &mut errors[ny * width + nx as usize];
+                for (n, e) in neighbor.iter_mut().zip(error) {
+                    *n += e * weight as i64;
+                }
+            }
+
+            output[index] = reconstructed;
+        }
+    }
+
+    output
+}
+
+/// Division rounded to the nearest integer, with halves away from zero.
+fn div_round(a: i64, b: i64) -> i64 {
+    match a < 0 {
+        true => (a - b / 2) / b,
+        false => (a + b / 2) / b,
+    }
+}
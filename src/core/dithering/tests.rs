@@ -0,0 +1,529 @@
+use super::*;
+use crate::error::{GreenfieldError, GreenfieldResult};
+use crate::image::Image;
+use crate::quantization::{Palette, UniformQuantization};
+
+/// A horizontal gray gradient, from black to white
+fn gradient(width: usize, height: usize) -> Vec<Rgb> {
+    (0..width * height)
+        .map(|i| {
+            let v = (i % width * 255 / (width - 1)) as u8;
+            Rgb::new(v, v, v)
+        })
+        .collect()
+}
+
+/// Mean of each component
+fn mean(colors: &[Rgb]) -> [f64; 3] {
+    let mut sum = [0f64; 3];
+    for color in colors {
+        for (s, c) in sum.iter_mut().zip(color.bytes()) {
+            *s += c as f64;
+        }
+    }
+    sum.map(|s| s / colors.len() as f64)
+}
+
+/// Without dithering, colors should be reconstructed on their own
+#[test]
+fn dithering_none() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?;
+    let colors = gradient(16, 4);
+
+    let expected = colors
+        .iter()
+        .map(|c| quantization.get_dequantized_color(&quantization.get_quantized_color(c)))
+        .collect::<Vec<Rgb>>();
+    assert_eq!(Dithering::None.apply(16, &quantization, &colors), expected);
+
+    Ok(())
+}
+
+/// Should diffuse the error to the neighbors, against the reconstruction
+#[test]
+fn dithering_floyd_steinberg() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(1, 1, 1)?;
+    let colors = vec![Rgb::new(128, 128, 128); 4];
+
+    // 128 is reconstructed as 192, so the error is -64:
+    // - The right pixel gets -64 * 7 / 16 = -28, so 100 -> 64 (error 36)
+    // - The bottom left one gets (-64 * 5 + 36 * 3) / 16 = -13, so 115 -> 64
+    // - The bottom right one gets (-64 + 36 * 5) / 16 = 7, so 135 -> 192
+    assert_eq!(
+        Dithering::FloydSteinberg.apply(2, &quantization, &colors),
+        vec![
+            Rgb::new(192, 192, 192),
+            Rgb::new(64, 64, 64),
+            Rgb::new(64, 64, 64),
+            Rgb::new(192, 192, 192),
+        ]
+    );
+
+    Ok(())
+}
+
+/// The odd rows should be scanned right to left
+#[test]
+fn dithering_serpentine() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(1, 1, 1)?;
+    let gray = |v: u8| Rgb::new(v, v, v);
+    // The first row and the first pixel of the second one are reconstructed exactly
+    let colors = vec![gray(64), gray(64), gray(64), gray(64), gray(100), gray(255)];
+
+    // The second row starts at the right: 255 -> 192 diffuses 63 * 7 / 16 = 28 to the left, so
+    // 100 + 28 -> 192. Scanning left to right, 100 would be 64.
+    assert_eq!(
+        Dithering::FloydSteinberg.apply(3, &quantization, &colors),
+        vec![gray(64), gray(64), gray(64), gray(64), gray(192), gray(192)]
+    );
+
+    Ok(())
+}
+
+/// Dithering should preserve the mean color of flat regions, unlike plain quantization
+#[test]
+fn dithering_mean() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?;
+    let colors = vec![Rgb::new(80, 140, 200); 32 * 32];
+
+    let plain = mean(&Dithering::None.apply(32, &quantization, &colors));
+    let dithered = mean(&Dithering::FloydSteinberg.apply(32, &quantization, &colors));
+
+    for ((p, d), c) in plain.iter().zip(dithered).zip([80.0, 140.0, 200.0]) {
+        assert!((d - c).abs() < 2.0, "{d} != {c}");
+        assert!((d - c).abs() < (p - c).abs());
+    }
+
+    Ok(())
+}
+
+/// Should work with palettes, always producing palette colors
+#[test]
+fn dithering_palette() -> GreenfieldResult<()> {
+    let palette = Palette::new(vec![Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)])?;
+    let colors = gradient(64, 8);
+
+    let dithered = Dithering::FloydSteinberg.apply(64, &palette, &colors);
+    assert!(dithered.iter().all(|c| palette.colors().contains(c)));
+
+    // Half of the gradient is bright, so about half the pixels are white
+    let white = dithered.iter().filter(|c| c.r == 255).count();
+    assert!((white as isize - 256).abs() < 16, "{white} white pixels");
+
+    Ok(())
+}
+
+/// Should dither images through `Image::new_dithered`
+#[test]
+fn dithering_image() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?;
+    let colors = gradient(16, 4);
+
+    let image = Image::new_dithered(
+        16,
+        4,
+        quantization.clone(),
+        colors.clone(),
+        Dithering::FloydSteinberg,
+    )?;
+    let expected = Dithering::FloydSteinberg.apply(16, &quantization, &colors);
+    assert!(image.colors().eq(expected.iter()));
+
+    // The colors are reconstructions, so they survive a round trip
+    let serialized = image.clone().serialize()?;
+    assert_eq!(Image::deserialize(&serialized)?, image);
+
+    // Without dithering, it's a plain image
+    let image = Image::new_dithered(16, 4, quantization.clone(), colors.clone(), Dithering::None)?;
+    assert_eq!(image, Image::new(16, 4, quantization.clone(), colors)?);
+
+    // The dimensions are still checked
+    assert!(
+        Image::new_dithered(3, 3, quantization.clone(), gradient(4, 2), Dithering::None).is_err()
+    );
+    assert!(Image::new_dithered(0, 0, quantization, vec![], Dithering::FloydSteinberg).is_ok());
+
+    Ok(())
+}
+
+/// Should round halves away from zero
+#[test]
+fn dithering_div_round() {
+    assert_eq!(div_round(8, 16), 1);
+    assert_eq!(div_round(7, 16), 0);
+    assert_eq!(div_round(-8, 16), -1);
+    assert_eq!(div_round(-7, 16), 0);
+    assert_eq!(div_round(-40, 16), -3);
+}
+
+/// The weights of the kernels, as `(weights, divisor)` sums
+#[test]
+fn dithering_kernels() {
+    let sum = |kernel: Kernel| {
+        let weights = kernel.weights.iter().map(|(_, _, w)| w).sum::<i32>();
+        (weights, kernel.divisor)
+    };
+
+    assert_eq!(sum(Kernel::FLOYD_STEINBERG), (16, 16));
+    // Atkinson drops a quarter of the error
+    assert_eq!(sum(Kernel::ATKINSON), (6, 8));
+    assert_eq!(sum(Kernel::JARVIS_JUDICE_NINKE), (48, 48));
+    assert_eq!(sum(Kernel::STUCKI), (42, 42));
+    assert_eq!(sum(Kernel::SIERRA), (32, 32));
+    assert_eq!(sum(Kernel::TWO_ROW_SIERRA), (16, 16));
+    assert_eq!(sum(Kernel::SIERRA_LITE), (4, 4));
+
+    // No kernel diffuses to the pixels already quantized
+    for kernel in [
+        Kernel::FLOYD_STEINBERG,
+        Kernel::ATKINSON,
+        Kernel::JARVIS_JUDICE_NINKE,
+        Kernel::STUCKI,
+        Kernel::SIERRA,
+        Kernel::TWO_ROW_SIERRA,
+        Kernel::SIERRA_LITE,
+    ] {
+        assert!(kernel.weights.iter().all(|&(dx, dy, _)| dy > 0 || dx > 0));
+    }
+}
+
+/// Should build kernels from weight matrices
+#[test]
+fn dithering_kernel_new() -> GreenfieldResult<()> {
+    let kernel = Kernel::new(&[&[0, 0, 1, 1], &[1, 1, 1, 0], &[0, 1, 0, 0]], 1, 8)?;
+    assert_eq!(kernel, Kernel::ATKINSON);
+
+    let kernel = Kernel::new(
+        &[&[0, 0, 0, 7, 5], &[3, 5, 7, 5, 3], &[1, 3, 5, 3, 1]],
+        2,
+        48,
+    )?;
+    assert_eq!(kernel, Kernel::JARVIS_JUDICE_NINKE);
+
+    let kernel = Kernel::new(&[&[0, 0, 2], &[1, 1, 0]], 1, 4)?;
+    assert_eq!(kernel, Kernel::SIERRA_LITE);
+
+    // Only right
+    let kernel = Kernel::new(&[&[0, 1]], 0, 1)?;
+    assert_eq!(kernel.weights.as_ref(), &[(1, 0, 1)]);
+
+    Ok(())
+}
+
+/// Should diffuse the largest weights without overflowing
+#[test]
+fn dithering_kernel_large() -> GreenfieldResult<()> {
+    // Every pixel gets an error of 255 times 65535 from each of the 200 pixels before it
+    let mut row = vec![u16::MAX; 201];
+    row[0] = 0;
+    let kernel = Kernel::new(&[&row], 0, 1)?;
+    let palette = Palette::new(vec![Rgb::new(0, 0, 0)])?;
+    let colors = vec![Rgb::new(255, 255, 255); 256];
+
+    let dithered = Dithering::ErrorDiffusion(kernel).apply(256, &palette, &colors);
+    assert_eq!(dithered, vec![Rgb::new(0, 0, 0); 256]);
+
+    Ok(())
+}
+
+/// Should reject malformed weight matrices
+#[test]
+fn dithering_kernel_err() {
+    let invalid = |result: GreenfieldResult<Kernel>| {
+        matches!(result, Err(GreenfieldError::InvalidDitheringKernel(_)))
+    };
+
+    // Origin out of the matrix
+    assert!(invalid(Kernel::new(&[&[0, 1]], 2, 1)));
+    assert!(invalid(Kernel::new(&[], 0, 1)));
+    // Ragged rows
+    assert!(invalid(Kernel::new(&[&[0, 1], &[1]], 0, 2)));
+    // Weights on already quantized pixels
+    assert!(invalid(Kernel::new(&[&[1, 0, 1]], 1, 2)));
+    assert!(invalid(Kernel::new(&[&[0, 1, 1]], 1, 2)));
+    // Nothing to diffuse
+    assert!(invalid(Kernel::new(&[&[0, 0], &[0, 0]], 0, 1)));
+    assert!(invalid(Kernel::new(&[&[0, 1]], 0, 0)));
+}
+
+/// Every kernel should keep the colors in the quantizer, and the mean of flat regions
+#[test]
+fn dithering_algorithms() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?;
+    let palette = Palette::new(vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(255, 255, 255),
+        Rgb::new(255, 0, 0),
+        Rgb::new(0, 0, 255),
+    ])?;
+    let flat = vec![Rgb::new(80, 140, 200); 32 * 32];
+    let custom = Kernel::new(&[&[0, 1], &[1, 0]], 0, 2)?;
+
+    for dithering in [
+        Dithering::FloydSteinberg,
+        Dithering::Atkinson,
+        Dithering::JarvisJudiceNinke,
+        Dithering::Stucki,
+        Dithering::Sierra,
+        Dithering::TwoRowSierra,
+        Dithering::SierraLite,
+        Dithering::ErrorDiffusion(custom.clone()),
+    ] {
+        let dithered = dithering.apply(32, &quantization, &flat);
+        assert!(dithered.iter().all(|c| quantization.reconstruct(c) == *c));
+        // Atkinson drops part of the error, so its mean drifts a bit more
+        for (d, c) in mean(&dithered).iter().zip([80.0, 140.0, 200.0]) {
+            assert!((d - c).abs() < 12.0, "{dithering:?}: {d} != {c}");
+        }
+
+        let dithered = dithering.apply(64, &palette, &gradient(64, 8));
+        assert!(dithered.iter().all(|c| palette.colors().contains(c)));
+    }
+
+    Ok(())
+}
+
+/// Atkinson should only diffuse 3/4 of the error, to 6 neighbors
+#[test]
+fn dithering_atkinson() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(1, 1, 1)?;
+    let gray = |v: u8| Rgb::new(v, v, v);
+    let colors = [gray(100), gray(120)];
+
+    // 100 -> 64 leaves 36: Atkinson gives 36 / 8 = 4.5 -> 5 to the next pixel (125 -> 64), while
+    // Floyd–Steinberg gives 36 * 7 / 16 = 15.75 -> 16 (136 -> 192)
+    assert_eq!(
+        Dithering::Atkinson.apply(2, &quantization, &colors),
+        vec![gray(64), gray(64)]
+    );
+    assert_eq!(
+        Dithering::FloydSteinberg.apply(2, &quantization, &colors),
+        vec![gray(64), gray(192)]
+    );
+
+    Ok(())
+}
+
+/// Generates a blue noise texture with the void-and-cluster method, returning the rank of each
+/// pixel: pixels are ranked by repeatedly taking the tightest cluster out of a pattern, or putting
+/// a pixel in its largest void, so consecutive ranks are always far apart.
+#[cfg(feature = "std")]
+fn void_and_cluster(size: usize, seed: u64) -> Vec<u16> {
+    let n = size * size;
+
+    // Gaussian energy of a pixel on the others, on a torus
+    let sigma = 2.0 * 1.5f64.powi(2);
+    let distance = |d: usize| d.min(size - d) as f64;
+    let kernel = (0..n)
+        .map(|i| (-(distance(i % size).powi(2) + distance(i / size).powi(2)) / sigma).exp())
+        .collect::<Vec<f64>>();
+    let update = |energy: &mut [f64], p: usize, sign: f64| {
+        let (px, py) = (p % size, p / size);
+        for (i, e) in energy.iter_mut().enumerate() {
+            let (dx, dy) = ((i % size + size - px) % size, (i / size + size - py) % size);
+            *e += sign * kernel[dy * size + dx];
+        }
+    };
+    // The tightest cluster (highest energy) of the set pixels, or the largest void (lowest
+    // energy) of the unset ones
+    let find = |pattern: &[bool], energy: &[f64], set: bool| {
+        let candidates = (0..n).filter(|&i| pattern[i] == set);
+        match set {
+            true => candidates.max_by(|&a, &b| energy[a].total_cmp(&energy[b]).then(b.cmp(&a))),
+            false => candidates.min_by(|&a, &b| energy[a].total_cmp(&energy[b]).then(a.cmp(&b))),
+        }
+        .unwrap()
+    };
+
+    // Random initial pattern, with a tenth of the pixels set
+    let mut state = seed;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % n
+    };
+    let (mut pattern, mut energy) = (vec![false; n], vec![0.0; n]);
+    let ones = n / 10;
+    while pattern.iter().filter(|&&p| p).count() < ones {
+        let i = random();
+        if !pattern[i] {
+            pattern[i] = true;
+            update(&mut energy, i, 1.0);
+        }
+    }
+
+    // Spreads the initial pattern evenly
+    loop {
+        let cluster = find(&pattern, &energy, true);
+        pattern[cluster] = false;
+        update(&mut energy, cluster, -1.0);
+
+        let void = find(&pattern, &energy, false);
+        pattern[void] = true;
+        update(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0u16; n];
+
+    // The initial pixels are ranked by removing the tightest clusters
+    let (mut removed, mut removed_energy) = (pattern.clone(), energy.clone());
+    for rank in (0..ones).rev() {
+        let cluster = find(&removed, &removed_energy, true);
+        removed[cluster] = false;
+        update(&mut removed_energy, cluster, -1.0);
+        ranks[cluster] = rank as u16;
+    }
+
+    // And the rest by filling the largest voids
+    for rank in ones..n {
+        let void = find(&pattern, &energy, false);
+        pattern[void] = true;
+        update(&mut energy, void, 1.0);
+        ranks[void] = rank as u16;
+    }
+
+    ranks
+}
+
+/// The bundled blue noise texture should match the one generated from the sources
+#[cfg(feature = "std")]
+#[test]
+fn dithering_blue_noise_up_to_date() {
+    let path =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/core/dithering/blue_noise.bin");
+
+    let generated = void_and_cluster(BLUE_NOISE_SIZE, 0x9e3779b97f4a7c15)
+        .iter()
+        .flat_map(|rank| rank.to_be_bytes())
+        .collect::<Vec<u8>>();
+
+    if std::env::var_os("GREENFIELD_BLESS").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+
+    assert!(
+        BLUE_NOISE.as_slice() == generated,
+        "blue_noise.bin is outdated, regenerate it with GREENFIELD_BLESS=1 cargo test"
+    );
+}
+
+/// The Bayer matrices should rank every cell once, spreading consecutive ranks
+#[test]
+fn dithering_bayer() {
+    assert_eq!(bayer(1), vec![0]);
+    assert_eq!(bayer(2), vec![0, 2, 3, 1]);
+    assert_eq!(
+        bayer(4),
+        vec![0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5]
+    );
+
+    for size in [2, 4, 8, 16] {
+        let mut ranks = bayer(size);
+        ranks.sort();
+        assert!(ranks.iter().enumerate().all(|(i, &r)| r as usize == i));
+    }
+}
+
+/// The blue noise texture should rank every cell once
+#[test]
+fn dithering_blue_noise() {
+    let (map, size) = Dithering::BlueNoise.threshold_map().unwrap();
+    assert_eq!(size, BLUE_NOISE_SIZE);
+
+    let mut ranks = map.clone();
+    ranks.sort();
+    assert!(ranks.iter().enumerate().all(|(i, &r)| r as usize == i));
+
+    // Consecutive ranks are never neighbors
+    let position = |rank: u16| {
+        let i = map.iter().position(|&r| r == rank).unwrap();
+        ((i % size) as isize, (i / size) as isize)
+    };
+    for rank in 0..64 {
+        let ((ax, ay), (bx, by)) = (position(rank), position(rank + 1));
+        assert!((ax - bx).abs().max((ay - by).abs()) > 1);
+    }
+}
+
+/// Ordered dithering should offset each pixel by the threshold of its cell
+#[test]
+fn dithering_ordered() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(1, 1, 1)?;
+    let gray = |v: u8| Rgb::new(v, v, v);
+
+    // The offsets of a 2x2 Bayer matrix are -3/8, 1/8, 3/8 and -1/8 of the spacing (128): -48,
+    // 16, 48 and -16. So 100 -> 52, 116, 148, 84
+    assert_eq!(
+        Dithering::Bayer2.apply(2, &quantization, &vec![gray(100); 4]),
+        vec![gray(64), gray(64), gray(192), gray(64)]
+    );
+
+    // It's stateless: each pixel only depends on its color and position, so the map is tiled
+    let colors = vec![gray(100); 6 * 4];
+    let dithered = Dithering::Bayer2.apply(6, &quantization, &colors);
+    for (i, color) in dithered.iter().enumerate() {
+        assert_eq!(color, &dithered[(i / 6 % 2) * 6 + i % 2]);
+    }
+
+    Ok(())
+}
+
+/// Ordered dithering should preserve the mean of flat regions, with uniform quantizations and
+/// palettes
+#[test]
+fn dithering_ordered_mean() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?;
+    let palette = Palette::new(vec![Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)])?;
+
+    // Small maps can only mix a few reconstructions, so they can only get so close
+    for (dithering, tolerance) in [
+        (Dithering::Bayer2, 8.5),
+        (Dithering::Bayer4, 2.0),
+        (Dithering::Bayer8, 2.0),
+        (Dithering::Bayer16, 2.0),
+        (Dithering::BlueNoise, 2.0),
+    ] {
+        let flat = vec![Rgb::new(80, 140, 200); 64 * 64];
+        let dithered = dithering.apply(64, &quantization, &flat);
+        assert!(dithered.iter().all(|c| quantization.reconstruct(c) == *c));
+        for (d, c) in mean(&dithered).iter().zip([80.0, 140.0, 200.0]) {
+            assert!((d - c).abs() < tolerance, "{dithering:?}: {d} != {c}");
+        }
+
+        let gray = vec![Rgb::new(64, 64, 64); 64 * 64];
+        let dithered = dithering.apply(64, &palette, &gray);
+        assert!(dithered.iter().all(|c| palette.colors().contains(c)));
+        let white = dithered.iter().filter(|c| c.r == 255).count();
+        assert!((white as isize - 1024).abs() < 64, "{dithering:?}: {white}");
+    }
+
+    Ok(())
+}
+
+/// The ordered dithering of a frame shouldn't depend on the rest of it, so it's stable across
+/// frames
+#[test]
+fn dithering_ordered_stable() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?;
+    let frame = gradient(32, 32);
+    let mut next = frame.clone();
+    // Only the first pixel changes
+    next[0] = Rgb::new(255, 0, 0);
+
+    for dithering in [Dithering::Bayer8, Dithering::BlueNoise] {
+        let a = dithering.apply(32, &quantization, &frame);
+        let b = dithering.apply(32, &quantization, &next);
+        assert_eq!(a[1..], b[1..]);
+    }
+
+    // Unlike error diffusion, where the change spreads to the rest of the frame
+    let a = Dithering::FloydSteinberg.apply(32, &quantization, &frame);
+    let b = Dithering::FloydSteinberg.apply(32, &quantization, &next);
+    assert_ne!(a[1..], b[1..]);
+
+    Ok(())
+}
@@ -14,6 +14,10 @@
 //!     for example, if the quantization tuple is `(5, 6, 5)`, then each color is 16 bits. To get
 //!     all the colors, you must read (width * height) * (bits_r + bits_g + bits_b) bits.
 //!
+//! Other quantizers are stored in place of the quantization tuple, after a tag (see
+//! [`quantization::Quantization`]), e.g. palettes, levels quantizations or uniform quantizations
+//! with other rounding and reconstruction policies ([`quantization::policy`]).
+//!
 //! ## Format on Disk
 //!
 //! ```text
@@ -51,7 +55,7 @@
 //!     assert_eq!(image.width, 1);
 //!     assert_eq!(image.height, 1);
 //!     assert_eq!(
-//!         image.uniform_quantization,
+//!         image.quantization,
 //!         quantization::UniformQuantization::new(1, 1, 1)?
 //!     );
 //!     assert_eq!(image.data, vec![color::Rgb::new(0, 0, 0)]);
@@ -66,7 +70,7 @@
 //!     assert_eq!(image.width, 0);
 //!     assert_eq!(image.height, 0);
 //!     assert_eq!(
-//!         image.uniform_quantization,
+//!         image.quantization,
 //!         quantization::UniformQuantization::new(1, 1, 1)?
 //!     );
 //!
@@ -306,12 +310,25 @@
 //! ```
 #[cfg(test)]
 mod tests;
-use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+pub mod base64;
+
+pub mod terminal;
+pub use terminal::TerminalBackend;
+
+// The deku derives need `format!` in scope
+#[allow(unused_imports)]
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Display;
+#[cfg(feature = "std")]
 use std::path::PathBuf;
 
-use super::{color, quantization};
+use super::{color, dithering, quantization, statistics};
 use crate::error::{GreenfieldError, GreenfieldResult};
 use crate::pixel;
+use crate::quantization::Quantizer;
 use deku::bitvec::{BitSlice, BitVec, Msb0};
 use deku::prelude::*;
 
@@ -329,16 +346,105 @@ pub struct Image {
     width: usize,
     #[deku(bits = "32")]
     height: usize,
-    uniform_quantization: quantization::UniformQuantization,
+    quantization: quantization::Quantization,
 
     #[deku(
         count = "self.width * self.height",
-        reader = "Self::data_read(deku::rest, &uniform_quantization, &width, &height)",
-        writer = "Self::data_write(deku::output, &data, &uniform_quantization, &width, &height)"
+        reader = "Self::data_read(deku::rest, &quantization, &width, &height)",
+        writer = "Self::data_write(deku::output, &data, &quantization, &width, &height)"
     )]
     data: Vec<color::Rgb>,
 }
 
+pub use header::Header;
+
+// The `DekuRead` derive rounds the size of the header up to bytes by hand, in an impl of its own
+// that attributes on the struct don't reach
+#[allow(clippy::manual_div_ceil)]
+mod header {
+    // The deku derives need `format!` and `Vec` in scope
+    #[allow(unused_imports)]
+    use alloc::{format, vec::Vec};
+
+    use super::quantization;
+    use deku::prelude::*;
+
+    /// ## Image header
+    ///
+    /// The prefix of a greenfield image: everything in [`Image`](super::Image) but the color data.
+    /// It can be read to inspect an image without decoding its colors, and is used by the
+    /// streaming codecs, which need to know the dimensions and quantization before the color data
+    /// arrives.
+    #[derive(Debug, Eq, Clone, PartialEq, DekuRead, DekuWrite)]
+    #[deku(magic = b"grnfld42", endian = "big")]
+    pub struct Header {
+        #[deku(bits = "32")]
+        pub width: usize,
+        #[deku(bits = "32")]
+        pub height: usize,
+        pub quantization: quantization::Quantization,
+    }
+}
+
+impl Header {
+    /// Size on disk of the header of uniformly quantized images, in bits. It's also the minimum
+    /// size of any header: palettes are stored in the header, so it can be longer (see
+    /// [`Header::bits`]).
+    pub const BITS: usize = 64 + 32 + 32 + 12;
+
+    /// Maximum size of a header on disk, in bits: the header of an image with the largest palette.
+    pub const MAX_BITS: usize = 64 + 32 + 32 + 4 + 16 + 24 * quantization::Palette::MAX_SIZE;
+
+    /// ## Size of the header on disk, in bits
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::image::Header;
+    /// use greenfield::prelude::*;
+    ///
+    /// let image = Image::new(1, 1, UniformQuantization::new(8, 8, 8)?, vec![Rgb::new(1, 2, 3)])?;
+    /// assert_eq!(image.header().bits(), Header::BITS);
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn bits(&self) -> usize {
+        let mut params = BitVec::new();
+        // Writing the parameters to memory can't fail
+        let _ = self.quantization.write_params(&mut params);
+        64 + 32 + 32 + params.len()
+    }
+
+    /// ## Reads the header from the beginning of a serialized image.
+    ///
+    /// Only the first [`Header::bits`] bits are read, so the color data may be missing.
+    ///
+    /// ## Errors
+    ///
+    /// - If the bytes don't start with a valid greenfield header. If they are a valid but
+    ///   truncated header, the error is [`deku::DekuError::Incomplete`].
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::image::Header;
+    /// use greenfield::prelude::*;
+    ///
+    /// let serialized = vec![
+    ///     103, 114, 110, 102, 108, 100, 52, 50, 0, 0, 0, 1, 0, 0, 0, 1, 136, 128,
+    /// ];
+    /// let header = Header::deserialize(&serialized)?;
+    ///
+    /// assert_eq!(header.width, 1);
+    /// assert_eq!(header.height, 1);
+    /// assert_eq!(header.quantization, UniformQuantization::new(8, 8, 8)?);
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn deserialize(bytes: &[u8]) -> GreenfieldResult<Self> {
+        let (_, header) = Self::from_bytes((bytes, 0))?;
+        Ok(header)
+    }
+}
+
 impl Display for Image {
     /// ## Formats the image as a string
     /// The format is `[width]x[height] [quantization] [colors]`
@@ -364,26 +470,72 @@ impl Display for Image {
     ///     Ok(())
     /// }
     /// ```
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "[{}x{}] {} {}x[RGB]",
             self.width,
             self.height,
-            self.uniform_quantization,
+            self.quantization,
             self.data.len()
         )
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Image {
+    /// ## Serializes the image as a structured object
+    ///
+    /// The object has the `width`, `height`, `quantization` and `data` fields, with the
+    /// quantization as a tuple and each color as a hex string. To serialize the image as a base64
+    /// greenfield blob instead, see [`base64`].
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Image", 4)?;
+        state.serialize_field("width", &self.width)?;
+        state.serialize_field("height", &self.height)?;
+        state.serialize_field("quantization", &self.quantization)?;
+        state.serialize_field("data", &self.data)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Image {
+    /// ## Deserializes the image from a structured object
+    ///
+    /// The image is validated and quantized the same way as in [`Image::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Image", deny_unknown_fields)]
+        struct Fields {
+            width: usize,
+            height: usize,
+            quantization: quantization::Quantization,
+            data: Vec<color::Rgb>,
+        }
+
+        let Fields {
+            width,
+            height,
+            quantization,
+            data,
+        } = Fields::deserialize(deserializer)?;
+        Self::new(width, height, quantization, data).map_err(serde::de::Error::custom)
+    }
+}
+
 impl Image {
-    /// ## Makes a new image from the given width, height, quantization tuple, and color data.
+    /// ## Makes a new image from the given width, height, quantization, and color data.
     ///
     /// Note that you must provide the colors in the full RGB format, even if the quantization tuple
     /// is not `(8, 8, 8)`. After the image is created, it will be quantized to the given tuple.
     ///
+    /// Any quantizer of [`quantization::Quantization`] can be given, e.g. a
+    /// [`quantization::UniformQuantization`].
+    ///
     /// ## Errors
-    /// - If the quantization tuple is invalid.
     /// - If the color data is not the same length as the width * height.
     ///
     /// ## Examples
@@ -401,7 +553,7 @@ impl Image {
     ///     assert_eq!(image.width, 1);
     ///     assert_eq!(image.height, 1);
     ///     assert_eq!(
-    ///         image.uniform_quantization,
+    ///         image.quantization,
     ///         quantization::UniformQuantization::new(1, 1, 1)?
     ///     );
     ///     assert_eq!(image.data, vec![color::Rgb::new(0, 0, 0)]);
@@ -416,7 +568,7 @@ impl Image {
     ///     assert_eq!(image.width, 0);
     ///     assert_eq!(image.height, 0);
     ///     assert_eq!(
-    ///         image.uniform_quantization,
+    ///         image.quantization,
     ///         quantization::UniformQuantization::new(1, 1, 1)?
     ///     );
     ///
@@ -439,24 +591,76 @@ impl Image {
     pub fn new(
         width: usize,
         height: usize,
-        uniform_quantization: quantization::UniformQuantization,
+        quantization: impl Into<quantization::Quantization>,
+        data: Vec<color::Rgb>,
+    ) -> GreenfieldResult<Self> {
+        Self::new_dithered(
+            width,
+            height,
+            quantization,
+            data,
+            dithering::Dithering::None,
+        )
+    }
+
+    /// ## Makes a new image, dithering its colors.
+    ///
+    /// Like [`Image::new`], but the colors are quantized with `dithering` (see [`dithering`]),
+    /// which trades the banding of low bit quantizations for noise.
+    ///
+    /// ## Arguments
+    /// * `width` - The width of the image
+    /// * `height` - The height of the image
+    /// * `quantization` - The quantization of the image
+    /// * `data` - The colors of the image, in row-major order
+    /// * `dithering` - The dithering algorithm
+    ///
+    /// ## Errors
+    /// - If the data doesn't match the dimensions.
+    /// - If the tables of a [`quantization::Lut`] don't fit the bits of the quantization.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::dithering::Dithering;
+    /// use greenfield::prelude::*;
+    ///
+    /// let image = Image::new_dithered(
+    ///     2,
+    ///     1,
+    ///     UniformQuantization::new(2, 2, 2)?,
+    ///     vec![Rgb::new(100, 100, 100); 2],
+    ///     Dithering::FloydSteinberg,
+    /// )?;
+    ///
+    /// assert_eq!(
+    ///     image.colors().cloned().collect::<Vec<Rgb>>(),
+    ///     vec![Rgb::new(96, 96, 96), Rgb::new(96, 96, 96)]
+    /// );
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn new_dithered(
+        width: usize,
+        height: usize,
+        quantization: impl Into<quantization::Quantization>,
         data: Vec<color::Rgb>,
+        dithering: dithering::Dithering,
     ) -> GreenfieldResult<Self> {
+        let quantization = quantization.into();
+        if let quantization::Quantization::Uniform(uniform) = &quantization {
+            uniform.check()?;
+        }
         let size = width * height;
         let data_len = data.len();
 
         match size == data_len {
             true => {
-                let data = data
-                    .iter()
-                    .map(|c| uniform_quantization.get_quantized_color(c))
-                    .map(|c| uniform_quantization.get_dequantized_color(&c))
-                    .collect();
+                let data = dithering.apply(width, &quantization, &data);
 
                 Ok(Self {
                     width,
                     height,
-                    uniform_quantization,
+                    quantization,
                     data,
                 })
             }
@@ -465,9 +669,56 @@ impl Image {
         }
     }
 
+    /// ## Makes a new image from already dequantized color data.
+    ///
+    /// Unlike [`Image::new`], the colors are stored as given, so they must already be the
+    /// reconstruction of the quantization (e.g. the output of
+    /// [`quantization::Quantizer::decompress`]).
+    pub(crate) fn from_raw_parts(header: Header, data: Vec<color::Rgb>) -> Self {
+        let Header {
+            width,
+            height,
+            quantization,
+        } = header;
+
+        Self {
+            width,
+            height,
+            quantization,
+            data,
+        }
+    }
+
+    /// ## Returns the header of the image.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    ///
+    /// let image = Image::new(
+    ///     2,
+    ///     1,
+    ///     UniformQuantization::new(5, 6, 5)?,
+    ///     vec![Rgb::default(); 2],
+    /// )?;
+    /// let header = image.header();
+    ///
+    /// assert_eq!((header.width, header.height), image.dimensions());
+    /// assert_eq!(header.quantization, *image.quantizer());
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn header(&self) -> Header {
+        Header {
+            width: self.width,
+            height: self.height,
+            quantization: self.quantization.clone(),
+        }
+    }
+
     pub fn quantify(&mut self) -> GreenfieldResult<()> {
         self.data.iter_mut().for_each(|c| {
-            self.uniform_quantization.quantify_color(c);
+            *c = self.quantization.quantize_color(c);
         });
 
         Ok(())
@@ -481,7 +732,7 @@ impl Image {
 
     pub fn dequantify(&mut self) -> GreenfieldResult<()> {
         self.data.iter_mut().for_each(|c| {
-            self.uniform_quantization.dequantify_color(c);
+            *c = self.quantization.dequantize_color(c);
         });
 
         Ok(())
@@ -612,6 +863,7 @@ impl Image {
     ///     Ok(())
     /// }
     /// ```
+    #[cfg(feature = "std")]
     pub fn to_file(self, path: &PathBuf) -> GreenfieldResult<()> {
         std::fs::write(path, self.serialize()?)?;
         Ok(())
@@ -651,6 +903,7 @@ impl Image {
     ///     Ok(())
     /// }
     /// ```
+    #[cfg(feature = "std")]
     pub fn from_file(path: &PathBuf) -> GreenfieldResult<Image> {
         let serialized = std::fs::read(path)?;
         let image = Image::try_from(serialized.as_slice())?;
@@ -683,7 +936,13 @@ impl Image {
         (self.width, self.height)
     }
 
-    /// ## Returns the quantization of the image.
+    /// ## Returns the uniform quantization of the image.
+    ///
+    /// Images quantized with another scheme have none, use [`Image::quantizer`] for those.
+    ///
+    /// ## Panics
+    /// - If the image has another quantization scheme than
+    ///   [`UniformQuantization`](quantization::UniformQuantization).
     ///
     /// ## Examples
     ///
@@ -707,7 +966,29 @@ impl Image {
     /// }
     /// ```
     pub fn quantization(&self) -> &quantization::UniformQuantization {
-        &self.uniform_quantization
+        match &self.quantization {
+            quantization::Quantization::Uniform(uniform) => uniform,
+            quantization => panic!("{quantization} isn't a uniform quantization"),
+        }
+    }
+
+    /// ## Returns the quantizer of the image, whichever its scheme.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::{Palette, Quantizer};
+    ///
+    /// let palette = Palette::new(vec![Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)])?;
+    /// let image = Image::new(2, 1, palette, vec![Rgb::new(10, 10, 10); 2])?;
+    ///
+    /// assert_eq!(image.quantizer().bits_per_pixel(), 1);
+    /// assert!(matches!(image.quantizer(), Quantization::Palette(_)));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn quantizer(&self) -> &quantization::Quantization {
+        &self.quantization
     }
 
     /// ## Iterates over the colors of the image.
@@ -763,6 +1044,51 @@ impl Image {
             .map(|(i, color)| pixel::Pixel::new(i / self.width, i % self.height, &color))
     }
 
+    /// ## Returns the histogram of the channels of the image.
+    ///
+    /// Counts the components of the colors, in parallel if enabled (see [`statistics`](crate::statistics)).
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    ///
+    /// let colors = vec![Rgb::new(0, 10, 20), Rgb::new(0, 10, 40)];
+    /// let image = Image::new(2, 1, UniformQuantization::new(8, 8, 8)?, colors)?;
+    ///
+    /// let histogram = image.histogram();
+    /// assert_eq!(histogram.channel(0)[0], 2);
+    /// assert_eq!(histogram.channel(2)[20], 1);
+    /// assert_eq!(histogram.used(), [1, 1, 2]);
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn histogram(&self) -> statistics::Histogram {
+        statistics::Histogram::count(&self.data, [256; 3], color::Rgb::bytes)
+    }
+
+    /// ## Returns the statistics of the colors of the image.
+    ///
+    /// The number of unique colors, the mean, variance and range of each channel, and for uniform
+    /// quantizations the quantization levels in use (see [`statistics`](crate::statistics)).
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    ///
+    /// let colors = vec![Rgb::new(0, 10, 20), Rgb::new(0, 10, 40)];
+    /// let image = Image::new(2, 1, UniformQuantization::new(8, 8, 8)?, colors)?;
+    ///
+    /// let statistics = image.statistics();
+    /// assert_eq!(statistics.unique_colors(), 2);
+    /// assert_eq!(statistics.mean(), [0.0, 10.0, 30.0]);
+    /// assert_eq!(statistics.variance(), [0.0, 0.0, 100.0]);
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn statistics(&self) -> statistics::Statistics {
+        statistics::Statistics::new(&self.data, &self.quantization)
+    }
+
     /// ## Iterates over the pixels of the image as bytes.
     ///
     /// ## Examples
@@ -793,20 +1119,25 @@ impl Image {
     /// Writes the data field of the image considering the quantization.
     fn data_read<'a>(
         rest: &'a BitSlice<u8, Msb0>,
-        uniform_quantization: &quantization::UniformQuantization,
+        quantization: &quantization::Quantization,
         width: &usize,
         height: &usize,
     ) -> GreenfieldResult<(&'a BitSlice<u8, Msb0>, Vec<color::Rgb>)> {
-        let quantization::UniformQuantization {
-            bits_r,
-            bits_g,
-            bits_b,
-        } = uniform_quantization;
-        let bits = (bits_r + bits_g + bits_b) as usize; // Number of bits per color
-        let count: usize = (*width as usize) * (*height as usize) * bits; // Expected number of bits
-        // let data_len = rest.len(); // Actual number of bits
-
-        let colors = uniform_quantization.decompress(&rest[..count]);
+        let bits = quantization.bits_per_pixel(); // Number of bits per color
+
+        // Expected number of bits
+        let count = width
+            .checked_mul(*height)
+            .and_then(|pixels| pixels.checked_mul(bits))
+            .ok_or(GreenfieldError::ImageTooLarge(*width, *height))?;
+        let data_len = rest.len(); // Actual number of bits
+
+        if data_len < count {
+            let missing = deku::error::NeedSize::new(count - data_len);
+            return Err(DekuError::Incomplete(missing).into());
+        }
+
+        let colors = quantization.decompress(&rest[..count]);
         let rest = BitSlice::<u8, Msb0>::empty();
         Ok((rest, colors))
     }
@@ -816,8 +1147,8 @@ impl Image {
     /// Writes the data field of the image considering the quantization.
     fn data_write(
         output: &mut BitVec<u8, Msb0>,
-        data: &Vec<color::Rgb>,
-        uniform_quantization: &quantization::UniformQuantization,
+        data: &[color::Rgb],
+        quantization: &quantization::Quantization,
         width: &usize,
         height: &usize,
     ) -> GreenfieldResult<()> {
@@ -826,7 +1157,7 @@ impl Image {
 
         match count == data_len {
             true => {
-                let compressed = uniform_quantization.compress(data);
+                let compressed = quantization.compress(data);
                 output.extend(compressed);
                 Ok(())
             }
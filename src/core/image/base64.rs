@@ -0,0 +1,60 @@
+//! ## Serde support for images as base64 greenfield blobs
+//!
+//! By default, an [`Image`] is serialized as a structured object (see its `Serialize`
+//! implementation). This module serializes it instead as a base64 string of its greenfield
+//! serialization (see [`Image::serialize`]), which is way more compact. Choose it with the
+//! `#[serde(with = "greenfield::image::base64")]` attribute.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Asset {
+//!     name: String,
+//!     #[serde(with = "greenfield::image::base64")]
+//!     image: Image,
+//! }
+//!
+//! let asset = Asset {
+//!     name: String::from("dot"),
+//!     image: Image::new(
+//!         1,
+//!         1,
+//!         UniformQuantization::new(8, 8, 8)?,
+//!         vec![Rgb::new(0, 0, 0)],
+//!     )?,
+//! };
+//!
+//! let json = serde_json::to_string(&asset).unwrap();
+//! assert_eq!(json, r#"{"name":"dot","image":"Z3JuZmxkNDIAAAABAAAAAYiAAAAA"}"#);
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use super::Image;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+/// ## Serializes the image as a base64 greenfield blob
+///
+/// ## Errors
+/// - If the image cannot be serialized to the greenfield format.
+pub fn serialize<S: Serializer>(image: &Image, serializer: S) -> Result<S::Ok, S::Error> {
+    let bytes = image.clone().serialize().map_err(ser::Error::custom)?;
+    serializer.serialize_str(&STANDARD.encode(bytes))
+}
+
+/// ## Deserializes the image from a base64 greenfield blob
+///
+/// ## Errors
+/// - If the string is not valid base64.
+/// - If the decoded bytes are not a valid greenfield image.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Image, D::Error> {
+    let encoded = <alloc::borrow::Cow<str>>::deserialize(deserializer)?;
+    let bytes = STANDARD
+        .decode(encoded.as_bytes())
+        .map_err(de::Error::custom)?;
+    Image::deserialize(&bytes).map_err(de::Error::custom)
+}
@@ -0,0 +1,289 @@
+//! ## Terminal rendering of images
+//!
+//! Renders an [`Image`] as escape sequences that a terminal draws as a picture, so images can be
+//! previewed without a graphical session (e.g. over SSH). The image is downscaled (with a box
+//! filter) to the requested number of columns, and then encoded with one of the backends:
+//!
+//! - [`TerminalBackend::HalfBlock`]: Unicode upper half blocks (`▀`) with 24-bit foreground and
+//!   background colors, two pixels per cell. Works in any truecolor terminal.
+//! - [`TerminalBackend::Sixel`]: The DEC sixel graphics protocol (xterm, foot, mlterm, ...), with
+//!   a 3-3-2 palette.
+//! - [`TerminalBackend::Kitty`]: The kitty graphics protocol (kitty, WezTerm, Ghostty, ...), in
+//!   full color.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::image::TerminalBackend;
+//! use greenfield::prelude::*;
+//!
+//! let image = Image::new(
+//!     2,
+//!     2,
+//!     UniformQuantization::new(8, 8, 8)?,
+//!     vec![Rgb::new(255, 0, 0); 4],
+//! )?;
+//!
+//! print!("{}", image.render_terminal(2, TerminalBackend::HalfBlock));
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use super::Image;
+use crate::color::Rgb;
+use crate::quantization::UniformQuantization;
+
+#[cfg(test)]
+mod tests;
+
+/// Width of a terminal cell in pixels assumed by the pixel-based backends.
+pub const CELL_WIDTH: usize = 8;
+
+/// Maximum size of a kitty graphics protocol payload chunk.
+const KITTY_CHUNK: usize = 4096;
+
+/// ## Terminal graphics backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TerminalBackend {
+    /// Unicode half blocks with 24-bit colors.
+    #[default]
+    HalfBlock,
+    /// DEC sixel graphics.
+    Sixel,
+    /// Kitty graphics protocol.
+    Kitty,
+}
+
+impl Image {
+    /// ## Renders the image for a terminal
+    ///
+    /// The image is downscaled to `width_cols` terminal columns, keeping its aspect ratio. It's
+    /// never upscaled. The pixel-based backends (sixel and kitty) assume cells [`CELL_WIDTH`]
+    /// pixels wide, and kitty is told to fit downscaled images in exactly `width_cols` columns.
+    ///
+    /// ## Arguments
+    /// * `width_cols` - The width of the rendered image, in terminal columns
+    /// * `backend` - The terminal graphics protocol
+    ///
+    /// ## Returns
+    /// The escape sequences drawing the image, ending on a new line. Empty for empty images.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::image::TerminalBackend;
+    /// use greenfield::prelude::*;
+    ///
+    /// let image = Image::new(
+    ///     1,
+    ///     2,
+    ///     UniformQuantization::new(8, 8, 8)?,
+    ///     vec![Rgb::new(255, 0, 0), Rgb::new(0, 0, 255)],
+    /// )?;
+    ///
+    /// assert_eq!(
+    ///     image.render_terminal(80, TerminalBackend::HalfBlock),
+    ///     "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[0m\n"
+    /// );
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn render_terminal(&self, width_cols: usize, backend: TerminalBackend) -> String {
+        let width_px = match backend {
+            TerminalBackend::HalfBlock => width_cols,
+            TerminalBackend::Sixel | TerminalBackend::Kitty => width_cols * CELL_WIDTH,
+        };
+
+        let width = width_px.min(self.width);
+        if width == 0 || self.height == 0 {
+            return String::new();
+        }
+        let height = (self.height * width).div_ceil(self.width);
+        let colors = self.downscale(width, height);
+        // Smaller images keep their size, instead of being stretched to the columns
+        let columns = (width == width_px).then_some(width_cols);
+
+        match backend {
+            TerminalBackend::HalfBlock => half_block(&colors, width),
+            TerminalBackend::Sixel => sixel(&colors, width, height),
+            TerminalBackend::Kitty => kitty(&colors, width, height, columns),
+        }
+    }
+
+    /// Downscales the image with a box filter: each color is the mean of the area it covers.
+    fn downscale(&self, width: usize, height: usize) -> Vec<Rgb> {
+        let span =
+            |i: usize, from: usize, to: usize| (i * from / to, ((i + 1) * from).div_ceil(to));
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (x0, x1) = span(x, self.width, width);
+                let (y0, y1) = span(y, self.height, height);
+
+                let mut sum = [0usize; 3];
+                for row in y0..y1 {
+                    for color in &self.data[row * self.width + x0..row * self.width + x1] {
+                        sum[0] += color.r as usize;
+                        sum[1] += color.g as usize;
+                        sum[2] += color.b as usize;
+                    }
+                }
+
+                let area = (x1 - x0) * (y1 - y0);
+                Rgb::new(
+                    (sum[0] / area) as u8,
+                    (sum[1] / area) as u8,
+                    (sum[2] / area) as u8,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Upper half blocks: the foreground is the top pixel and the background the bottom one.
+fn half_block(colors: &[Rgb], width: usize) -> String {
+    let mut out = String::new();
+    let rows = colors.chunks(width).collect::<Vec<&[Rgb]>>();
+
+    for pair in rows.chunks(2) {
+        for (x, top) in pair[0].iter().enumerate() {
+            let _ = write!(out, "\x1b[38;2;{};{};{}m", top.r, top.g, top.b);
+            match pair.get(1) {
+                Some(bottom) => {
+                    let bottom = &bottom[x];
+                    let _ = write!(out, "\x1b[48;2;{};{};{}m", bottom.r, bottom.g, bottom.b);
+                }
+                // Odd height: the last row has no bottom pixel
+                None => out.push_str("\x1b[49m"),
+            }
+            out.push('▀');
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    out
+}
+
+/// Sixels: bands of 6 rows, each drawn once per color in it, with a 3-3-2 palette.
+fn sixel(colors: &[Rgb], width: usize, height: usize) -> String {
+    let palette = UniformQuantization {
+        bits_r: 3,
+        bits_g: 3,
+        bits_b: 2,
+        ..Default::default()
+    };
+    let index = |c: &Rgb| {
+        let Rgb { r, g, b } = palette.get_quantized_color(c);
+        ((r as usize) << 5) | ((g as usize) << 2) | b as usize
+    };
+    let indices = colors.iter().map(index).collect::<Vec<usize>>();
+
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+
+    // Only the used colors are defined, in percents
+    let mut used = [false; 256];
+    indices.iter().for_each(|&i| used[i] = true);
+    for (i, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        let Rgb { r, g, b } = palette.get_dequantized_color(&Rgb::new(
+            (i >> 5) as u8,
+            ((i >> 2) & 0b111) as u8,
+            (i & 0b11) as u8,
+        ));
+        let percent = |c: u8| c as usize * 100 / 255;
+        let _ = write!(out, "#{};2;{};{};{}", i, percent(r), percent(g), percent(b));
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+
+        let mut in_band = [false; 256];
+        for y in rows.clone() {
+            indices[y * width..(y + 1) * width]
+                .iter()
+                .for_each(|&i| in_band[i] = true);
+        }
+
+        for (color, _) in in_band.iter().enumerate().filter(|(_, used)| **used) {
+            let _ = write!(out, "#{}", color);
+
+            let sixels = (0..width).map(|x| {
+                let bits = rows
+                    .clone()
+                    .filter(|y| indices[y * width + x] == color)
+                    .fold(0u8, |bits, y| bits | 1 << (y - band));
+                (63 + bits) as char
+            });
+            run_length(&mut out, sixels);
+
+            // Carriage return, to draw the next color over the same band
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\\n");
+    out
+}
+
+/// Writes sixels, collapsing runs with the `!<count><sixel>` repeat introducer.
+fn run_length(out: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut flush = |sixel: char, count: usize| match count {
+        0 => {}
+        1..=3 => (0..count).for_each(|_| out.push(sixel)),
+        _ => {
+            let _ = write!(out, "!{}{}", count, sixel);
+        }
+    };
+
+    let (last, count) = sixels.fold(('?', 0), |(last, count), sixel| match sixel == last {
+        true => (last, count + 1),
+        false => {
+            flush(last, count);
+            (sixel, 1)
+        }
+    });
+    flush(last, count);
+}
+
+/// Kitty graphics: raw RGB pixels, base64 encoded and split in chunks, fit in `width_cols`
+/// columns if given.
+fn kitty(colors: &[Rgb], width: usize, height: usize, width_cols: Option<usize>) -> String {
+    let bytes = colors
+        .iter()
+        .flat_map(|c| [c.r, c.g, c.b])
+        .collect::<Vec<u8>>();
+    let payload = STANDARD.encode(bytes);
+    let chunks = payload
+        .as_bytes()
+        .chunks(KITTY_CHUNK)
+        .collect::<Vec<&[u8]>>();
+
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        match i {
+            0 => {
+                let _ = write!(out, "\x1b_Ga=T,f=24,s={},v={},", width, height);
+                if let Some(width_cols) = width_cols {
+                    let _ = write!(out, "c={},", width_cols);
+                }
+                let _ = write!(out, "m={};", more);
+            }
+            _ => {
+                let _ = write!(out, "\x1b_Gm={};", more);
+            }
+        }
+        // The payload is base64, so it's ASCII
+        out.extend(chunk.iter().map(|&b| b as char));
+        out.push_str("\x1b\\");
+    }
+
+    out.push('\n');
+    out
+}
@@ -0,0 +1,103 @@
+use super::*;
+use crate::error::GreenfieldResult;
+
+/// A `width`x`height` image, red on the top half and blue on the bottom one
+fn halves(width: usize, height: usize) -> GreenfieldResult<Image> {
+    let colors = (0..width * height)
+        .map(|i| match i / width < height / 2 {
+            true => Rgb::new(255, 0, 0),
+            false => Rgb::new(0, 0, 255),
+        })
+        .collect();
+
+    Image::new(width, height, UniformQuantization::new(8, 8, 8)?, colors)
+}
+
+#[test]
+/// Should downscale an image averaging the covered areas
+fn terminal_downscale() -> GreenfieldResult<()> {
+    let image = Image::new(
+        2,
+        2,
+        UniformQuantization::new(8, 8, 8)?,
+        vec![
+            Rgb::new(0, 0, 0),
+            Rgb::new(255, 255, 255),
+            Rgb::new(100, 100, 100),
+            Rgb::new(45, 45, 45),
+        ],
+    )?;
+
+    assert_eq!(image.downscale(1, 1), vec![Rgb::new(100, 100, 100)]);
+    assert_eq!(image.downscale(2, 2), image.data);
+    assert_eq!(
+        image.downscale(1, 2),
+        vec![Rgb::new(127, 127, 127), Rgb::new(72, 72, 72)]
+    );
+
+    Ok(())
+}
+
+#[test]
+/// Should render an image as half blocks
+fn terminal_half_block() -> GreenfieldResult<()> {
+    // Two pixel rows per line
+    let rendered = halves(4, 4)?.render_terminal(2, TerminalBackend::HalfBlock);
+    let cell = "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀";
+    assert_eq!(rendered, format!("{cell}{cell}\x1b[0m\n"));
+
+    // Odd heights leave the background of the last line alone
+    let image = Image::new(
+        1,
+        1,
+        UniformQuantization::new(8, 8, 8)?,
+        vec![Rgb::new(1, 2, 3)],
+    )?;
+    assert_eq!(
+        image.render_terminal(10, TerminalBackend::HalfBlock),
+        "\x1b[38;2;1;2;3m\x1b[49m▀\x1b[0m\n"
+    );
+
+    // Empty images render nothing
+    let image = Image::new(0, 0, UniformQuantization::new(8, 8, 8)?, vec![])?;
+    assert_eq!(image.render_terminal(10, TerminalBackend::HalfBlock), "");
+    assert_eq!(halves(2, 2)?.render_terminal(0, TerminalBackend::Kitty), "");
+
+    Ok(())
+}
+
+#[test]
+/// Should render an image as sixels
+fn terminal_sixel() -> GreenfieldResult<()> {
+    let rendered = halves(4, 4)?.render_terminal(1, TerminalBackend::Sixel);
+
+    // Red is 0b111_000_00 = 224, blue is 0b000_000_11 = 3. Each color is drawn on its rows: the
+    // top two (0b000011 + 63 = 'B') and the bottom two (0b001100 + 63 = 'K').
+    assert_eq!(
+        rendered,
+        "\x1bPq\"1;1;4;4#3;2;6;6;87#224;2;94;6;12#3!4K$#224!4B$-\x1b\\\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+/// Should render an image with the kitty graphics protocol
+fn terminal_kitty() -> GreenfieldResult<()> {
+    // Smaller than the columns, so it isn't fit to them
+    let rendered = halves(2, 2)?.render_terminal(3, TerminalBackend::Kitty);
+    let payload = STANDARD.encode([255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255]);
+    assert_eq!(
+        rendered,
+        format!("\x1b_Ga=T,f=24,s=2,v=2,m=0;{payload}\x1b\\\n")
+    );
+
+    // Big images are sent in chunks
+    let rendered = halves(64, 64)?.render_terminal(8, TerminalBackend::Kitty);
+    // 64 * 64 * 3 bytes are 16384 base64 characters
+    assert_eq!(rendered.matches("\x1b\\").count(), 4);
+    assert!(rendered.starts_with("\x1b_Ga=T,f=24,s=64,v=64,c=8,m=1;"));
+    assert!(rendered.contains("\x1b_Gm=0;"));
+
+    Ok(())
+}
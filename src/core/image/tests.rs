@@ -1,4 +1,5 @@
 use super::*;
+#[cfg(feature = "std")]
 use std::env;
 
 #[test]
@@ -14,7 +15,7 @@ fn image_new() -> GreenfieldResult<()> {
     assert_eq!(image.width, 1);
     assert_eq!(image.height, 1);
     assert_eq!(
-        image.uniform_quantization,
+        image.quantization,
         quantization::UniformQuantization::new(1, 1, 1)?
     );
     assert_eq!(image.data, vec![color::Rgb::new(0, 0, 0)]);
@@ -29,7 +30,7 @@ fn image_new() -> GreenfieldResult<()> {
     assert_eq!(image.width, 0);
     assert_eq!(image.height, 0);
     assert_eq!(
-        image.uniform_quantization,
+        image.quantization,
         quantization::UniformQuantization::new(1, 1, 1)?
     );
 
@@ -104,6 +105,22 @@ fn image_deserialize() -> GreenfieldResult<()> {
     let image = Image::deserialize(&serialized);
     assert!(image.is_err());
 
+    // Invalid data: components with 0 or more than 8 bits
+    for quantization in [[0x80, 0x90], [0x80, 0x00], [0x8f, 0x80]] {
+        let mut serialized = vec![103, 114, 110, 102, 108, 100, 52, 50, 0, 0, 0, 1, 0, 0, 0, 1];
+        serialized.extend(quantization);
+        serialized.extend([0; 8]);
+        assert!(Image::deserialize(&serialized).is_err());
+        assert!(Header::deserialize(&serialized).is_err());
+    }
+
+    // Invalid data: dimensions whose number of bits overflows
+    let serialized = vec![
+        103, 114, 110, 102, 108, 100, 52, 50, 255, 255, 255, 255, 255, 255, 255, 255, 136, 128,
+    ];
+    let error = Image::deserialize(&serialized).unwrap_err().to_string();
+    assert!(error.contains("Image too large: 4294967295x4294967295"));
+
     Ok(())
 }
 
@@ -123,6 +140,7 @@ fn image_serialize_deserialize() -> GreenfieldResult<()> {
     Ok(())
 }
 
+#[cfg(feature = "std")]
 #[test]
 /// Should correctly IO an image
 fn image_io() -> GreenfieldResult<()> {
@@ -146,6 +164,7 @@ fn image_io() -> GreenfieldResult<()> {
     Ok(())
 }
 
+#[cfg(feature = "rand")]
 #[test]
 /// Should display an image
 fn image_display() -> GreenfieldResult<()> {
@@ -165,6 +184,7 @@ fn image_display() -> GreenfieldResult<()> {
     Ok(())
 }
 
+#[cfg(feature = "rand")]
 #[test]
 /// Should debug an image
 fn image_debug() -> GreenfieldResult<()> {
@@ -266,3 +286,96 @@ fn image_quantization() -> GreenfieldResult<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "serde")]
+#[test]
+/// Should serialize and deserialize an image as a structured object
+fn image_serde() -> GreenfieldResult<()> {
+    let image = Image::new(
+        2,
+        1,
+        quantization::UniformQuantization::new(5, 6, 5)?,
+        vec![color::Rgb::new(255, 0, 0), color::Rgb::new(0, 0, 255)],
+    )?;
+
+    let serialized = serde_json::to_string(&image).unwrap();
+    assert_eq!(
+        serialized,
+        r##"{"width":2,"height":1,"quantization":[5,6,5],"data":["#fc0204","#0402fc"]}"##
+    );
+
+    let deserialized = serde_json::from_str::<Image>(&serialized).unwrap();
+    assert_eq!(deserialized, image);
+
+    // Invalid data: lower colors than expected
+    let serialized = r##"{"width":2,"height":1,"quantization":[5,6,5],"data":["#fc0204"]}"##;
+    assert!(serde_json::from_str::<Image>(serialized).is_err());
+
+    // Invalid quantization
+    let serialized = r##"{"width":1,"height":1,"quantization":[9,6,5],"data":["#fc0204"]}"##;
+    assert!(serde_json::from_str::<Image>(serialized).is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+/// Should serialize and deserialize an image as a base64 greenfield blob
+fn image_serde_base64() -> GreenfieldResult<()> {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Asset {
+        #[serde(with = "crate::image::base64")]
+        image: Image,
+    }
+
+    let asset = Asset {
+        image: Image::new(
+            1,
+            1,
+            quantization::UniformQuantization::new(8, 8, 8)?,
+            vec![color::Rgb::new(0, 0, 0)],
+        )?,
+    };
+
+    let serialized = serde_json::to_string(&asset).unwrap();
+    assert_eq!(serialized, r#"{"image":"Z3JuZmxkNDIAAAABAAAAAYiAAAAA"}"#);
+
+    let deserialized = serde_json::from_str::<Asset>(&serialized).unwrap();
+    assert_eq!(deserialized, asset);
+
+    // Invalid data: not base64
+    assert!(serde_json::from_str::<Asset>(r#"{"image":"Z3JuZmxkNDI*"}"#).is_err());
+
+    // Invalid data: invalid magic number
+    assert!(serde_json::from_str::<Asset>(r#"{"image":"Z3JuZmxkNDMAAAABAAAAAYiAAAAA"}"#).is_err());
+
+    Ok(())
+}
+
+#[test]
+/// Should read the header of a serialized image
+fn image_header() -> GreenfieldResult<()> {
+    let image = Image::new(
+        3,
+        2,
+        quantization::UniformQuantization::new(5, 6, 5)?,
+        vec![color::Rgb::default(); 6],
+    )?;
+    let serialized = image.clone().serialize()?;
+
+    // The color data is not needed
+    let header = Header::deserialize(&serialized[..Header::BITS.div_ceil(8)])?;
+    assert_eq!(header, image.header());
+    assert_eq!(header.width, 3);
+    assert_eq!(header.height, 2);
+
+    // Invalid data: truncated header
+    let header = Header::deserialize(&serialized[..16]);
+    assert!(header.is_err());
+
+    // Invalid data: invalid magic number
+    let header = Header::deserialize(b"grnfld43");
+    assert!(header.is_err());
+
+    Ok(())
+}
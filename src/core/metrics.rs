@@ -0,0 +1,370 @@
+//! ## Image quality metrics
+//!
+//! Measures how far an image is from a reference one, e.g. how much a quantization hurts it. Each
+//! metric is computed on every channel, and overall:
+//!
+//! - The mean squared error (MSE) of the components, and the PSNR, `10 log10(255² / MSE)` dB.
+//!   The overall PSNR is the one of the overall MSE, the mean of the channels.
+//! - The largest absolute error of a component.
+//! - The structural similarity (SSIM) of Wang et al., 2004: the mean over 11x11 Gaussian windows
+//!   (σ = 1.5) of the similarity of their means, variances and covariance, with `K1 = 0.01` and
+//!   `K2 = 0.03`. 1 for identical images. Images less than 11 pixels wide or high don't have room
+//!   for a window, so their windows shrink to squares of their smaller side (same σ): their SSIM
+//!   isn't comparable to the one of larger images.
+//! - The multi-scale SSIM (MS-SSIM) of Wang et al., 2003: the SSIM of 5 scales of the images,
+//!   halved each time, with the weights of the paper. Images smaller than 176 pixels don't have
+//!   room for every scale, so only the ones that have a whole window are used, with their weights
+//!   scaled to add up to 1.
+//!
+//! The overall SSIM and MS-SSIM are the mean of the channels.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::metrics::Metrics;
+//! use greenfield::prelude::*;
+//!
+//! let colors = (0..64 * 64).map(|i| Rgb::new(i as u8, (i / 64) as u8 * 4, 128)).collect();
+//! let image = Image::new(64, 64, UniformQuantization::new(8, 8, 8)?, colors)?;
+//!
+//! let metrics = Metrics::requantized(&image, &UniformQuantization::new(5, 6, 5)?)?;
+//! assert!(metrics.psnr() > 38.0);
+//! assert!(metrics.ssim() > 0.99);
+//! assert_eq!(metrics.channel_max_error(), [4, 2, 4]);
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::error::{GreenfieldError, GreenfieldResult};
+use crate::image::Image;
+use crate::quantization::UniformQuantization;
+
+#[cfg(test)]
+mod tests;
+
+/// Side of the SSIM windows.
+const WINDOW: usize = 11;
+
+/// Standard deviation of the Gaussian weights of the SSIM windows.
+const SIGMA: f64 = 1.5;
+
+/// Stabilizers of the SSIM luminance and contrast terms: `(K1 * 255)²` and `(K2 * 255)²`.
+const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Weights of the MS-SSIM scales, from the finest.
+const SCALE_WEIGHTS: [f64; 5] = [0.0448, 0.2856, 0.3001, 0.2363, 0.1333];
+
+/// ## Quality metrics of an image against a reference
+///
+/// See the [module documentation](self) for the definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    mse: [f64; 3],
+    max_error: [u8; 3],
+    ssim: [f64; 3],
+    ms_ssim: [f64; 3],
+}
+
+impl Metrics {
+    /// ## Measures an image against a reference
+    ///
+    /// The order of the images doesn't matter.
+    ///
+    /// ## Errors
+    /// If the images don't have the same dimensions.
+    pub fn compare(reference: &Image, image: &Image) -> GreenfieldResult<Self> {
+        let (width, height) = reference.dimensions();
+        if image.dimensions() != (width, height) {
+            let (other_width, other_height) = image.dimensions();
+            return Err(GreenfieldError::MismatchedDimensions(
+                width,
+                height,
+                other_width,
+                other_height,
+            ));
+        }
+
+        let mut metrics = Self {
+            mse: [0.0; 3],
+            max_error: [0; 3],
+            ssim: [1.0; 3],
+            ms_ssim: [1.0; 3],
+        };
+        if width * height == 0 {
+            return Ok(metrics);
+        }
+
+        let mut sums = [0u64; 3];
+        for (a, b) in reference.colors().zip(image.colors()) {
+            for (channel, (a, b)) in a.bytes().into_iter().zip(b.bytes()).enumerate() {
+                let error = a.abs_diff(b);
+                sums[channel] += error as u64 * error as u64;
+                metrics.max_error[channel] = metrics.max_error[channel].max(error);
+            }
+        }
+        metrics.mse = sums.map(|sum| sum as f64 / (width * height) as f64);
+
+        for channel in 0..3 {
+            let x = Plane::new(reference, channel);
+            let y = Plane::new(image, channel);
+            metrics.ssim[channel] = ssim(&x, &y).0;
+            metrics.ms_ssim[channel] = ms_ssim(x, y);
+        }
+
+        Ok(metrics)
+    }
+
+    /// ## Measures an image against itself re-quantized
+    ///
+    /// The colors of the image are quantized again with `quantization`, as in [`Image::new`].
+    ///
+    /// ## Errors
+    /// Never in practice: the re-quantized image has the dimensions of the image.
+    pub fn requantized(
+        image: &Image,
+        quantization: &UniformQuantization,
+    ) -> GreenfieldResult<Self> {
+        let (width, height) = image.dimensions();
+        let colors = image.colors().cloned().collect();
+        let requantized = Image::new(width, height, quantization.clone(), colors)?;
+        Self::compare(image, &requantized)
+    }
+
+    /// ## Mean squared error of the components of all the channels
+    pub fn mse(&self) -> f64 {
+        self.mse.iter().sum::<f64>() / 3.0
+    }
+
+    /// ## Mean squared error of each channel
+    pub fn channel_mse(&self) -> [f64; 3] {
+        self.mse
+    }
+
+    /// ## PSNR of all the channels, in dB
+    ///
+    /// Infinite for identical images.
+    pub fn psnr(&self) -> f64 {
+        psnr(self.mse())
+    }
+
+    /// ## PSNR of each channel, in dB
+    pub fn channel_psnr(&self) -> [f64; 3] {
+        self.mse.map(psnr)
+    }
+
+    /// ## Largest absolute error of a component
+    pub fn max_error(&self) -> u8 {
+        self.max_error.into_iter().max().unwrap_or_default()
+    }
+
+    /// ## Largest absolute error of each channel
+    pub fn channel_max_error(&self) -> [u8; 3] {
+        self.max_error
+    }
+
+    /// ## Mean SSIM of the channels
+    ///
+    /// Over 11x11 windows, or smaller ones in images less than 11 pixels wide or high (see the
+    /// [module documentation](self)).
+    pub fn ssim(&self) -> f64 {
+        self.ssim.iter().sum::<f64>() / 3.0
+    }
+
+    /// ## SSIM of each channel
+    pub fn channel_ssim(&self) -> [f64; 3] {
+        self.ssim
+    }
+
+    /// ## Mean MS-SSIM of the channels
+    pub fn ms_ssim(&self) -> f64 {
+        self.ms_ssim.iter().sum::<f64>() / 3.0
+    }
+
+    /// ## MS-SSIM of each channel
+    pub fn channel_ms_ssim(&self) -> [f64; 3] {
+        self.ms_ssim
+    }
+}
+
+impl Display for Metrics {
+    /// e.g. `MSE 4.12, PSNR 41.98 dB, SSIM 0.9912, MS-SSIM 0.9954, max error 6`
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "MSE {:.2}, PSNR {:.2} dB, SSIM {:.4}, MS-SSIM {:.4}, max error {}",
+            self.mse(),
+            self.psnr(),
+            self.ssim(),
+            self.ms_ssim(),
+            self.max_error()
+        )
+    }
+}
+
+/// PSNR of a mean squared error, in dB.
+fn psnr(mse: f64) -> f64 {
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    10.0 * libm::log10(255.0 * 255.0 / mse)
+}
+
+/// A channel of an image.
+struct Plane {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+impl Plane {
+    /// Channel `channel` (0 for red, 1 for green and 2 for blue) of an image.
+    fn new(image: &Image, channel: usize) -> Self {
+        let (width, height) = image.dimensions();
+        let values = image
+            .colors()
+            .map(|color| color.bytes()[channel] as f64)
+            .collect();
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// The values multiplied by the ones of another plane.
+    fn product(&self, other: &Self) -> Self {
+        Self {
+            values: self
+                .values
+                .iter()
+                .zip(&other.values)
+                .map(|(a, b)| a * b)
+                .collect(),
+            ..*self
+        }
+    }
+
+    /// The weighted sums of the windows of `weights.len()` values that fit in the plane, first
+    /// along the rows and then the columns.
+    fn filter(&self, weights: &[f64]) -> Self {
+        let side = weights.len();
+        let (width, height) = (self.width + 1 - side, self.height + 1 - side);
+        let window = |values: &[f64], stride: usize| {
+            weights
+                .iter()
+                .enumerate()
+                .map(|(i, weight)| weight * values[i * stride])
+                .sum::<f64>()
+        };
+
+        let mut rows = Vec::with_capacity(width * self.height);
+        for y in 0..self.height {
+            let row = &self.values[y * self.width..(y + 1) * self.width];
+            rows.extend((0..width).map(|x| window(&row[x..], 1)));
+        }
+        let mut values = Vec::with_capacity(width * height);
+        for y in 0..height {
+            values.extend((0..width).map(|x| window(&rows[y * width + x..], width)));
+        }
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// The plane at half the size, each value the mean of a 2x2 block (an odd last row or column
+    /// is dropped).
+    fn downsample(&self) -> Self {
+        let (width, height) = (self.width / 2, self.height / 2);
+        let value = |x: usize, y: usize| self.values[y * self.width + x];
+        let values = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                (value(2 * x, 2 * y)
+                    + value(2 * x + 1, 2 * y)
+                    + value(2 * x, 2 * y + 1)
+                    + value(2 * x + 1, 2 * y + 1))
+                    / 4.0
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+}
+
+/// Normalized Gaussian weights of a window side (at most [`WINDOW`]).
+fn gaussian(side: usize) -> Vec<f64> {
+    let center = (side - 1) as f64 / 2.0;
+    let weights: Vec<f64> = (0..side)
+        .map(|i| {
+            let d = i as f64 - center;
+            libm::exp(-d * d / (2.0 * SIGMA * SIGMA))
+        })
+        .collect();
+    let total: f64 = weights.iter().sum();
+    weights.into_iter().map(|w| w / total).collect()
+}
+
+/// The mean SSIM and contrast-structure term of two planes of the same size, over the windows
+/// that fit in them (smaller ones than [`WINDOW`] in small planes).
+fn ssim(x: &Plane, y: &Plane) -> (f64, f64) {
+    let side = WINDOW.min(x.width).min(x.height);
+    if side == 0 {
+        return (1.0, 1.0);
+    }
+
+    let weights = gaussian(side);
+    let (mu_x, mu_y) = (x.filter(&weights), y.filter(&weights));
+    let xx = x.product(x).filter(&weights);
+    let yy = y.product(y).filter(&weights);
+    let xy = x.product(y).filter(&weights);
+
+    let (mut ssim, mut cs) = (0.0, 0.0);
+    for i in 0..mu_x.values.len() {
+        let (mx, my) = (mu_x.values[i], mu_y.values[i]);
+        let variance_x = xx.values[i] - mx * mx;
+        let variance_y = yy.values[i] - my * my;
+        let covariance = xy.values[i] - mx * my;
+
+        let contrast = (2.0 * covariance + C2) / (variance_x + variance_y + C2);
+        let luminance = (2.0 * mx * my + C1) / (mx * mx + my * my + C1);
+        ssim += luminance * contrast;
+        cs += contrast;
+    }
+
+    let windows = mu_x.values.len() as f64;
+    (ssim / windows, cs / windows)
+}
+
+/// The MS-SSIM of two planes of the same size.
+fn ms_ssim(mut x: Plane, mut y: Plane) -> f64 {
+    let mut scales = 1;
+    while scales < SCALE_WEIGHTS.len() && (x.width >> scales).min(x.height >> scales) >= WINDOW {
+        scales += 1;
+    }
+    let total: f64 = SCALE_WEIGHTS[..scales].iter().sum();
+
+    let mut result = 1.0;
+    for (scale, weight) in SCALE_WEIGHTS[..scales].iter().enumerate() {
+        let (ssim, cs) = ssim(&x, &y);
+        // The contrast-structure term of the finer scales, and the whole SSIM of the coarsest.
+        // Negative terms (anticorrelated windows) count as 0.
+        let term = match scale + 1 == scales {
+            true => ssim,
+            false => cs,
+        };
+        result *= libm::pow(term.max(0.0), weight / total);
+        if scale + 1 < scales {
+            (x, y) = (x.downsample(), y.downsample());
+        }
+    }
+    result
+}
@@ -0,0 +1,241 @@
+use super::*;
+use crate::color::Rgb;
+
+/// An image of the given colors, stored losslessly
+fn image(width: usize, height: usize, colors: Vec<Rgb>) -> GreenfieldResult<Image> {
+    Image::new(width, height, UniformQuantization::new(8, 8, 8)?, colors)
+}
+
+/// A smooth gradient with some texture, large enough for every MS-SSIM scale
+fn gradient(side: usize) -> GreenfieldResult<Image> {
+    let colors = (0..side * side)
+        .map(|i| {
+            let (x, y) = (i % side, i / side);
+            let texture = (x * 7 + y * 13) % 23;
+            Rgb::new(
+                (x + texture) as u8,
+                (y + texture) as u8,
+                ((x + y) / 2) as u8,
+            )
+        })
+        .collect();
+    image(side, side, colors)
+}
+
+/// Identical images should have no error and a similarity of 1
+#[test]
+fn metrics_identical() -> GreenfieldResult<()> {
+    let image = gradient(64)?;
+    let metrics = Metrics::compare(&image, &image)?;
+
+    assert_eq!(metrics.mse(), 0.0);
+    assert_eq!(metrics.psnr(), f64::INFINITY);
+    assert_eq!(metrics.channel_psnr(), [f64::INFINITY; 3]);
+    assert_eq!(metrics.max_error(), 0);
+    assert_eq!(metrics.channel_ssim(), [1.0; 3]);
+    assert_eq!(metrics.channel_ms_ssim(), [1.0; 3]);
+
+    Ok(())
+}
+
+/// Errors should be measured per channel and overall
+#[test]
+fn metrics_errors() -> GreenfieldResult<()> {
+    let reference = image(2, 2, vec![Rgb::new(10, 20, 30); 4])?;
+    let image = image(
+        2,
+        2,
+        vec![
+            Rgb::new(12, 20, 27),
+            Rgb::new(12, 20, 27),
+            Rgb::new(8, 20, 33),
+            Rgb::new(8, 20, 30),
+        ],
+    )?;
+    let metrics = Metrics::compare(&reference, &image)?;
+
+    assert_eq!(metrics.channel_mse(), [4.0, 0.0, 6.75]);
+    assert_eq!(metrics.mse(), 10.75 / 3.0);
+    assert_eq!(metrics.channel_max_error(), [2, 0, 3]);
+    assert_eq!(metrics.max_error(), 3);
+
+    let expected = |mse: f64| 10.0 * (255.0 * 255.0 / mse).log10();
+    let [r, g, b] = metrics.channel_psnr();
+    assert!((r - expected(4.0)).abs() < 1e-9);
+    assert_eq!(g, f64::INFINITY);
+    assert!((b - expected(6.75)).abs() < 1e-9);
+    assert!((metrics.psnr() - expected(10.75 / 3.0)).abs() < 1e-9);
+
+    // The order doesn't matter
+    assert_eq!(Metrics::compare(&image, &reference)?, metrics);
+
+    Ok(())
+}
+
+/// Flat images only differ in luminance, so their SSIM is the luminance term
+#[test]
+fn metrics_ssim_flat() -> GreenfieldResult<()> {
+    let a = image(16, 16, vec![Rgb::new(100, 50, 0); 256])?;
+    let b = image(16, 16, vec![Rgb::new(120, 50, 10); 256])?;
+    let metrics = Metrics::compare(&a, &b)?;
+
+    let luminance = |x: f64, y: f64| (2.0 * x * y + C1) / (x * x + y * y + C1);
+    let expected = [luminance(100.0, 120.0), 1.0, luminance(0.0, 10.0)];
+    for (ssim, expected) in metrics.channel_ssim().into_iter().zip(expected) {
+        assert!((ssim - expected).abs() < 1e-12, "{ssim} {expected}");
+    }
+
+    Ok(())
+}
+
+/// Fewer bits should give lower similarities, and noise lower than a smooth error
+#[test]
+fn metrics_ssim_order() -> GreenfieldResult<()> {
+    let image = gradient(176)?;
+
+    let fine = Metrics::requantized(&image, &UniformQuantization::new(6, 6, 6)?)?;
+    let coarse = Metrics::requantized(&image, &UniformQuantization::new(3, 3, 3)?)?;
+    assert!(fine.mse() < coarse.mse());
+    assert!(fine.psnr() > coarse.psnr());
+    assert!(coarse.ssim() < fine.ssim() && fine.ssim() < 1.0);
+    assert!(coarse.ms_ssim() < fine.ms_ssim() && fine.ms_ssim() < 1.0);
+    assert!(coarse.ms_ssim() > 0.0);
+
+    // A shift of every component by 8 and noise of ±8 have the same MSE, but the noise destroys
+    // the structure
+    let colors = |offset: &dyn Fn(usize) -> i32| -> Vec<Rgb> {
+        image
+            .colors()
+            .enumerate()
+            .map(|(i, c)| {
+                let [r, g, b] = c
+                    .bytes()
+                    .map(|v| (v as i32 + offset(i)).clamp(0, 255) as u8);
+                Rgb::new(r, g, b)
+            })
+            .collect()
+    };
+    let shifted = self::image(176, 176, colors(&|_| 8))?;
+    let noisy = self::image(
+        176,
+        176,
+        colors(&|i| match (i * 2_654_435_761) % 7 < 3 {
+            true => 8,
+            false => -8,
+        }),
+    )?;
+    let shifted = Metrics::compare(&image, &shifted)?;
+    let noisy = Metrics::compare(&image, &noisy)?;
+    assert!(noisy.ssim() < shifted.ssim());
+    assert!(noisy.ms_ssim() < shifted.ms_ssim());
+
+    Ok(())
+}
+
+/// Re-quantizing should be the same as comparing with the new image
+#[test]
+fn metrics_requantized() -> GreenfieldResult<()> {
+    let image = gradient(40)?;
+    let quantization = UniformQuantization::new(2, 3, 2)?;
+    let requantized = Image::new(
+        40,
+        40,
+        quantization.clone(),
+        image.colors().cloned().collect(),
+    )?;
+
+    assert_eq!(
+        Metrics::requantized(&image, &quantization)?,
+        Metrics::compare(&image, &requantized)?
+    );
+    // Lossless quantizations don't change anything
+    let lossless = Metrics::requantized(&image, &UniformQuantization::new(8, 8, 8)?)?;
+    assert_eq!(lossless.mse(), 0.0);
+
+    Ok(())
+}
+
+/// Images smaller than a window, or empty, still have metrics
+#[test]
+fn metrics_small() -> GreenfieldResult<()> {
+    let a = image(
+        3,
+        1,
+        vec![Rgb::new(0, 0, 0), Rgb::new(9, 9, 9), Rgb::new(90, 9, 0)],
+    )?;
+    let b = image(
+        3,
+        1,
+        vec![Rgb::new(0, 0, 0), Rgb::new(9, 9, 9), Rgb::new(0, 9, 90)],
+    )?;
+    let metrics = Metrics::compare(&a, &b)?;
+    for value in metrics
+        .channel_ssim()
+        .into_iter()
+        .chain(metrics.channel_ms_ssim())
+    {
+        assert!(value.is_finite() && value <= 1.0);
+    }
+    assert_eq!(metrics.channel_ssim()[1], 1.0);
+
+    let empty = image(0, 0, vec![])?;
+    let metrics = Metrics::compare(&empty, &empty)?;
+    assert_eq!(metrics.mse(), 0.0);
+    assert_eq!(metrics.ssim(), 1.0);
+
+    Ok(())
+}
+
+/// Images smaller than a window should be measured with a window of their smaller side
+#[test]
+fn metrics_ssim_small_window() -> GreenfieldResult<()> {
+    let (x, y): (Vec<u8>, Vec<u8>) = (0..25u8).map(|i| (i * 10, i * 9 + i % 3 * 7)).unzip();
+    let a = image(5, 5, x.iter().map(|&v| Rgb::new(v, 0, 0)).collect())?;
+    let b = image(5, 5, y.iter().map(|&v| Rgb::new(v, 0, 0)).collect())?;
+
+    // A single 5x5 window covers the whole image
+    let weights = gaussian(5);
+    let weight = |i: usize| weights[i % 5] * weights[i / 5];
+    let mean = |f: &dyn Fn(usize) -> f64| (0..25).map(|i| weight(i) * f(i)).sum::<f64>();
+    let (x, y) = (|i: usize| x[i] as f64, |i: usize| y[i] as f64);
+    let (mx, my) = (mean(&x), mean(&y));
+    let variance_x = mean(&|i| x(i) * x(i)) - mx * mx;
+    let variance_y = mean(&|i| y(i) * y(i)) - my * my;
+    let covariance = mean(&|i| x(i) * y(i)) - mx * my;
+    let luminance = (2.0 * mx * my + C1) / (mx * mx + my * my + C1);
+    let contrast = (2.0 * covariance + C2) / (variance_x + variance_y + C2);
+    let expected = luminance * contrast;
+
+    let ssim = Metrics::compare(&a, &b)?.channel_ssim()[0];
+    assert!((ssim - expected).abs() < 1e-9, "{ssim} != {expected}");
+    assert!(ssim < 1.0);
+
+    Ok(())
+}
+
+/// Images of other dimensions can't be compared
+#[test]
+fn metrics_dimensions() -> GreenfieldResult<()> {
+    let a = image(2, 3, vec![Rgb::new(0, 0, 0); 6])?;
+    let b = image(3, 2, vec![Rgb::new(0, 0, 0); 6])?;
+    assert!(matches!(
+        Metrics::compare(&a, &b),
+        Err(GreenfieldError::MismatchedDimensions(2, 3, 3, 2))
+    ));
+
+    Ok(())
+}
+
+/// Should show the overall metrics
+#[test]
+fn metrics_display() -> GreenfieldResult<()> {
+    let reference = image(1, 1, vec![Rgb::new(10, 20, 30)])?;
+    let image = image(1, 1, vec![Rgb::new(12, 20, 30)])?;
+    let metrics = Metrics::compare(&reference, &image)?;
+    assert!(metrics
+        .to_string()
+        .starts_with("MSE 1.33, PSNR 46.88 dB, SSIM "));
+    assert!(metrics.to_string().ends_with(", max error 2"));
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! ## Parallelism
+//!
+//! With the `rayon` feature, compressing and decompressing the colors of an image
+//! ([`UniformQuantization::compress`](crate::quantization::UniformQuantization::compress) and
+//! [`UniformQuantization::decompress`](crate::quantization::UniformQuantization::decompress)) and
+//! quantizing the colors of a new image ([`Image::new`](crate::image::Image::new)) are split in
+//! chunks of pixels that are processed in parallel. The result is the same as on a single thread:
+//! every chunk but the last has a multiple of 8 pixels, so its codes start and end on a byte and
+//! the chunks are packed and unpacked independently.
+//!
+//! Ordered dithering, k-means and octree palettes, the [quantization
+//! optimizer](crate::quantization::Optimizer) and batch conversions are parallel too, on the same
+//! threads.
+//!
+//! By default, the chunks run on the global rayon pool (or on the pool of the caller, inside
+//! `ThreadPool::install`). [`set_threads`] runs them on a pool of its own instead, and `1` runs
+//! them on the calling thread. Without the `rayon` feature, everything runs on the calling thread.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::parallel;
+//! use greenfield::prelude::*;
+//!
+//! // Only use the calling thread
+//! parallel::set_threads(1);
+//! let image = Image::new(2, 1, UniformQuantization::new(5, 6, 5)?, vec![Rgb::new(9, 9, 9); 2])?;
+//!
+//! // Back to the global pool
+//! parallel::set_threads(0);
+//! assert_eq!(parallel::threads(), 0);
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "rayon")]
+use std::sync::{Arc, Mutex, PoisonError};
+
+#[cfg(test)]
+mod tests;
+
+/// Pixels per chunk: a multiple of 8, so that chunks of codes start on a byte.
+pub(crate) const CHUNK: usize = 1 << 14;
+
+/// The number of threads set with [`set_threads`].
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// The pool of [`set_threads`], if any.
+#[cfg(feature = "rayon")]
+static POOL: Mutex<Option<Arc<rayon::ThreadPool>>> = Mutex::new(None);
+
+/// ## Sets the number of threads that process images
+///
+/// `0` (the default) uses the current rayon pool, `1` the calling thread only, and any other
+/// number a pool of that many threads. If the pool can't be made, the current rayon pool is used.
+/// Without the `rayon` feature, it has no effect.
+pub fn set_threads(threads: usize) {
+    THREADS.store(threads, Ordering::Relaxed);
+
+    #[cfg(feature = "rayon")]
+    {
+        let pool = match threads {
+            0 | 1 => None,
+            threads => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .ok()
+                .map(Arc::new),
+        };
+        *POOL.lock().unwrap_or_else(PoisonError::into_inner) = pool;
+    }
+}
+
+/// ## The number of threads set with [`set_threads`]
+pub fn threads() -> usize {
+    THREADS.load(Ordering::Relaxed)
+}
+
+/// Maps the chunks of `0..len` with `map`, in parallel if enabled, and returns the results in
+/// order.
+pub(crate) fn map_chunks<R: Send>(len: usize, map: impl Fn(Range<usize>) -> R + Sync) -> Vec<R> {
+    let chunks = (0..len)
+        .step_by(CHUNK)
+        .map(|start| start..(start + CHUNK).min(len));
+
+    #[cfg(feature = "rayon")]
+    if threads() != 1 && len > CHUNK {
+        let chunks = chunks.collect::<Vec<_>>();
+        return install(|| chunks.into_par_iter().map(&map).collect());
+    }
+
+    chunks.map(map).collect()
+}
+
+/// Maps every item with `map`, in parallel if enabled, and returns the results in order. Unlike
+/// [`map_chunks`], each item is a task of its own, for a few costly items.
+pub(crate) fn map<T: Sync, R: Send>(items: &[T], map: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    #[cfg(feature = "rayon")]
+    if threads() != 1 {
+        return install(|| items.par_iter().map(&map).collect());
+    }
+
+    items.iter().map(map).collect()
+}
+
+/// Runs `run` on the pool of [`set_threads`], or on the current rayon pool if there's none.
+#[cfg(feature = "rayon")]
+fn install<R: Send>(run: impl FnOnce() -> R + Send) -> R {
+    let pool = POOL.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    match pool {
+        Some(pool) => pool.install(run),
+        None => run(),
+    }
+}
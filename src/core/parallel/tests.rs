@@ -0,0 +1,110 @@
+use super::*;
+use crate::color::Rgb;
+use crate::image::Image;
+use crate::quantization::{ColorSpace, Quantizer, UniformQuantization};
+use crate::GreenfieldResult;
+use deku::bitvec::{BitVec, Msb0};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+
+/// Serializes the tests that set the number of threads, which is global
+static THREADS_LOCK: Mutex<()> = Mutex::new(());
+
+/// More colors than fit in a chunk, and not a multiple of 8 of them
+fn colors() -> Vec<Rgb> {
+    (0..3 * CHUNK as u32 + 5)
+        .map(|i| {
+            let [_, r, g, b] = i.wrapping_mul(0x9E37_79B9).to_be_bytes();
+            Rgb::new(r, g, b)
+        })
+        .collect()
+}
+
+/// Chunks should cover the range in order
+#[test]
+fn parallel_map_chunks() {
+    for len in [0, 1, CHUNK, CHUNK + 1, 5 * CHUNK + 3] {
+        let chunks = map_chunks(len, |range| range);
+        assert!(chunks
+            .iter()
+            .all(|range| range.len() <= CHUNK && !range.is_empty()));
+        assert_eq!(
+            chunks.into_iter().flatten().collect::<Vec<_>>(),
+            (0..len).collect::<Vec<_>>()
+        );
+    }
+}
+
+/// Compressing, decompressing and quantizing new images should give the same result on any
+/// number of threads
+#[test]
+fn parallel_identical() -> GreenfieldResult<()> {
+    let _lock = THREADS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let colors = colors();
+    let quantizations = [
+        UniformQuantization::new(5, 6, 5)?,
+        UniformQuantization::new(3, 2, 2)?,
+        UniformQuantization::new(7, 7, 5)?.with_color_space(ColorSpace::YCbCr),
+    ];
+
+    for quantization in quantizations {
+        // A bit at a time, on the calling thread
+        let bits = quantization.bits_per_pixel();
+        let mut serial_compressed = BitVec::<u8, Msb0>::new();
+        for color in &colors {
+            let code = quantization.quantize(color);
+            serial_compressed.extend((0..bits).rev().map(|i| code >> i & 1 == 1));
+        }
+        let serial_colors: Vec<_> = colors.iter().map(|c| quantization.reconstruct(c)).collect();
+
+        for threads in [1, 0, 3] {
+            set_threads(threads);
+            let compressed = quantization.compress(&colors);
+            assert_eq!(
+                compressed, serial_compressed,
+                "{quantization}, {threads} threads"
+            );
+            assert_eq!(quantization.decompress(&compressed), serial_colors);
+
+            let image = Image::new(colors.len(), 1, quantization.clone(), colors.clone())?;
+            assert!(image.colors().eq(&serial_colors));
+        }
+    }
+    set_threads(0);
+
+    Ok(())
+}
+
+/// With a single thread, everything should run on the calling thread
+#[test]
+fn parallel_single_thread() {
+    let _lock = THREADS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let caller = thread::current().id();
+
+    set_threads(1);
+    let items = (0..64).collect::<Vec<usize>>();
+    assert!(map(&items, |_| thread::current().id())
+        .into_iter()
+        .all(|id| id == caller));
+    assert!(map_chunks(5 * CHUNK, |_| thread::current().id())
+        .into_iter()
+        .all(|id| id == caller));
+    set_threads(0);
+}
+
+/// With more threads, everything should run on a pool of that many threads
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_pool() {
+    let _lock = THREADS_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    set_threads(3);
+    let items = (0..64).collect::<Vec<usize>>();
+    assert!(map(&items, |_| rayon::current_num_threads())
+        .into_iter()
+        .all(|threads| threads == 3));
+    assert!(map_chunks(5 * CHUNK, |_| rayon::current_num_threads())
+        .into_iter()
+        .all(|threads| threads == 3));
+    set_threads(0);
+}
@@ -43,9 +43,10 @@
 #[cfg(test)]
 mod tests;
 
-use std::fmt::{Display, Formatter};
+use core::fmt::{Display, Formatter};
 
 use super::color;
+#[cfg(feature = "colored")]
 use colored::Colorize;
 
 /// ## Pixel struct
@@ -100,10 +101,16 @@ impl<'a> Display for Pixel<'a> {
     ///     println!("{}", pixel);
     /// }
     /// ```
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let Self { x, y, color } = self;
-        let color::Rgb { r, g, b } = color;
-        let pixel = format!("({},{})", x, y);
-        write!(f, "{}", pixel.truecolor(*r, *g, *b))
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let Self { x, y, .. } = self;
+        let pixel = alloc::format!("({},{})", x, y);
+
+        #[cfg(feature = "colored")]
+        let pixel = {
+            let color::Rgb { r, g, b } = self.color;
+            pixel.truecolor(*r, *g, *b)
+        };
+
+        write!(f, "{}", pixel)
     }
 }
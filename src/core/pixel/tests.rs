@@ -16,6 +16,7 @@ fn pixel_new() {
     assert_eq!(*b, 0);
 }
 
+#[cfg(feature = "rand")]
 #[test]
 /// Should Display a pixel
 fn pixel_display() {
@@ -25,6 +26,7 @@ fn pixel_display() {
     println!("{}", pixel);
 }
 
+#[cfg(feature = "rand")]
 #[test]
 /// Should debug a pixel
 fn pixel_debug() {
@@ -17,6 +17,26 @@
 //! Reducing each component to 5 bits, we now have 2^5 = 32 possible values for each component.
 //! Each distinct value is the mean of the interval in the RGB color space.
 //!
+//! ## Quantizers
+//!
+//! Quantization schemes implement the [`Quantizer`] trait: they map colors to codes, codes back to
+//! colors, and read/write their parameters from/to the image header. The schemes that can be
+//! recorded in an image header are the variants of [`Quantization`], which is what
+//! [`Image`](crate::image::Image) stores. [`UniformQuantization`] is one of them.
+//!
+//! [`Quantization`] is deliberately a closed set: a header is only readable by a decoder that
+//! knows its quantizer, so there are no user-defined quantizers, and each variant is identified by
+//! a fixed 4 bit tag. Tags 0 to 9 and 15 are taken, and 10 to 14 are reserved for new quantizers
+//! of this crate. Custom [`Quantizer`]s can still be used on colors directly, just not recorded in
+//! an image.
+//!
+//! ## Palettes
+//!
+//! A [`Palette`] quantizes each color to the index of its nearest palette color, with palettes
+//! adapted to each image. They can be built with the median cut algorithm
+//! ([`Palette::median_cut`]), with k-means (`KMeans`, with the `rand` feature) for a lower
+//! error, or with an [`Octree`] when speed matters most.
+//!
 //! ## Examples
 //!
 //! ```rust
@@ -25,12 +45,8 @@
 //! fn quantization_new_ok() -> GreenfieldResult<()> {
 //!     let quantization = UniformQuantization::new(1, 1, 1)?;
 //!     assert_eq!(
-//!         quantization,
-//!         UniformQuantization {
-//!             bits_r: 1,
-//!             bits_g: 1,
-//!             bits_b: 1
-//!         }
+//!         (quantization.bits_r, quantization.bits_g, quantization.bits_b),
+//!         (1, 1, 1)
 //!     );
 //!
 //!     Ok(())
@@ -65,12 +81,8 @@
 //! fn quantization_default() -> GreenfieldResult<()> {
 //!     let quantization = UniformQuantization::default();
 //!     assert_eq!(
-//!         quantization,
-//!         UniformQuantization {
-//!             bits_r: 8,
-//!             bits_g: 8,
-//!             bits_b: 8
-//!         }
+//!         (quantization.bits_r, quantization.bits_g, quantization.bits_b),
+//!         (8, 8, 8)
 //!     );
 //!
 //!     Ok(())
@@ -297,14 +309,35 @@
 #[cfg(test)]
 mod tests;
 
-use std::fmt::Display;
+#[cfg(feature = "rand")]
+pub mod kmeans;
+pub mod levels;
+pub mod octree;
+#[cfg(feature = "std")]
+pub mod optimizer;
+mod packing;
+pub mod palette;
+pub mod policy;
+#[cfg(feature = "rand")]
+pub use kmeans::KMeans;
+pub use levels::LevelsQuantization;
+pub use octree::Octree;
+#[cfg(feature = "std")]
+pub use optimizer::{Optimizer, Target};
+pub use palette::Palette;
+pub use policy::{Bounds, ColorSpace, Companding, Curve, Lut, Reconstruction, Rounding};
+
+#[allow(unused_imports)] // Used by the deku derives without std
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Display;
 
 use crate::error::{GreenfieldError, GreenfieldResult};
 
 use super::color;
 use deku::prelude::*;
+use policy::Param;
 
-use bitvec::prelude::*;
 use deku::bitvec::{BitSlice, BitVec, Msb0};
 
 /// ## A quantization information structure.
@@ -318,22 +351,129 @@ use deku::bitvec::{BitSlice, BitVec, Msb0};
 /// = 65536 different colors in total. In this case, instead of 3 bytes to encode a RGB color, we
 /// just use 2 bytes.
 ///
+/// How the components are mapped to their indexes and back is set by its [`Rounding`] and
+/// [`Reconstruction`] policies, in a [`ColorSpace`], after companding them with a [`Curve`] each
+/// and over their [`Bounds`] (see [`policy`]). They are set with the `with_*` methods, e.g.
+/// [`UniformQuantization::with_rounding`]. The tables of a [`Lut`] must fit the bits: if the bits
+/// of a component change after they were set, its indexes are reconstructed to midpoints, and
+/// images can't be made or written with the quantization.
+///
 /// This structure is Deku serializable, with each entry occupying only 4 bits in disk (12 bits in
-/// total)
-#[derive(Debug, Eq, Clone, PartialEq, DekuRead, DekuWrite)]
-#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+/// total). Quantizations with other than the default policies are also preceded by a `15` tag and
+/// followed by their policies.
+#[derive(Debug, Eq, Clone, PartialEq)]
 pub struct UniformQuantization {
-    #[deku(bits = "4")]
     pub bits_r: u8,
-    #[deku(bits = "4")]
     pub bits_g: u8,
-    #[deku(bits = "4")]
     pub bits_b: u8,
+    /// How the components are mapped to their indexes
+    pub(crate) rounding: Rounding,
+    /// How the indexes are mapped back to components
+    pub(crate) reconstruction: Reconstruction,
+    /// The color space of the components
+    pub(crate) color_space: ColorSpace,
+    /// The curves the components are companded with before being quantized
+    pub(crate) companding: Companding,
+    /// The range of each component the intervals are spread over
+    pub(crate) bounds: Bounds,
+}
+
+/// Tag of the uniform quantizations stored with their policies.
+const EXTENDED: u8 = 15;
+
+impl DekuWrite<deku::ctx::Endian> for UniformQuantization {
+    fn write(
+        &self,
+        output: &mut BitVec<u8, Msb0>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(), DekuError> {
+        let nibble = deku::ctx::BitSize(4);
+        let params = self.params();
+
+        if !params.is_empty() {
+            EXTENDED.write(output, (endian, nibble))?;
+        }
+        for bits in self.bits() {
+            bits.write(output, (endian, nibble))?;
+        }
+        if !params.is_empty() {
+            for param in params.iter().chain([&Param::End]) {
+                param.write(output, (endian, self.bits()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> DekuRead<'a, deku::ctx::Endian> for UniformQuantization {
+    /// ## Errors
+    /// If the bits of a component are not between 1 and 8, which would make the colors undecodable.
+    fn read(
+        input: &'a BitSlice<u8, Msb0>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(&'a BitSlice<u8, Msb0>, Self), DekuError> {
+        let nibble = deku::ctx::BitSize(4);
+        let (rest, tag) = u8::read(input, (endian, nibble))?;
+        let extended = tag == EXTENDED;
+
+        // Without the tag, the first 4 bits are the bits of the red component
+        let mut rest = if extended { rest } else { input };
+        let mut bits = [0u8; 3];
+        for component in &mut bits {
+            (rest, *component) = u8::read(rest, (endian, nibble))?;
+        }
+
+        let [bits_r, bits_g, bits_b] = bits;
+        if !bits.iter().all(|bits| (1..=8).contains(bits)) {
+            return Err(GreenfieldError::InvalidQuantizationLevel(bits_r, bits_g, bits_b).into());
+        }
+        let mut quantization = Self {
+            bits_r,
+            bits_g,
+            bits_b,
+            ..Default::default()
+        };
+        if extended {
+            loop {
+                let (remaining, param) = Param::read(rest, (endian, bits))?;
+                rest = remaining;
+                match param {
+                    Param::End => break,
+                    Param::Rounding(rounding) => quantization.rounding = rounding,
+                    Param::Reconstruction(reconstruction) => {
+                        quantization.reconstruction = reconstruction
+                    }
+                    Param::ColorSpace(color_space) => quantization.color_space = color_space,
+                    Param::Companding(companding) => quantization.companding = companding,
+                    Param::Bounds(bounds) => quantization.bounds = bounds,
+                }
+            }
+        }
+
+        Ok((rest, quantization))
+    }
 }
 
 impl Display for UniformQuantization {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}, {}, {})", self.bits_r, self.bits_g, self.bits_b)
+    /// The policies are only shown when they aren't the default, e.g. `(1, 1, 1; nearest,
+    /// replicate)`, and the color space when it isn't RGB, e.g. `(6, 3, 3; truncate, midpoint,
+    /// ycbcr)`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "({}, {}, {}", self.bits_r, self.bits_g, self.bits_b)?;
+        if !self.params().is_empty() {
+            write!(f, "; {}, {}", self.rounding, self.reconstruction)?;
+        }
+        if self.color_space != ColorSpace::Rgb {
+            write!(f, ", {}", self.color_space)?;
+        }
+        if !self.companding.is_identity() {
+            write!(f, ", {}", self.companding)?;
+        }
+        if !self.bounds.is_full() {
+            write!(f, ", {}", self.bounds)?;
+        }
+        write!(f, ")")
     }
 }
 
@@ -353,12 +493,8 @@ impl Default for UniformQuantization {
     /// fn quantization_default() -> GreenfieldResult<()> {
     ///     let quantization = UniformQuantization::default();
     ///     assert_eq!(
-    ///         quantization,
-    ///         UniformQuantization {
-    ///             bits_r: 8,
-    ///             bits_g: 8,
-    ///             bits_b: 8
-    ///         }
+    ///         (quantization.bits_r, quantization.bits_g, quantization.bits_b),
+    ///         (8, 8, 8)
     ///     );
     ///
     ///     Ok(())
@@ -369,10 +505,163 @@ impl Default for UniformQuantization {
             bits_r: 8,
             bits_g: 8,
             bits_b: 8,
+            rounding: Rounding::default(),
+            reconstruction: Reconstruction::default(),
+            color_space: ColorSpace::default(),
+            companding: Companding::default(),
+            bounds: Bounds::default(),
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for UniformQuantization {
+    /// ## Serializes the quantization as a `(bits_r, bits_g, bits_b)` tuple.
+    ///
+    /// Quantizations with other than the default policies are `(bits_r, bits_g, bits_b, rounding,
+    /// reconstruction)` tuples instead, followed by the color space, the companding curves and the
+    /// bounds, up to the last one that isn't the default.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (bits_r, bits_g, bits_b) = (self.bits_r, self.bits_g, self.bits_b);
+        let (rounding, reconstruction) = (self.rounding, &self.reconstruction);
+        let (color_space, companding) = (self.color_space, &self.companding);
+
+        if self.params().is_empty() {
+            (bits_r, bits_g, bits_b).serialize(serializer)
+        } else if !self.bounds.is_full() {
+            let tuple = (
+                bits_r,
+                bits_g,
+                bits_b,
+                rounding,
+                reconstruction,
+                color_space,
+                companding,
+                self.bounds,
+            );
+            tuple.serialize(serializer)
+        } else if !companding.is_identity() {
+            let tuple = (
+                bits_r,
+                bits_g,
+                bits_b,
+                rounding,
+                reconstruction,
+                color_space,
+                companding,
+            );
+            tuple.serialize(serializer)
+        } else if color_space != ColorSpace::Rgb {
+            let tuple = (
+                bits_r,
+                bits_g,
+                bits_b,
+                rounding,
+                reconstruction,
+                color_space,
+            );
+            tuple.serialize(serializer)
+        } else {
+            (bits_r, bits_g, bits_b, rounding, reconstruction).serialize(serializer)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for UniformQuantization {
+    /// ## Deserializes the quantization from a `(bits_r, bits_g, bits_b)` tuple.
+    ///
+    /// The tuple may also have the rounding and reconstruction policies, and then the color space,
+    /// the companding curves and the bounds. It's validated the same way as in
+    /// [`UniformQuantization::new`], [`UniformQuantization::with_reconstruction`],
+    /// [`Companding::new`] and [`Bounds::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Tuple {
+            Bits(u8, u8, u8),
+            Policies(u8, u8, u8, Rounding, Reconstruction),
+            Space(u8, u8, u8, Rounding, Reconstruction, ColorSpace),
+            Companding(u8, u8, u8, Rounding, Reconstruction, ColorSpace, Companding),
+            Bounds(
+                u8,
+                u8,
+                u8,
+                Rounding,
+                Reconstruction,
+                ColorSpace,
+                Companding,
+                Bounds,
+            ),
+        }
+
+        let (bits, rounding, reconstruction, color_space, companding, bounds) =
+            match Tuple::deserialize(deserializer)? {
+                Tuple::Bits(r, g, b) => (
+                    [r, g, b],
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                ),
+                Tuple::Policies(r, g, b, rounding, reconstruction) => (
+                    [r, g, b],
+                    rounding,
+                    reconstruction,
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                ),
+                Tuple::Space(r, g, b, rounding, reconstruction, color_space) => (
+                    [r, g, b],
+                    rounding,
+                    reconstruction,
+                    color_space,
+                    Default::default(),
+                    Default::default(),
+                ),
+                Tuple::Companding(r, g, b, rounding, reconstruction, color_space, companding) => (
+                    [r, g, b],
+                    rounding,
+                    reconstruction,
+                    color_space,
+                    companding,
+                    Default::default(),
+                ),
+                Tuple::Bounds(
+                    r,
+                    g,
+                    b,
+                    rounding,
+                    reconstruction,
+                    color_space,
+                    companding,
+                    bounds,
+                ) => (
+                    [r, g, b],
+                    rounding,
+                    reconstruction,
+                    color_space,
+                    companding,
+                    bounds,
+                ),
+            };
+        let [bits_r, bits_g, bits_b] = bits;
+
+        Self::new(bits_r, bits_g, bits_b)
+            .and_then(|quantization| quantization.with_reconstruction(reconstruction))
+            .map(|quantization| {
+                quantization
+                    .with_rounding(rounding)
+                    .with_color_space(color_space)
+                    .with_companding(companding)
+                    .with_bounds(bounds)
+            })
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl UniformQuantization {
     /// ## Creates a new UniformQuantization structure.
     ///
@@ -385,12 +674,8 @@ impl UniformQuantization {
     /// fn quantization_new_ok() -> GreenfieldResult<()> {
     ///     let quantization = UniformQuantization::new(1, 1, 1)?;
     ///     assert_eq!(
-    ///         quantization,
-    ///         UniformQuantization {
-    ///             bits_r: 1,
-    ///             bits_g: 1,
-    ///             bits_b: 1
-    ///         }
+    ///         (quantization.bits_r, quantization.bits_g, quantization.bits_b),
+    ///         (1, 1, 1)
     ///     );
     ///
     ///     Ok(())
@@ -427,6 +712,7 @@ impl UniformQuantization {
                 bits_r,
                 bits_g,
                 bits_b,
+                ..Default::default()
             }),
             _ => Err(GreenfieldError::InvalidQuantizationLevel(
                 bits_r, bits_g, bits_b,
@@ -434,12 +720,352 @@ impl UniformQuantization {
         }
     }
 
+    /// ## Sets how the components are mapped to their indexes
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::{Reconstruction, Rounding};
+    ///
+    /// // 2 bits, reconstructed to 0, 85, 170 and 255
+    /// let quantization =
+    ///     UniformQuantization::new(2, 2, 2)?.with_reconstruction(Reconstruction::Replicate)?;
+    /// assert_eq!(quantization.reconstruct(&Rgb::new(60, 60, 60)), Rgb::new(0, 0, 0));
+    ///
+    /// // 60 is nearer to 85
+    /// let quantization = quantization.with_rounding(Rounding::Nearest);
+    /// assert_eq!(quantization.reconstruct(&Rgb::new(60, 60, 60)), Rgb::new(85, 85, 85));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn with_rounding(self, rounding: Rounding) -> Self {
+        Self { rounding, ..self }
+    }
+
+    /// ## Sets how the indexes are mapped back to components
+    ///
+    /// ## Errors
+    /// If the reconstruction is a [`Lut`] whose tables don't have `2^bits` entries, for the bits of
+    /// their components.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::Reconstruction;
+    ///
+    /// let quantization =
+    ///     UniformQuantization::new(1, 1, 1)?.with_reconstruction(Reconstruction::Replicate)?;
+    /// assert_eq!(quantization.get_dequantized_color(&Rgb::new(0, 1, 1)), Rgb::new(0, 255, 255));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn with_reconstruction(self, reconstruction: Reconstruction) -> GreenfieldResult<Self> {
+        if let Reconstruction::Lookup(lut) = &reconstruction {
+            lut.check(self.bits())?;
+        }
+
+        Ok(Self {
+            reconstruction,
+            ..self
+        })
+    }
+
+    /// ## Sets the color space whose components are quantized
+    ///
+    /// With midpoint reconstruction, neutral chroma is kept, so grays stay gray.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::ColorSpace;
+    ///
+    /// // 6 bits of luma, and 3 of each chroma
+    /// let quantization = UniformQuantization::new(6, 3, 3)?.with_color_space(ColorSpace::YCbCr);
+    /// let gray = Rgb::new(100, 100, 100);
+    /// assert_eq!(quantization.get_quantized_color(&gray), Rgb::new(25, 4, 4));
+    /// assert_eq!(quantization.get_dequantized_color(&Rgb::new(25, 4, 4)), Rgb::new(102, 102, 102));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn with_color_space(self, color_space: ColorSpace) -> Self {
+        Self {
+            color_space,
+            ..self
+        }
+    }
+
+    /// ## Sets the curves the components are companded with
+    ///
+    /// The components are companded before being quantized, and expanded once reconstructed (see
+    /// [`Curve`]).
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::{Companding, Curve};
+    ///
+    /// // µ-law on the green component only
+    /// let companding = Companding::new([Curve::Identity, Curve::Log(255), Curve::Identity])?;
+    /// let quantization = UniformQuantization::new(3, 3, 3)?.with_companding(companding);
+    /// assert_eq!(quantization.to_string(), "(3, 3, 3; truncate, midpoint, identity/log 255/identity)");
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn with_companding(self, companding: Companding) -> Self {
+        Self { companding, ..self }
+    }
+
+    /// ## Sets the range of each component the intervals are spread over
+    ///
+    /// See [`Bounds`], and [`UniformQuantization::adapt`] to get them from an image.
+    pub fn with_bounds(self, bounds: Bounds) -> Self {
+        Self { bounds, ..self }
+    }
+
+    /// How the components are mapped to their indexes
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    /// How the indexes are mapped back to components
+    pub fn reconstruction(&self) -> &Reconstruction {
+        &self.reconstruction
+    }
+
+    /// The color space of the components
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// The curves the components are companded with before being quantized
+    pub fn companding(&self) -> &Companding {
+        &self.companding
+    }
+
+    /// The range of each component the intervals are spread over
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// ## Spreads the intervals over the range of each component of an image
+    ///
+    /// The bounds are the minimum and maximum of each component, in the color space and after the
+    /// companding of the quantization. Images without colors keep the full range.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    ///
+    /// let colors = (0..64).map(|v| Rgb::new(v, v / 2, 10)).collect();
+    /// let image = Image::new(8, 8, UniformQuantization::default(), colors)?;
+    ///
+    /// let quantization = UniformQuantization::new(6, 5, 1)?.adapt(&image);
+    /// assert_eq!(quantization.bounds().min(), [0, 0, 10]);
+    /// assert_eq!(quantization.bounds().max(), [63, 31, 10]);
+    ///
+    /// // Every color is kept
+    /// let adapted = Image::new(8, 8, quantization, image.colors().cloned().collect())?;
+    /// assert_eq!(adapted.colors().collect::<Vec<_>>(), image.colors().collect::<Vec<_>>());
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn adapt(self, image: &crate::image::Image) -> Self {
+        let bounds = self.percentile_bounds(image, 0.0, 100.0);
+        // The minimum and maximum can't cross
+        self.with_bounds(bounds.unwrap_or_default())
+    }
+
+    /// ## Spreads the intervals over percentiles of each component of an image
+    ///
+    /// Like [`UniformQuantization::adapt`], but the bounds are the `low` and `high` percentiles
+    /// (from 0 to 100) of each component, so that a few outliers don't widen the range. The
+    /// components out of it are clamped.
+    ///
+    /// ## Errors
+    /// If a bound from `low` is above the one from `high`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    ///
+    /// // A dark image, with a single bright pixel
+    /// let mut colors = vec![Rgb::new(20, 30, 40); 99];
+    /// colors.push(Rgb::new(255, 255, 255));
+    /// let image = Image::new(10, 10, UniformQuantization::default(), colors)?;
+    ///
+    /// let quantization = UniformQuantization::new(4, 4, 4)?.adapt_percentiles(&image, 0.0, 95.0)?;
+    /// assert_eq!(quantization.bounds().max(), [20, 30, 40]);
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn adapt_percentiles(
+        self,
+        image: &crate::image::Image,
+        low: f64,
+        high: f64,
+    ) -> GreenfieldResult<Self> {
+        let bounds = self.percentile_bounds(image, low, high)?;
+        Ok(self.with_bounds(bounds))
+    }
+
+    /// The `low` and `high` percentiles of each component of an image (nearest rank).
+    fn percentile_bounds(
+        &self,
+        image: &crate::image::Image,
+        low: f64,
+        high: f64,
+    ) -> GreenfieldResult<Bounds> {
+        let mut histograms = [[0usize; 256]; 3];
+        for color in image.colors() {
+            let components = self.color_space.components(color);
+            for (channel, value) in components.into_iter().enumerate() {
+                histograms[channel][self.companding.compand(channel, value) as usize] += 1;
+            }
+        }
+
+        let count = image.colors().count();
+        if count == 0 {
+            return Ok(Bounds::default());
+        }
+        let rank = |percentile: f64, up: bool| {
+            let rank = percentile.clamp(0.0, 100.0) / 100.0 * (count - 1) as f64;
+            match rank as usize {
+                floor if up && (floor as f64) < rank => floor + 1,
+                floor => floor,
+            }
+        };
+        // The value of the component at a rank of the sorted components
+        let value = |channel: usize, rank: usize| {
+            let mut seen = 0;
+            (0..=u8::MAX)
+                .find(|&value| {
+                    seen += histograms[channel][value as usize];
+                    seen > rank
+                })
+                .unwrap_or(u8::MAX)
+        };
+
+        let (low, high) = (rank(low, false), rank(high, true));
+        Bounds::new(
+            [0, 1, 2].map(|channel| value(channel, low)),
+            [0, 1, 2].map(|channel| value(channel, high)),
+        )
+    }
+
+    /// The bits of the red, green and blue components.
+    fn bits(&self) -> [u8; 3] {
+        [self.bits_r, self.bits_g, self.bits_b]
+    }
+
+    /// The reconstruction of the indexes of a component. A [`Lut`] table that doesn't fit the bits
+    /// anymore (they changed since it was set) can't be used, so they're reconstructed to midpoints.
+    fn component_reconstruction(&self, channel: usize) -> &Reconstruction {
+        match &self.reconstruction {
+            Reconstruction::Lookup(lut)
+                if lut.tables()[channel].len() != 1 << self.bits()[channel] =>
+            {
+                &Reconstruction::Midpoint
+            }
+            reconstruction => reconstruction,
+        }
+    }
+
+    /// Checks that the tables of a [`Lut`] reconstruction fit the bits, which may have changed
+    /// since it was set.
+    pub(crate) fn check(&self) -> GreenfieldResult<()> {
+        match &self.reconstruction {
+            Reconstruction::Lookup(lut) => lut.check(self.bits()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The policies that aren't the default, as stored in the header.
+    fn params(&self) -> Vec<Param> {
+        let mut params = Vec::new();
+        if self.rounding != Rounding::default() {
+            params.push(Param::Rounding(self.rounding));
+        }
+        if self.reconstruction != Reconstruction::default() {
+            params.push(Param::Reconstruction(self.reconstruction.clone()));
+        }
+        if self.color_space != ColorSpace::default() {
+            params.push(Param::ColorSpace(self.color_space));
+        }
+        if !self.companding.is_identity() {
+            params.push(Param::Companding(self.companding.clone()));
+        }
+        if !self.bounds.is_full() {
+            params.push(Param::Bounds(self.bounds));
+        }
+        params
+    }
+
+    /// Whether quantizing and reconstructing leaves every color unchanged, with the full bounds.
+    fn is_lossless(&self) -> bool {
+        self.bits() == [8; 3]
+            && !matches!(self.reconstruction, Reconstruction::Lookup(_))
+            && self.color_space == ColorSpace::Rgb
+            && self.companding.is_identity()
+    }
+
+    /// The index of a component (0 for red, 1 for green and 2 for blue).
+    fn quantize_component(&self, channel: usize, value: u8) -> u8 {
+        let bits = self.bits()[channel];
+        let truncated = value >> (8 - bits);
+
+        match (self.rounding, self.component_reconstruction(channel)) {
+            (Rounding::Truncate, _) => truncated,
+            (Rounding::Nearest, Reconstruction::Lookup(lut)) => lut.nearest(channel, value),
+            // The other reconstructions are sorted and at most an interval apart from the
+            // truncated one. Ties are kept in the interval of the component.
+            (Rounding::Nearest, reconstruction) => {
+                let last = ((1u16 << bits) - 1) as u8;
+                [
+                    truncated,
+                    truncated.saturating_sub(1),
+                    truncated.saturating_add(1).min(last),
+                ]
+                .into_iter()
+                .min_by_key(|&index| {
+                    reconstruction
+                        .component(channel, index, bits)
+                        .unwrap_or_default()
+                        .abs_diff(value)
+                })
+                .unwrap_or(truncated)
+            }
+        }
+    }
+
+    /// Half an interval for the chroma components (1 and 2) of other color spaces than RGB, so that
+    /// the midpoints include their neutral value, 128 (see [`ColorSpace`]).
+    fn chroma_offset(&self, channel: usize) -> u8 {
+        match (
+            self.color_space,
+            self.component_reconstruction(channel),
+            channel,
+        ) {
+            (ColorSpace::Rgb, _, _) | (_, _, 0) => 0,
+            (_, Reconstruction::Midpoint, _) => ((1u16 << (8 - self.bits()[channel])) / 2) as u8,
+            _ => 0,
+        }
+    }
+
+    /// The component reconstructed from an index (0 for red, 1 for green and 2 for blue).
+    fn dequantize_component(&self, channel: usize, index: u8) -> u8 {
+        self.component_reconstruction(channel)
+            .component(channel, index, self.bits()[channel])
+            .unwrap_or_default()
+    }
+
     /// ## Return a new color, quantized to the given number of bits. Immutable version of
     /// [`quantify_color`].
     ///
     /// The quantization is done by dividing the color space in equal intervals, and then assigning a
     /// index to each interval, this is accomplished using bitwise magic 🧙 ((x >> (8 - bits_x)) puts a
-    /// component in a specific interval).
+    /// component in a specific interval). With [`Rounding::Nearest`], the index is the one whose
+    /// reconstruction is nearest to the component instead. The components are the ones of the
+    /// [`ColorSpace`] of the quantization.
     ///
     /// ## Examples
     ///
@@ -487,28 +1113,49 @@ impl UniformQuantization {
     /// }
     /// ```
     pub fn get_quantized_color(&self, color: &color::Rgb) -> color::Rgb {
-        let color::Rgb { r, g, b } = *color;
-
-        match &self {
-            Self {
-                bits_r: 8,
-                bits_g: 8,
-                bits_b: 8,
-            } => color::Rgb::new(r, g, b),
+        self.get_quantized_color_within(color, &self.bounds)
+    }
 
-            Self {
-                bits_r,
-                bits_g,
-                bits_b,
-            } => color::Rgb::new(r >> 8 - bits_r, g >> 8 - bits_g, b >> 8 - bits_b),
+    /// ## Return a new color, quantized with its components spread over other bounds than the
+    /// ones of the quantization.
+    ///
+    /// Same as [`UniformQuantization::get_quantized_color`], which uses the bounds of the
+    /// quantization (see [`Bounds`]).
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::Bounds;
+    ///
+    /// let quantization = UniformQuantization::new(1, 1, 1)?;
+    /// let dark = Bounds::new([0, 0, 0], [60, 60, 60])?;
+    ///
+    /// let color = Rgb::new(10, 40, 200);
+    /// assert_eq!(quantization.get_quantized_color(&color), Rgb::new(0, 0, 1));
+    /// assert_eq!(quantization.get_quantized_color_within(&color, &dark), Rgb::new(0, 1, 1));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn get_quantized_color_within(&self, color: &color::Rgb, bounds: &Bounds) -> color::Rgb {
+        if self.is_lossless() && bounds.is_full() {
+            return color.clone();
         }
+
+        let [r, g, b] = self.color_space.components(color);
+        let index = |channel, value| {
+            let value = bounds.stretch(channel, self.companding.compand(channel, value));
+            self.quantize_component(channel, value.saturating_add(self.chroma_offset(channel)))
+        };
+        color::Rgb::new(index(0, r), index(1, g), index(2, b))
     }
 
     /// ## Quantify a color in place, given a number of bits. Mutable version of [`get_quantized_color`].
     ///
     /// The quantization is done by dividing the color space in equal intervals, and then assigning a
     /// index to each interval, this is accomplished using bitwise magic 🧙 ((x >> (8 - bits_x)) puts a
-    /// component in a specific interval).
+    /// component in a specific interval). With [`Rounding::Nearest`], the index is the one whose
+    /// reconstruction is nearest to the component instead. The components are the ones of the
+    /// [`ColorSpace`] of the quantization.
     ///
     /// ## Examples
     ///
@@ -556,23 +1203,7 @@ impl UniformQuantization {
     /// }
     /// ```
     pub fn quantify_color(&self, color: &mut color::Rgb) {
-        match &self {
-            Self {
-                bits_r: 8,
-                bits_g: 8,
-                bits_b: 8,
-            } => (),
-
-            Self {
-                bits_r,
-                bits_g,
-                bits_b,
-            } => {
-                color.r >>= 8 - bits_r;
-                color.g >>= 8 - bits_g;
-                color.b >>= 8 - bits_b;
-            }
-        }
+        *color = self.get_quantized_color(color);
     }
 
     /// ## Return a new color, dequantized to the given number of bits. Immutable version of `quantization::dequantify_color`.
@@ -581,6 +1212,9 @@ impl UniformQuantization {
     /// component to his index interval and then summing the "middle of the interval" to the total
     /// value. This is done using the bitwise wizardry 🧙 ((x << (8 - bits_x)) to align a component to his
     /// interval and then summing the middle of the interval (1 << (7 - bits_x) to the total value.
+    /// That's [`Reconstruction::Midpoint`], the default: other reconstructions can be set with
+    /// [`UniformQuantization::with_reconstruction`]. The components are then converted back from
+    /// the [`ColorSpace`] of the quantization.
     ///
     /// ## Examples
     ///
@@ -622,24 +1256,42 @@ impl UniformQuantization {
     /// }
     /// ```
     pub fn get_dequantized_color(&self, color: &color::Rgb) -> color::Rgb {
-        let color::Rgb { r, g, b } = *color;
+        self.get_dequantized_color_within(color, &self.bounds)
+    }
 
-        match &self {
-            Self {
-                bits_r: 8,
-                bits_g: 8,
-                bits_b: 8,
-            } => color::Rgb::new(r, g, b),
-            Self {
-                bits_r,
-                bits_g,
-                bits_b,
-            } => color::Rgb::new(
-                (r << (8 - bits_r)) + (1 << (7 - bits_r)),
-                (g << (8 - bits_g)) + (1 << (7 - bits_g)),
-                (b << (8 - bits_b)) + (1 << (7 - bits_b)),
-            ),
+    /// ## Return a new color, dequantized with its components spread over other bounds than the
+    /// ones of the quantization.
+    ///
+    /// Same as [`UniformQuantization::get_dequantized_color`], which uses the bounds of the
+    /// quantization (see [`Bounds`]). The bounds must be the ones the color was quantized with.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::Bounds;
+    ///
+    /// let quantization = UniformQuantization::new(1, 1, 1)?;
+    /// let dark = Bounds::new([0, 0, 0], [60, 60, 60])?;
+    ///
+    /// let color = Rgb::new(0, 1, 1);
+    /// assert_eq!(quantization.get_dequantized_color(&color), Rgb::new(64, 192, 192));
+    /// assert_eq!(quantization.get_dequantized_color_within(&color, &dark), Rgb::new(15, 45, 45));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn get_dequantized_color_within(&self, color: &color::Rgb, bounds: &Bounds) -> color::Rgb {
+        if self.is_lossless() && bounds.is_full() {
+            return color.clone();
         }
+
+        let color::Rgb { r, g, b } = *color;
+        let value = |channel, index| {
+            let value = self.dequantize_component(channel, index);
+            let value = bounds.shrink(channel, value.saturating_sub(self.chroma_offset(channel)));
+            self.companding.expand(channel, value)
+        };
+        self.color_space
+            .color([value(0, r), value(1, g), value(2, b)])
     }
     /// ## Dequantify a color in place, to the given number of bits. Mutable version of `quantization::get_dequantized_color`.
     ///
@@ -647,6 +1299,9 @@ impl UniformQuantization {
     /// component to his index interval and then summing the "middle of the interval" to the total
     /// value. This is done using the bitwise wizardry 🧙 ((x << (8 - bits_x)) to align a component to his
     /// interval and then summing the middle of the interval (1 << (7 - bits_x) to the total value.
+    /// That's [`Reconstruction::Midpoint`], the default: other reconstructions can be set with
+    /// [`UniformQuantization::with_reconstruction`]. The components are then converted back from
+    /// the [`ColorSpace`] of the quantization.
     ///
     /// ## Examples
     ///
@@ -688,22 +1343,7 @@ impl UniformQuantization {
     /// }
     /// ```
     pub fn dequantify_color(&self, color: &mut color::Rgb) {
-        match &self {
-            Self {
-                bits_r: 8,
-                bits_g: 8,
-                bits_b: 8,
-            } => (),
-            Self {
-                bits_r,
-                bits_g,
-                bits_b,
-            } => {
-                color.r = (color.r << (8 - bits_r)) + (1 << (7 - bits_r));
-                color.g = (color.g << (8 - bits_g)) + (1 << (7 - bits_g));
-                color.b = (color.b << (8 - bits_b)) + (1 << (7 - bits_b));
-            }
-        }
+        *color = self.get_dequantized_color(color);
     }
 
     /// ## Decompress a BitSlice containing color data into a Vec of colors.
@@ -714,6 +1354,10 @@ impl UniformQuantization {
     /// (using bitwise wizardry 🧙 again) we extract the r, g and b components from their respective positions
     /// and then we dequantize them to the original color values.
     ///
+    /// The codes are read a word at a time, and in RGB the components are dequantized with a table
+    /// computed once per call. 8-8-8, 5-6-5, 4-4-4 and 3-3-2 codes are read straight from the bytes.
+    /// Large images are read by chunks of pixels in parallel (see [`crate::parallel`]).
+    ///
     /// ## Examples
     /// ```rust
     /// /// A compressed BitSlice should be correctly decompressed to a Vec of Colors
@@ -748,22 +1392,32 @@ impl UniformQuantization {
     /// }
     /// ```
     pub fn decompress<'a>(&'a self, data: &'a BitSlice<u8, Msb0>) -> Vec<color::Rgb> {
-        let Self {
-            bits_r,
-            bits_g,
-            bits_b,
-        } = &self;
-        let data_size = bits_r + bits_g + bits_b;
+        let bits = self.bits_per_pixel();
+        let indexes = |code: u32| {
+            let [r, g, b] = self.split(code);
+            color::Rgb::new(r, g, b)
+        };
 
-        data.chunks_exact(data_size as usize)
-            .map(|chunk| {
-                let r = chunk[0..*bits_r as usize].load_be::<u8>();
-                let g = chunk[*bits_r as usize..(*bits_r + *bits_g) as usize].load_be::<u8>();
-                let b = chunk[(*bits_r + *bits_g) as usize..].load_be::<u8>();
+        if let Some(tables) = self.tables() {
+            let [r, g, b] = &tables.dequantize;
+            return packing::unpack_chunks(data, bits, |code| {
+                let [ri, gi, bi] = self.split(code);
+                color::Rgb::new(r[ri as usize], g[gi as usize], b[bi as usize])
+            });
+        }
 
-                self.get_dequantized_color(&color::Rgb::new(r, g, b))
-            })
-            .collect::<Vec<_>>()
+        // Other color spaces mix the components, but the colors of every code can still be
+        // computed once when there are more pixels than codes
+        let count = data.len() / bits;
+        if bits <= 16 && count >= 1 << bits {
+            let colors: Vec<_> = (0..1u32 << bits)
+                .map(|code| self.get_dequantized_color(&indexes(code)))
+                .collect();
+            return packing::unpack_chunks(data, bits, |code| colors[code as usize].clone());
+        }
+        packing::unpack_chunks(data, bits, |code| {
+            self.get_dequantized_color(&indexes(code))
+        })
     }
 
     /// ## Compress a Vec of colors into a BitVec containing the compressed data.
@@ -773,6 +1427,10 @@ impl UniformQuantization {
     /// So, we iterate over the Vec of colors and (using bitwise wizardry 🧙 again) we insert the r, g and b
     /// components into their respective positions (using only the respective needed bits ).
     ///
+    /// As in [`UniformQuantization::decompress`], the components are quantized with tables in RGB,
+    /// and the codes are written a word at a time, by chunks of pixels in parallel (see
+    /// [`crate::parallel`]).
+    ///
     /// ## Examples
     /// ```rust
     /// /// A Vec of Colors should be correctly compressed to a BitSlice
@@ -806,27 +1464,393 @@ impl UniformQuantization {
     ///     Ok(())
     /// }
     /// ```
-    pub fn compress(&self, colors: &Vec<color::Rgb>) -> BitVec<u8, Msb0> {
-        let Self {
-            bits_r,
-            bits_g,
-            bits_b,
-        } = &self;
-        let data_size = (bits_r + bits_g + bits_b) as usize;
-        let mut compressed_data = BitVec::<u8, Msb0>::repeat(false, colors.len() * data_size);
+    pub fn compress(&self, colors: &[color::Rgb]) -> BitVec<u8, Msb0> {
+        let bits = self.bits_per_pixel();
+
+        match self.tables() {
+            Some(tables) => {
+                let [r, g, b] = &tables.quantize;
+                packing::pack_chunks(colors, bits, |color| {
+                    self.join([
+                        r[color.r as usize],
+                        g[color.g as usize],
+                        b[color.b as usize],
+                    ])
+                })
+            }
+            None => packing::pack_chunks(colors, bits, |color| {
+                self.join(self.get_quantized_color(color).bytes())
+            }),
+        }
+    }
+
+    /// The code of the indexes of the components, red in the most significant bits.
+    fn join(&self, [r, g, b]: [u8; 3]) -> u32 {
+        ((r as u32) << (self.bits_g + self.bits_b)) | ((g as u32) << self.bits_b) | b as u32
+    }
 
-        for (i, color) in colors.iter().enumerate() {
-            let color::Rgb { r, g, b } = self.get_quantized_color(color);
-            let index = i * data_size;
+    /// The indexes of the components of a code (see [`UniformQuantization::join`]).
+    fn split(&self, code: u32) -> [u8; 3] {
+        let mask = |bits: u8| (1u32 << bits) - 1;
+        [
+            (code >> (self.bits_g + self.bits_b)) as u8,
+            ((code >> self.bits_b) & mask(self.bits_g)) as u8,
+            (code & mask(self.bits_b)) as u8,
+        ]
+    }
+
+    /// The index of every value and the value of every index of each component, when the color
+    /// space doesn't mix them (only RGB), so that colors don't have to be quantized one by one.
+    ///
+    /// The tables are made with [`UniformQuantization::get_quantized_color`] and
+    /// [`UniformQuantization::get_dequantized_color`], so they give the same colors.
+    fn tables(&self) -> Option<Tables> {
+        if self.color_space != ColorSpace::Rgb {
+            return None;
+        }
+
+        let mut tables = Tables {
+            quantize: [[0; 256]; 3],
+            dequantize: [[0; 256]; 3],
+        };
+        let last = self.bits().map(|bits| ((1u16 << bits) - 1) as u8);
+        for value in 0..=255u8 {
+            let gray = color::Rgb::new(value, value, value);
+            let quantized = self.get_quantized_color(&gray).bytes();
+            let [r, g, b] = last.map(|last| value.min(last));
+            let dequantized = self
+                .get_dequantized_color(&color::Rgb::new(r, g, b))
+                .bytes();
+            for channel in 0..3 {
+                tables.quantize[channel][value as usize] = quantized[channel];
+                tables.dequantize[channel][value as usize] = dequantized[channel];
+            }
+        }
+        Some(tables)
+    }
+}
+
+/// Lookup tables of a [`UniformQuantization`], per component.
+struct Tables {
+    /// The index of every component value.
+    quantize: [[u8; 256]; 3],
+    /// The component value of every index (the ones past the last index are unused).
+    dequantize: [[u8; 256]; 3],
+}
 
-            compressed_data[index..(index + *bits_r as usize)].store_be(r);
-            compressed_data
-                [(index + *bits_r as usize)..(index + *bits_r as usize + *bits_g as usize)]
-                .store_be(g);
-            compressed_data[(index + *bits_r as usize + *bits_g as usize)..(index + data_size)]
-                .store_be(b);
+/// ## A color quantization scheme
+///
+/// A quantizer maps each color to a code of [`Quantizer::bits_per_pixel`] bits, and each code back
+/// to the color it represents. The codes are what is stored on disk: [`Quantizer::compress`] packs
+/// them MSB first, one after the other. The parameters of the quantizer (e.g. the bits of each
+/// component or a palette) are stored in the image header, before the codes.
+///
+/// [`UniformQuantization`] is the default implementation, and [`Quantization`] is the closed set of
+/// quantizers that can be recorded in an image header. Other implementations can quantize colors,
+/// but not be stored in an [`Image`](crate::image::Image).
+///
+/// ## Examples
+///
+/// ```rust
+/// use greenfield::prelude::*;
+/// use greenfield::quantization::Quantizer;
+///
+/// let quantization = UniformQuantization::new(5, 6, 5)?;
+/// let code = quantization.quantize(&Rgb::new(255, 0, 255));
+///
+/// assert_eq!(quantization.bits_per_pixel(), 16);
+/// assert_eq!(code, 0b11111_000000_11111);
+/// assert_eq!(quantization.dequantize(code), Rgb::new(252, 2, 252));
+/// # Ok::<(), GreenfieldError>(())
+/// ```
+pub trait Quantizer {
+    /// ## Number of bits of each code
+    fn bits_per_pixel(&self) -> usize;
+
+    /// ## Returns the code of the color that best represents `color`
+    fn quantize(&self, color: &color::Rgb) -> u32;
+
+    /// ## Returns the color represented by `code`
+    fn dequantize(&self, code: u32) -> color::Rgb;
+
+    /// ## Writes the parameters of the quantizer, as stored in an image header
+    fn write_params(&self, output: &mut BitVec<u8, Msb0>) -> GreenfieldResult<()>;
+
+    /// ## Reads the parameters of the quantizer, as stored in an image header
+    ///
+    /// ## Returns
+    /// The remaining bits and the quantizer.
+    fn read_params(input: &BitSlice<u8, Msb0>) -> GreenfieldResult<(&BitSlice<u8, Msb0>, Self)>
+    where
+        Self: Sized;
+
+    /// ## Returns the code of a color, as a color
+    ///
+    /// This is the "index space" of the quantizer. By default the code is split in the three bytes
+    /// of the color, the most significant in `r`.
+    fn quantize_color(&self, color: &color::Rgb) -> color::Rgb {
+        let [_, r, g, b] = self.quantize(color).to_be_bytes();
+        color::Rgb::new(r, g, b)
+    }
+
+    /// ## Returns the color represented by a code, given as a color (see [`Quantizer::quantize_color`])
+    fn dequantize_color(&self, color: &color::Rgb) -> color::Rgb {
+        self.dequantize(u32::from_be_bytes([0, color.r, color.g, color.b]))
+    }
+
+    /// ## Returns the color that `color` is reconstructed to after quantization
+    fn reconstruct(&self, color: &color::Rgb) -> color::Rgb {
+        self.dequantize(self.quantize(color))
+    }
+
+    /// ## Distance between neighboring reconstructions, per component
+    ///
+    /// Scales the noise of ordered dithering. By default it's estimated as if the bits of the
+    /// codes were split evenly between the components.
+    fn spacing(&self) -> [u16; 3] {
+        [256 >> (self.bits_per_pixel() / 3).min(8); 3]
+    }
+
+    /// ## Compresses colors to their codes, packed MSB first
+    fn compress(&self, colors: &[color::Rgb]) -> BitVec<u8, Msb0> {
+        let codes = colors.iter().map(|color| self.quantize(color));
+        packing::pack(codes, colors.len(), self.bits_per_pixel())
+    }
+
+    /// ## Decompresses packed codes to the colors they represent
+    ///
+    /// Trailing bits that don't make a whole code are ignored.
+    fn decompress(&self, data: &BitSlice<u8, Msb0>) -> Vec<color::Rgb> {
+        packing::unpack(data, self.bits_per_pixel(), |code| self.dequantize(code))
+    }
+}
+
+impl Quantizer for UniformQuantization {
+    fn bits_per_pixel(&self) -> usize {
+        (self.bits_r + self.bits_g + self.bits_b) as usize
+    }
+
+    /// The code is the concatenation of the indexes of the components.
+    fn quantize(&self, color: &color::Rgb) -> u32 {
+        let color::Rgb { r, g, b } = self.get_quantized_color(color);
+        ((r as u32) << (self.bits_g + self.bits_b)) | ((g as u32) << self.bits_b) | b as u32
+    }
+
+    fn dequantize(&self, code: u32) -> color::Rgb {
+        let mask = |bits: u8| (1u32 << bits) - 1;
+        let r = (code >> (self.bits_g + self.bits_b)) & mask(self.bits_r);
+        let g = (code >> self.bits_b) & mask(self.bits_g);
+        let b = code & mask(self.bits_b);
+
+        self.get_dequantized_color(&color::Rgb::new(r as u8, g as u8, b as u8))
+    }
+
+    fn write_params(&self, output: &mut BitVec<u8, Msb0>) -> GreenfieldResult<()> {
+        Ok(self.write(output, deku::ctx::Endian::Big)?)
+    }
+
+    fn read_params(input: &BitSlice<u8, Msb0>) -> GreenfieldResult<(&BitSlice<u8, Msb0>, Self)> {
+        let (rest, quantization) = Self::read(input, deku::ctx::Endian::Big)?;
+        Self::new(
+            quantization.bits_r,
+            quantization.bits_g,
+            quantization.bits_b,
+        )?;
+        Ok((rest, quantization))
+    }
+
+    /// The components are quantized separately: the index space is the color of the indexes.
+    fn quantize_color(&self, color: &color::Rgb) -> color::Rgb {
+        self.get_quantized_color(color)
+    }
+
+    fn dequantize_color(&self, color: &color::Rgb) -> color::Rgb {
+        self.get_dequantized_color(color)
+    }
+
+    fn reconstruct(&self, color: &color::Rgb) -> color::Rgb {
+        self.get_dequantized_color(&self.get_quantized_color(color))
+    }
+
+    /// The mean distance between the reconstructions of each component: the width of the
+    /// intervals, for midpoint reconstructions. Narrower bounds make them closer.
+    fn spacing(&self) -> [u16; 3] {
+        [0, 1, 2].map(|channel| {
+            let last = (1u16 << self.bits()[channel]) - 1;
+            let (min, max) = (0..=last as u8)
+                .map(|index| self.dequantize_component(channel, index))
+                .fold((u8::MAX, u8::MIN), |(min, max), c| (min.min(c), max.max(c)));
+            let width = (self.bounds.max()[channel] - self.bounds.min()[channel]) as u16;
+            (max.saturating_sub(min) as u16 * width / (255 * last)).max(1)
+        })
+    }
+
+    fn compress(&self, colors: &[color::Rgb]) -> BitVec<u8, Msb0> {
+        UniformQuantization::compress(self, colors)
+    }
+
+    fn decompress(&self, data: &BitSlice<u8, Msb0>) -> Vec<color::Rgb> {
+        UniformQuantization::decompress(self, data)
+    }
+}
+
+/// ## The quantizers that can be recorded in an image header
+///
+/// Stored on disk as a 4 bit tag followed by the parameters of the quantizer. Uniform
+/// quantizations have no tag: their first 4 bits (`bits_r`, between 1 and 8) double as it, so
+/// images from before other quantizers existed are still valid. Uniform quantizations with other
+/// than the default policies are tagged `15` (see [`policy`]), and levels quantizations `9`. The
+/// other tags (10 to 14) are reserved for new quantizers (see the [module documentation](self)).
+#[derive(Debug, Eq, Clone, PartialEq, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "4",
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian"
+)]
+pub enum Quantization {
+    /// Palette quantization (see [`Palette`]).
+    #[deku(id = "0")]
+    Palette(Palette),
+    /// Uniform quantization (see [`UniformQuantization`]).
+    #[deku(id_pat = "1..=8 | 15")]
+    Uniform(UniformQuantization),
+    /// Levels quantization (see [`LevelsQuantization`]).
+    #[deku(id = "9")]
+    Levels(LevelsQuantization),
+}
+
+/// Calls a [`Quantizer`] method on the quantizer of a [`Quantization`].
+macro_rules! dispatch {
+    ($quantization:expr, $quantizer:ident => $call:expr) => {
+        match $quantization {
+            Quantization::Palette($quantizer) => $call,
+            Quantization::Uniform($quantizer) => $call,
+            Quantization::Levels($quantizer) => $call,
+        }
+    };
+}
+
+impl Quantizer for Quantization {
+    fn bits_per_pixel(&self) -> usize {
+        dispatch!(self, q => Quantizer::bits_per_pixel(q))
+    }
+
+    fn quantize(&self, color: &color::Rgb) -> u32 {
+        dispatch!(self, q => Quantizer::quantize(q, color))
+    }
+
+    fn dequantize(&self, code: u32) -> color::Rgb {
+        dispatch!(self, q => Quantizer::dequantize(q, code))
+    }
+
+    fn write_params(&self, output: &mut BitVec<u8, Msb0>) -> GreenfieldResult<()> {
+        Ok(self.write(output, deku::ctx::Endian::Big)?)
+    }
+
+    /// The parameters of the quantizer are validated.
+    fn read_params(input: &BitSlice<u8, Msb0>) -> GreenfieldResult<(&BitSlice<u8, Msb0>, Self)> {
+        let (rest, quantization) = Self::read(input, deku::ctx::Endian::Big)?;
+        match &quantization {
+            Self::Palette(_) | Self::Levels(_) => (),
+            Self::Uniform(q) => {
+                UniformQuantization::new(q.bits_r, q.bits_g, q.bits_b)?;
+            }
+        };
+        Ok((rest, quantization))
+    }
+
+    fn quantize_color(&self, color: &color::Rgb) -> color::Rgb {
+        dispatch!(self, q => Quantizer::quantize_color(q, color))
+    }
+
+    fn dequantize_color(&self, color: &color::Rgb) -> color::Rgb {
+        dispatch!(self, q => Quantizer::dequantize_color(q, color))
+    }
+
+    fn reconstruct(&self, color: &color::Rgb) -> color::Rgb {
+        dispatch!(self, q => Quantizer::reconstruct(q, color))
+    }
+
+    fn spacing(&self) -> [u16; 3] {
+        dispatch!(self, q => Quantizer::spacing(q))
+    }
+
+    fn compress(&self, colors: &[color::Rgb]) -> BitVec<u8, Msb0> {
+        dispatch!(self, q => Quantizer::compress(q, colors))
+    }
+
+    fn decompress(&self, data: &BitSlice<u8, Msb0>) -> Vec<color::Rgb> {
+        dispatch!(self, q => Quantizer::decompress(q, data))
+    }
+}
+
+impl Default for Quantization {
+    fn default() -> Self {
+        Self::Uniform(UniformQuantization::default())
+    }
+}
+
+impl From<UniformQuantization> for Quantization {
+    fn from(quantization: UniformQuantization) -> Self {
+        Self::Uniform(quantization)
+    }
+}
+
+impl From<Palette> for Quantization {
+    fn from(palette: Palette) -> Self {
+        Self::Palette(palette)
+    }
+}
+
+impl From<LevelsQuantization> for Quantization {
+    fn from(quantization: LevelsQuantization) -> Self {
+        Self::Levels(quantization)
+    }
+}
+
+impl PartialEq<UniformQuantization> for Quantization {
+    fn eq(&self, other: &UniformQuantization) -> bool {
+        matches!(self, Self::Uniform(quantization) if quantization == other)
+    }
+}
+
+impl Display for Quantization {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        dispatch!(self, q => q.fmt(f))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Quantization {
+    /// ## Serializes the quantization as its quantizer
+    ///
+    /// Uniform quantizations are `(bits_r, bits_g, bits_b)` tuples, palettes lists of colors and
+    /// levels quantizations `{"levels": [levels_r, levels_g, levels_b]}` objects.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        dispatch!(self, q => q.serialize(serializer))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Quantization {
+    /// ## Deserializes the quantization from its quantizer
+    ///
+    /// Tuples are uniform quantizations, lists of colors are palettes, and objects with levels are
+    /// levels quantizations.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Quantizers {
+            Uniform(UniformQuantization),
+            Palette(Palette),
+            Levels(LevelsQuantization),
         }
 
-        compressed_data
+        Ok(match Quantizers::deserialize(deserializer)? {
+            Quantizers::Uniform(quantization) => Self::Uniform(quantization),
+            Quantizers::Palette(palette) => Self::Palette(palette),
+            Quantizers::Levels(quantization) => Self::Levels(quantization),
+        })
     }
 }
@@ -0,0 +1,233 @@
+//! ## K-means palettes
+//!
+//! Builds a [`Palette`] that minimizes the quantization error of an image with Lloyd's algorithm:
+//! every color is assigned to its nearest palette color, each palette color is moved to the mean
+//! of the colors assigned to it, and that's repeated until the palette stops moving. The initial
+//! palette is picked with k-means++, which spreads it over the colors of the image.
+//!
+//! K-means is slower than [`Palette::median_cut`], but its palettes have a lower error. The
+//! initialization is random, so a seed is used to make the results reproducible. The seed drives a
+//! ChaCha8 generator, so a seed gives the same palette on every platform and version of `rand`.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use greenfield::quantization::KMeans;
+//!
+//! let colors = vec![
+//!     Rgb::new(250, 10, 10),
+//!     Rgb::new(255, 0, 0),
+//!     Rgb::new(0, 0, 250),
+//!     Rgb::new(10, 10, 255),
+//! ];
+//! let image = Image::new(2, 2, UniformQuantization::default(), colors.clone())?;
+//!
+//! let palette = KMeans::new(2).seed(42).max_iterations(10).run(&image)?;
+//! let mut palette = palette.colors().to_vec();
+//! palette.sort_by_key(|c| c.bytes());
+//!
+//! assert_eq!(palette, vec![Rgb::new(5, 5, 253), Rgb::new(253, 5, 5)]);
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::vec::Vec;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::palette::{self, Palette};
+use crate::color::Rgb;
+use crate::error::{GreenfieldError, GreenfieldResult};
+use crate::image::Image;
+use crate::parallel;
+
+#[cfg(test)]
+mod tests;
+
+/// A palette color while it's being refined.
+type Center = [f64; 3];
+
+/// ## K-means palette builder
+///
+/// Configures and runs the k-means quantizer. By default, the seed is `0`, at most
+/// [`KMeans::DEFAULT_MAX_ITERATIONS`] iterations are run, and the iterations stop when no palette
+/// color moves more than [`KMeans::DEFAULT_THRESHOLD`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KMeans {
+    size: usize,
+    seed: u64,
+    max_iterations: usize,
+    threshold: f64,
+}
+
+impl KMeans {
+    /// Default maximum number of iterations.
+    pub const DEFAULT_MAX_ITERATIONS: usize = 64;
+
+    /// Default convergence threshold, as a distance in the RGB space.
+    pub const DEFAULT_THRESHOLD: f64 = 0.5;
+
+    /// ## Creates a builder of palettes of at most `size` colors
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            seed: 0,
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+            threshold: Self::DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// ## Sets the seed of the k-means++ initialization
+    ///
+    /// The same image, size and seed always build the same palette.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// ## Sets the maximum number of iterations
+    ///
+    /// With `0` iterations, the palette is the k-means++ initialization.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// ## Sets the convergence threshold
+    ///
+    /// The iterations stop once no palette color moves more than `threshold` (in the RGB space).
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// ## Builds the palette of an image
+    ///
+    /// If the image has less than `size` distinct colors, the palette is exactly those colors.
+    ///
+    /// ## Errors
+    /// - If `size` is 0 or more than [`Palette::MAX_SIZE`].
+    /// - If the image is empty.
+    pub fn run(&self, image: &Image) -> GreenfieldResult<Palette> {
+        if !(1..=Palette::MAX_SIZE).contains(&self.size) {
+            return Err(GreenfieldError::InvalidPaletteSize(self.size));
+        }
+
+        let colors = palette::histogram(image.colors());
+        if colors.is_empty() {
+            return Err(GreenfieldError::InvalidPaletteSize(0));
+        }
+
+        let mut centers = self.initialize(&colors);
+        for _ in 0..self.max_iterations {
+            let (updated, shift) = update(&colors, &centers);
+            centers = updated;
+
+            if shift <= self.threshold {
+                break;
+            }
+        }
+
+        Palette::new(
+            centers
+                .iter()
+                .map(|c| {
+                    let [r, g, b] = c.map(|v| v.round().clamp(0.0, 255.0) as u8);
+                    Rgb::new(r, g, b)
+                })
+                .collect(),
+        )
+    }
+
+    /// K-means++: the first center is a random pixel, and each of the next ones is a pixel picked
+    /// with probability proportional to its squared distance to the nearest center.
+    fn initialize(&self, colors: &[(Rgb, usize)]) -> Vec<Center> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut centers = Vec::with_capacity(self.size);
+        let mut distances = alloc::vec![f64::INFINITY; colors.len()];
+        let mut weights = colors
+            .iter()
+            .map(|(_, count)| *count as f64)
+            .collect::<Vec<f64>>();
+
+        while centers.len() < self.size {
+            // Every color is a center already, so there are no more candidates
+            let Ok(candidates) = WeightedIndex::new(&weights) else {
+                break;
+            };
+            let center = center(&colors[candidates.sample(&mut rng)].0);
+            centers.push(center);
+
+            for (i, (color, count)) in colors.iter().enumerate() {
+                distances[i] = distances[i].min(distance(&center, color));
+                weights[i] = distances[i] * *count as f64;
+            }
+        }
+
+        centers
+    }
+}
+
+/// One iteration of Lloyd's algorithm. Returns the new centers and how much they moved at most.
+fn update(colors: &[(Rgb, usize)], centers: &[Center]) -> (Vec<Center>, f64) {
+    let nearest = |(color, _): &(Rgb, usize)| {
+        centers
+            .iter()
+            .map(|c| distance(c, color))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+            .unwrap_or_default()
+    };
+
+    let assignments = parallel::map_chunks(colors.len(), |chunk| {
+        colors[chunk].iter().map(nearest).collect::<Vec<usize>>()
+    })
+    .concat();
+
+    // Integer sums, so the result doesn't depend on the order of the additions
+    let mut sums = alloc::vec![([0u64; 3], 0u64); centers.len()];
+    for ((color, count), &i) in colors.iter().zip(&assignments) {
+        let (sum, total) = &mut sums[i];
+        for (s, c) in sum.iter_mut().zip(color.bytes()) {
+            *s += c as u64 * *count as u64;
+        }
+        *total += *count as u64;
+    }
+
+    let mut shift = 0f64;
+    let updated = centers
+        .iter()
+        .zip(&sums)
+        .map(|(center, (sum, total))| match total {
+            // Centers without colors stay where they are
+            0 => *center,
+            _ => {
+                let mean = sum.map(|s| s as f64 / *total as f64);
+                let moved = mean
+                    .iter()
+                    .zip(center)
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum::<f64>();
+                shift = shift.max(moved.sqrt());
+                mean
+            }
+        })
+        .collect();
+
+    (updated, shift)
+}
+
+fn center(color: &Rgb) -> Center {
+    color.bytes().map(|c| c as f64)
+}
+
+/// Squared euclidean distance between a center and a color.
+fn distance(center: &Center, color: &Rgb) -> f64 {
+    center
+        .iter()
+        .zip(color.bytes())
+        .map(|(a, b)| (a - b as f64).powi(2))
+        .sum()
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::quantization::tests::{self, row};
+use crate::quantization::{Quantizer, UniformQuantization};
+
+/// A gradient with clusters around a few colors
+fn gradient() -> GreenfieldResult<Image> {
+    tests::gradient(64, 64, UniformQuantization::new(8, 8, 8)?, |i| {
+        let base = [(240, 20, 20), (20, 240, 20), (20, 20, 240), (128, 128, 128)][i % 4];
+        let noise = ((i * 37) % 23) as u8;
+        Rgb::new(base.0 + noise / 2, base.1 + noise / 3, base.2 + noise / 4)
+    })
+}
+
+/// Sum of the squared errors of quantizing an image to a palette
+fn error(image: &Image, palette: &Palette) -> u64 {
+    image
+        .colors()
+        .map(|c| palette::distance(c, &palette.dequantize(palette.quantize(c))) as u64)
+        .sum()
+}
+
+/// Should find the clusters of the image
+#[test]
+fn kmeans_clusters() -> GreenfieldResult<()> {
+    let image = row(vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(2, 2, 2),
+        Rgb::new(200, 0, 0),
+        Rgb::new(202, 2, 0),
+        Rgb::new(0, 0, 200),
+        Rgb::new(0, 2, 202),
+    ])?;
+
+    let palette = KMeans::new(3).seed(7).run(&image)?;
+    let mut colors = palette.colors().to_vec();
+    colors.sort_by_key(|c| c.bytes());
+    assert_eq!(
+        colors,
+        vec![Rgb::new(0, 1, 201), Rgb::new(1, 1, 1), Rgb::new(201, 1, 0)]
+    );
+
+    Ok(())
+}
+
+/// The same seed should build the same palette
+#[test]
+fn kmeans_seed() -> GreenfieldResult<()> {
+    let image = gradient()?;
+
+    let a = KMeans::new(6).seed(1).max_iterations(2).run(&image)?;
+    let b = KMeans::new(6).seed(1).max_iterations(2).run(&image)?;
+    assert_eq!(a, b);
+
+    Ok(())
+}
+
+/// A seed should build the same palette on every platform
+#[test]
+fn kmeans_seed_pinned() -> GreenfieldResult<()> {
+    let image = gradient()?;
+
+    // A single iteration, so the palette still depends on the initialization
+    let palette = KMeans::new(3).seed(1).max_iterations(1).run(&image)?;
+    assert_eq!(
+        palette.colors(),
+        &[
+            Rgb::new(78, 76, 188),
+            Rgb::new(25, 243, 22),
+            Rgb::new(241, 28, 27)
+        ]
+    );
+
+    Ok(())
+}
+
+/// Should never build a palette with more colors than the image
+#[test]
+fn kmeans_few_colors() -> GreenfieldResult<()> {
+    let image = row(vec![
+        Rgb::new(1, 2, 3),
+        Rgb::new(1, 2, 3),
+        Rgb::new(4, 5, 6),
+    ])?;
+
+    let palette = KMeans::new(16).run(&image)?;
+    let mut colors = palette.colors().to_vec();
+    colors.sort_by_key(|c| c.bytes());
+    assert_eq!(colors, vec![Rgb::new(1, 2, 3), Rgb::new(4, 5, 6)]);
+
+    Ok(())
+}
+
+/// Iterating should never increase the error of the initialization
+#[test]
+fn kmeans_iterations() -> GreenfieldResult<()> {
+    let image = gradient()?;
+
+    let initial = KMeans::new(4).seed(3).max_iterations(0).run(&image)?;
+    let refined = KMeans::new(4).seed(3).threshold(0.0).run(&image)?;
+    assert!(error(&image, &refined) <= error(&image, &initial));
+
+    // Every initial color is a color of the image
+    assert!(initial
+        .colors()
+        .iter()
+        .all(|c| image.colors().any(|i| i == c)));
+
+    Ok(())
+}
+
+/// Should have a lower error than median cut on clustered images
+#[test]
+fn kmeans_median_cut() -> GreenfieldResult<()> {
+    let image = gradient()?;
+
+    let kmeans = KMeans::new(4).run(&image)?;
+    let median_cut = Palette::median_cut(&image, 4)?;
+    assert!(error(&image, &kmeans) <= error(&image, &median_cut));
+
+    Ok(())
+}
+
+/// Should fail on invalid sizes and empty images
+#[test]
+fn kmeans_err() -> GreenfieldResult<()> {
+    let image = row(vec![Rgb::new(1, 2, 3)])?;
+    assert!(KMeans::new(0).run(&image).is_err());
+    assert!(KMeans::new(Palette::MAX_SIZE + 1).run(&image).is_err());
+
+    let empty = row(vec![])?;
+    assert!(KMeans::new(4).run(&empty).is_err());
+
+    Ok(())
+}
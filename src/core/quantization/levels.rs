@@ -0,0 +1,195 @@
+//! ## Levels quantization
+//!
+//! A [`UniformQuantization`](super::UniformQuantization) has a power of two levels per component,
+//! so the cubes that fit a byte are lopsided, like `(3, 3, 2)`. A levels quantization takes any
+//! number of levels per component instead, e.g. the `6 × 7 × 6 = 252` levels cube, which also fits
+//! a byte. The indexes of the components are packed as a single mixed-radix code, `(r * levels_g
+//! + g) * levels_b + b`, in the fewest bits that hold every code.
+//!
+//! The levels are spread evenly over the full range of each component, from 0 to 255, and each
+//! component is quantized to the nearest one.
+//!
+//! The levels are stored in the image header: a 4 bit tag (`9`) and the number of levels of each
+//! component minus one (8 bits each).
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use greenfield::quantization::LevelsQuantization;
+//!
+//! let quantization = LevelsQuantization::new(6, 7, 6)?;
+//! assert_eq!(quantization.bits_per_pixel(), 8);
+//!
+//! let colors = vec![Rgb::new(255, 128, 0), Rgb::new(40, 40, 40)];
+//! let image = Image::new(2, 1, quantization, colors)?;
+//! assert_eq!(image.colors().next(), Some(&Rgb::new(255, 128, 0)));
+//! assert_eq!(image.colors().nth(1), Some(&Rgb::new(51, 43, 51)));
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+#[allow(unused_imports)] // Used by the deku derives without std
+use alloc::format;
+use core::fmt::Display;
+
+use deku::bitvec::{BitSlice, BitVec, Msb0};
+use deku::prelude::*;
+
+use super::Quantizer;
+use crate::color::Rgb;
+use crate::error::{GreenfieldError, GreenfieldResult};
+
+#[cfg(test)]
+mod tests;
+
+/// ## A quantization to any number of levels per component
+///
+/// Each component has between 1 and [`LevelsQuantization::MAX_LEVELS`] levels. A component with a
+/// single level is always reconstructed to 128.
+#[derive(Debug, Eq, Clone, PartialEq, DekuRead, DekuWrite)]
+#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+pub struct LevelsQuantization {
+    /// Index of the last level of each component, so every byte is a valid number of levels.
+    last: [u8; 3],
+}
+
+impl LevelsQuantization {
+    /// Maximum number of levels of a component.
+    pub const MAX_LEVELS: u16 = 256;
+
+    /// ## Creates a quantization with the levels of the red, green and blue components
+    ///
+    /// ## Errors
+    /// If a component has no levels, or more than [`LevelsQuantization::MAX_LEVELS`].
+    pub fn new(levels_r: u16, levels_g: u16, levels_b: u16) -> GreenfieldResult<Self> {
+        let levels = [levels_r, levels_g, levels_b];
+        if let Some(&invalid) = levels
+            .iter()
+            .find(|levels| !(1..=Self::MAX_LEVELS).contains(levels))
+        {
+            return Err(GreenfieldError::InvalidLevelCount(invalid));
+        }
+
+        Ok(Self {
+            last: levels.map(|levels| (levels - 1) as u8),
+        })
+    }
+
+    /// ## The levels of the red, green and blue components
+    pub fn levels(&self) -> [u16; 3] {
+        self.last.map(|last| last as u16 + 1)
+    }
+
+    /// ## Number of distinct codes: the product of the levels
+    pub fn size(&self) -> u32 {
+        self.levels().iter().map(|&levels| levels as u32).product()
+    }
+
+    /// The index of the level nearest to a component.
+    fn index(&self, channel: usize, value: u8) -> u8 {
+        let last = self.last[channel] as u32;
+        ((value as u32 * last + 127) / 255) as u8
+    }
+
+    /// The component a level is reconstructed to.
+    fn level(&self, channel: usize, index: u8) -> u8 {
+        match self.last[channel] as u32 {
+            0 => 128,
+            last => ((index.min(last as u8) as u32 * 255 + last / 2) / last) as u8,
+        }
+    }
+}
+
+impl Display for LevelsQuantization {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [r, g, b] = self.levels();
+        write!(f, "[{r}x{g}x{b} levels]")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for LevelsQuantization {
+    /// ## Serializes the quantization as `{"levels": [levels_r, levels_g, levels_b]}`
+    ///
+    /// Unlike a plain tuple, it can't be mistaken for a uniform quantization.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("LevelsQuantization", 1)?;
+        state.serialize_field("levels", &self.levels())?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LevelsQuantization {
+    /// ## Deserializes the quantization from `{"levels": [levels_r, levels_g, levels_b]}`
+    ///
+    /// The levels are validated the same way as in [`LevelsQuantization::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Levels {
+            levels: [u16; 3],
+        }
+
+        let [r, g, b] = Levels::deserialize(deserializer)?.levels;
+        Self::new(r, g, b).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Quantizer for LevelsQuantization {
+    /// ceil(log2(size)), but at least a bit.
+    fn bits_per_pixel(&self) -> usize {
+        let last = self.size() - 1;
+        (u32::BITS - last.leading_zeros()).max(1) as usize
+    }
+
+    /// The mixed-radix code of the indexes of the components.
+    fn quantize(&self, color: &Rgb) -> u32 {
+        let [r, g, b] = color.bytes();
+        let [_, levels_g, levels_b] = self.levels().map(|levels| levels as u32);
+        (self.index(0, r) as u32 * levels_g + self.index(1, g) as u32) * levels_b
+            + self.index(2, b) as u32
+    }
+
+    /// Codes past the last one are decoded as it.
+    fn dequantize(&self, code: u32) -> Rgb {
+        let [_, levels_g, levels_b] = self.levels().map(|levels| levels as u32);
+        let code = code.min(self.size() - 1);
+        self.dequantize_color(&Rgb::new(
+            (code / (levels_g * levels_b)) as u8,
+            (code / levels_b % levels_g) as u8,
+            (code % levels_b) as u8,
+        ))
+    }
+
+    fn write_params(&self, output: &mut BitVec<u8, Msb0>) -> GreenfieldResult<()> {
+        Ok(self.write(output, deku::ctx::Endian::Big)?)
+    }
+
+    /// Every byte is a valid number of levels, so there's nothing to validate.
+    fn read_params(input: &BitSlice<u8, Msb0>) -> GreenfieldResult<(&BitSlice<u8, Msb0>, Self)> {
+        Ok(Self::read(input, deku::ctx::Endian::Big)?)
+    }
+
+    /// The index space is the color of the indexes of the components.
+    fn quantize_color(&self, color: &Rgb) -> Rgb {
+        let [r, g, b] = color.bytes();
+        Rgb::new(self.index(0, r), self.index(1, g), self.index(2, b))
+    }
+
+    /// Indexes past the last level of a component are decoded as it.
+    fn dequantize_color(&self, color: &Rgb) -> Rgb {
+        let [r, g, b] = color.bytes();
+        Rgb::new(self.level(0, r), self.level(1, g), self.level(2, b))
+    }
+
+    fn reconstruct(&self, color: &Rgb) -> Rgb {
+        self.dequantize_color(&self.quantize_color(color))
+    }
+
+    /// The distance between neighboring levels.
+    fn spacing(&self) -> [u16; 3] {
+        self.last.map(|last| 255 / last.max(1) as u16)
+    }
+}
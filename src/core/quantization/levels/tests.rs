@@ -0,0 +1,214 @@
+use super::*;
+use crate::image::{Header, Image};
+use crate::quantization::{Quantization, UniformQuantization};
+
+/// Components have between 1 and 256 levels
+#[test]
+fn levels_new() -> GreenfieldResult<()> {
+    let quantization = LevelsQuantization::new(6, 7, 6)?;
+    assert_eq!(quantization.levels(), [6, 7, 6]);
+    assert_eq!(quantization.size(), 252);
+
+    assert!(LevelsQuantization::new(1, 1, 1).is_ok());
+    assert!(LevelsQuantization::new(256, 256, 256).is_ok());
+    assert!(matches!(
+        LevelsQuantization::new(0, 1, 1),
+        Err(GreenfieldError::InvalidLevelCount(0))
+    ));
+    assert!(matches!(
+        LevelsQuantization::new(6, 257, 6),
+        Err(GreenfieldError::InvalidLevelCount(257))
+    ));
+
+    Ok(())
+}
+
+/// Should take the smallest number of bits that can hold every code
+#[test]
+fn levels_bits_per_pixel() -> GreenfieldResult<()> {
+    let bits = |r, g, b| LevelsQuantization::new(r, g, b).map(|q| q.bits_per_pixel());
+
+    assert_eq!(bits(1, 1, 1)?, 1);
+    assert_eq!(bits(2, 1, 1)?, 1);
+    assert_eq!(bits(2, 2, 2)?, 3);
+    assert_eq!(bits(3, 3, 3)?, 5);
+    assert_eq!(bits(6, 6, 6)?, 8);
+    assert_eq!(bits(6, 7, 6)?, 8);
+    assert_eq!(bits(6, 8, 6)?, 9);
+    assert_eq!(bits(256, 256, 256)?, 24);
+
+    Ok(())
+}
+
+/// Codes should be mixed-radix, with red the most significant
+#[test]
+fn levels_codes() -> GreenfieldResult<()> {
+    let quantization = LevelsQuantization::new(6, 7, 6)?;
+
+    assert_eq!(quantization.quantize(&Rgb::new(0, 0, 0)), 0);
+    assert_eq!(quantization.quantize(&Rgb::new(0, 0, 255)), 5);
+    assert_eq!(quantization.quantize(&Rgb::new(0, 255, 0)), 6 * 6);
+    assert_eq!(quantization.quantize(&Rgb::new(255, 0, 0)), 5 * 7 * 6);
+    assert_eq!(quantization.quantize(&Rgb::new(255, 128, 0)), 228);
+    assert_eq!(quantization.quantize(&Rgb::new(255, 255, 255)), 251);
+
+    assert_eq!(quantization.dequantize(228), Rgb::new(255, 128, 0));
+    assert_eq!(
+        quantization.quantize_color(&Rgb::new(255, 128, 0)),
+        Rgb::new(5, 3, 0)
+    );
+    assert_eq!(
+        quantization.dequantize_color(&Rgb::new(5, 3, 0)),
+        Rgb::new(255, 128, 0)
+    );
+
+    // Codes and indexes past the end are the last ones
+    assert_eq!(quantization.dequantize(255), Rgb::new(255, 255, 255));
+    assert_eq!(quantization.dequantize(u32::MAX), Rgb::new(255, 255, 255));
+    assert_eq!(
+        quantization.dequantize_color(&Rgb::new(9, 9, 9)),
+        Rgb::new(255, 255, 255)
+    );
+
+    for code in 0..quantization.size() {
+        let color = quantization.dequantize(code);
+        assert_eq!(quantization.quantize(&color), code);
+    }
+
+    Ok(())
+}
+
+/// Components should be reconstructed to the nearest level
+#[test]
+fn levels_nearest() -> GreenfieldResult<()> {
+    for levels in [2, 3, 5, 6, 7, 100, 255, 256] {
+        let quantization = LevelsQuantization::new(levels, levels, levels)?;
+        let step = 255.0 / (levels - 1) as f64;
+        for value in 0..=255 {
+            let color = quantization.reconstruct(&Rgb::new(value, value, value));
+            let error = (color.r as f64 - value as f64).abs();
+            assert!(error <= step / 2.0 + 0.5, "{levels} {value} {color:?}");
+            assert_eq!(quantization.reconstruct(&color), color);
+        }
+    }
+
+    // A single level is the middle, and 256 levels are lossless
+    let quantization = LevelsQuantization::new(1, 256, 1)?;
+    assert_eq!(
+        quantization.reconstruct(&Rgb::new(10, 20, 30)),
+        Rgb::new(128, 20, 128)
+    );
+
+    Ok(())
+}
+
+/// Should write the levels minus one, behind the levels tag
+#[test]
+fn levels_params() -> GreenfieldResult<()> {
+    let quantization = Quantization::from(LevelsQuantization::new(6, 7, 6)?);
+
+    let mut params = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut params)?;
+    assert_eq!(params.len(), 4 + 3 * 8);
+    assert_eq!(params.as_raw_slice(), [0x90, 0x50, 0x60, 0x50]);
+
+    let (rest, read) = Quantization::read_params(&params)?;
+    assert!(rest.is_empty());
+    assert_eq!(read, quantization);
+
+    // Truncated levels
+    assert!(Quantization::read_params(&params[..20]).is_err());
+
+    Ok(())
+}
+
+/// Should pack pixels in the fewest bits, and round trip images
+#[test]
+fn levels_image() -> GreenfieldResult<()> {
+    let colors = vec![
+        Rgb::new(250, 10, 10),
+        Rgb::new(10, 250, 10),
+        Rgb::new(10, 10, 250),
+        Rgb::new(128, 128, 128),
+        Rgb::new(60, 120, 180),
+    ];
+    let quantization = LevelsQuantization::new(3, 3, 3)?;
+    let image = Image::new(5, 1, quantization.clone(), colors.clone())?;
+    assert!(image
+        .colors()
+        .zip(&colors)
+        .all(|(color, original)| *color == quantization.reconstruct(original)));
+
+    let compressed = quantization.compress(&colors);
+    assert_eq!(compressed.len(), 5 * 5);
+    assert!(quantization
+        .decompress(&compressed)
+        .iter()
+        .eq(image.colors()));
+
+    let header = image.header();
+    assert_eq!(header.bits(), Header::BITS - 12 + 4 + 3 * 8);
+
+    let serialized = image.clone().serialize()?;
+    // Header, then 5 pixels of 5 bits
+    assert_eq!(serialized.len(), (header.bits() + 5 * 5).div_ceil(8));
+    assert_eq!(Header::deserialize(&serialized)?, header);
+    assert_eq!(Image::deserialize(&serialized)?, image);
+
+    Ok(())
+}
+
+/// 6 × 7 × 6 levels fit a byte, with a lower worst error than 3-3-2 bits
+#[test]
+fn levels_byte() -> GreenfieldResult<()> {
+    let colors: Vec<_> = (0..=255)
+        .step_by(5)
+        .flat_map(|r| (0..=255).step_by(5).map(move |g| Rgb::new(r, g, 255 - r)))
+        .collect();
+    let max_error = |image: &Image| {
+        image
+            .colors()
+            .zip(&colors)
+            .flat_map(|(a, b)| a.bytes().into_iter().zip(b.bytes()))
+            .map(|(a, b)| a.abs_diff(b))
+            .max()
+    };
+
+    let levels = Image::new(52, 52, LevelsQuantization::new(6, 7, 6)?, colors.clone())?;
+    let uniform = Image::new(52, 52, UniformQuantization::new(3, 3, 2)?, colors.clone())?;
+    assert_eq!(levels.quantizer().bits_per_pixel(), 8);
+    assert!(max_error(&levels) <= Some(26));
+    assert!(max_error(&levels) < max_error(&uniform));
+
+    Ok(())
+}
+
+/// Should show the levels of each component
+#[test]
+fn levels_display() -> GreenfieldResult<()> {
+    let quantization = Quantization::from(LevelsQuantization::new(6, 7, 6)?);
+    assert_eq!(quantization.to_string(), "[6x7x6 levels]");
+
+    Ok(())
+}
+
+/// Levels should be serialized as objects, and validated on deserialization
+#[cfg(feature = "serde")]
+#[test]
+fn levels_serde() -> GreenfieldResult<()> {
+    let quantization = Quantization::from(LevelsQuantization::new(6, 7, 6)?);
+    let serialized = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(serialized, r#"{"levels":[6,7,6]}"#);
+
+    let deserialized = serde_json::from_str::<Quantization>(&serialized).unwrap();
+    assert_eq!(deserialized, quantization);
+
+    // Tuples are still uniform quantizations
+    let deserialized = serde_json::from_str::<Quantization>("[6,7,6]").unwrap();
+    assert_eq!(deserialized, UniformQuantization::new(6, 7, 6)?);
+
+    assert!(serde_json::from_str::<LevelsQuantization>(r#"{"levels":[0,7,6]}"#).is_err());
+    assert!(serde_json::from_str::<Quantization>(r#"{"levels":[6,7]}"#).is_err());
+
+    Ok(())
+}
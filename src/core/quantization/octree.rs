@@ -0,0 +1,297 @@
+//! ## Octree palettes
+//!
+//! Builds a [`Palette`] with an octree: the pixels of an image are inserted one at a time in a
+//! tree up to 8 levels deep, where each level splits the color cube in 8 octants by one bit of
+//! each component (the most significant bit first). Whenever the tree has more leaves than the
+//! requested number of colors, the node with the fewest pixels of the deepest level is merged
+//! into a leaf. Each leaf is a palette color, the mean of the pixels inserted or merged into it.
+//!
+//! Octrees are built in a single pass over the pixels, and the tree never holds much more than
+//! the requested number of leaves, however many colors the image has. So they're way faster than
+//! [`Palette::median_cut`] and k-means, at the cost of a higher error. The tree is also a fast
+//! lookup of the palette color of each color: it's found by walking down the tree, instead of
+//! comparing it to every palette color.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use greenfield::quantization::Octree;
+//!
+//! let colors = vec![
+//!     Rgb::new(250, 10, 10),
+//!     Rgb::new(255, 0, 0),
+//!     Rgb::new(0, 0, 250),
+//!     Rgb::new(10, 10, 255),
+//! ];
+//! let mut image = Image::new(2, 2, UniformQuantization::default(), colors)?;
+//!
+//! let octree = Octree::new(&image, 2)?;
+//! assert_eq!(octree.palette().colors().len(), 2);
+//!
+//! // Quantizes the image to the palette, in place
+//! octree.quantize(&mut image);
+//! assert_eq!(image.quantizer().bits_per_pixel(), 1);
+//! assert_eq!(image.colors().next(), image.colors().nth(1));
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::vec::Vec;
+
+use super::palette::Palette;
+use super::Quantization;
+use crate::color::Rgb;
+use crate::error::{GreenfieldError, GreenfieldResult};
+use crate::image::{Header, Image};
+use crate::parallel;
+
+#[cfg(test)]
+mod tests;
+
+/// Depth of the tree: one level per bit of the components.
+const DEPTH: usize = 8;
+
+/// Index of the root node. No node has it as a child, so it also means "no child".
+const ROOT: u32 = 0;
+
+/// ## Octree quantizer
+///
+/// The reduced tree of the colors of an image, and the palette of its leaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Octree {
+    nodes: Vec<Node>,
+    palette: Palette,
+}
+
+/// A node of the tree, and the pixels under it.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Node {
+    children: [u32; 8],
+    count: u64,
+    sum: [u64; 3],
+    /// Leaves are the palette colors
+    leaf: bool,
+    /// Index of the palette color of a leaf
+    index: usize,
+}
+
+impl Node {
+    fn add(&mut self, color: &Rgb, count: u64) {
+        self.count += count;
+        self.sum
+            .iter_mut()
+            .zip(color.bytes())
+            .for_each(|(s, c)| *s += c as u64 * count);
+    }
+}
+
+impl Octree {
+    /// ## Builds the octree of an image, reduced to at most `size` colors
+    ///
+    /// If the image has less than `size` distinct colors, the palette is exactly those colors.
+    /// Merging a node may remove up to 7 colors at once, so the palette can also be a bit smaller
+    /// than `size`.
+    ///
+    /// ## Errors
+    /// - If `size` is 0 or more than [`Palette::MAX_SIZE`].
+    /// - If the image is empty.
+    pub fn new(image: &Image, size: usize) -> GreenfieldResult<Self> {
+        if !(1..=Palette::MAX_SIZE).contains(&size) {
+            return Err(GreenfieldError::InvalidPaletteSize(size));
+        }
+
+        let mut tree = Tree::default();
+        for color in image.colors() {
+            tree.insert(color);
+            while tree.leaves > size {
+                tree.reduce();
+            }
+        }
+        if tree.leaves == 0 {
+            return Err(GreenfieldError::InvalidPaletteSize(0));
+        }
+
+        let Tree {
+            mut nodes, leaves, ..
+        } = tree;
+        let mut colors = Vec::with_capacity(leaves);
+        index(&mut nodes, ROOT, &mut colors);
+
+        Ok(Self {
+            nodes,
+            palette: Palette::new(colors)?,
+        })
+    }
+
+    /// ## The palette of the leaves of the tree
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// ## Consumes the tree, returning its palette
+    pub fn into_palette(self) -> Palette {
+        self.palette
+    }
+
+    /// ## Returns the index of the palette color of `color`
+    ///
+    /// Colors of the image the tree was built from are found by walking down the tree. Other
+    /// colors may fall outside of it, and are looked up in the palette instead
+    /// ([`Palette::nearest`]).
+    pub fn index(&self, color: &Rgb) -> usize {
+        let mut node = &self.nodes[ROOT as usize];
+        for level in 0..DEPTH {
+            if node.leaf {
+                break;
+            }
+            match node.children[octant(color, level)] {
+                ROOT => return self.palette.nearest(color),
+                child => node = &self.nodes[child as usize],
+            }
+        }
+
+        node.index
+    }
+
+    /// ## Quantizes an image to the palette, in place
+    ///
+    /// Every color is replaced by its palette color (see [`Octree::index`]), and the image is
+    /// now quantized to the palette, so it's serialized as palette indices. Its colors are all
+    /// palette colors, which the palette encodes without searching (see [`Palette::nearest`]).
+    pub fn quantize(&self, image: &mut Image) {
+        let (width, height) = image.dimensions();
+        let colors = image.colors().collect::<Vec<&Rgb>>();
+        let lookup = |color: &&Rgb| self.palette.colors()[self.index(color)].clone();
+
+        let data = parallel::map_chunks(colors.len(), |chunk| {
+            colors[chunk].iter().map(lookup).collect::<Vec<Rgb>>()
+        })
+        .concat();
+
+        let header = Header {
+            width,
+            height,
+            quantization: Quantization::Palette(self.palette.clone()),
+        };
+        *image = Image::from_raw_parts(header, data);
+    }
+}
+
+/// The child of a node of `level` that contains `color`: one bit of each component.
+fn octant(color: &Rgb, level: usize) -> usize {
+    let bit = |c: u8| ((c >> (DEPTH - 1 - level)) & 1) as usize;
+    (bit(color.r) << 2) | (bit(color.g) << 1) | bit(color.b)
+}
+
+/// The tree while it's built.
+struct Tree {
+    nodes: Vec<Node>,
+    /// The inner nodes of each level, which can be reduced to leaves
+    levels: [Vec<u32>; DEPTH],
+    /// Nodes merged into their parents, to be reused
+    free: Vec<u32>,
+    leaves: usize,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Self {
+            nodes: alloc::vec![Node::default()],
+            levels: Default::default(),
+            free: Vec::new(),
+            leaves: 0,
+        }
+    }
+}
+
+impl Tree {
+    /// Adds a pixel to the nodes in its path, down to a leaf, creating the missing ones.
+    fn insert(&mut self, color: &Rgb) {
+        let mut node = ROOT;
+        for level in 0..DEPTH {
+            let current = &mut self.nodes[node as usize];
+            current.add(color, 1);
+            if current.leaf {
+                return;
+            }
+
+            let octant = octant(color, level);
+            node = match current.children[octant] {
+                ROOT => {
+                    // Nodes become reducible with their first child
+                    if current.children.iter().all(|&c| c == ROOT) {
+                        self.levels[level].push(node);
+                    }
+
+                    let child = self.node();
+                    self.nodes[node as usize].children[octant] = child;
+                    child
+                }
+                child => child,
+            };
+        }
+
+        let leaf = &mut self.nodes[node as usize];
+        leaf.add(color, 1);
+        if !leaf.leaf {
+            leaf.leaf = true;
+            self.leaves += 1;
+        }
+    }
+
+    /// A new node, reusing a free one if any.
+    fn node(&mut self) -> u32 {
+        match self.free.pop() {
+            Some(node) => {
+                self.nodes[node as usize] = Node::default();
+                node
+            }
+            None => {
+                self.nodes.push(Node::default());
+                (self.nodes.len() - 1) as u32
+            }
+        }
+    }
+
+    /// Merges the children of the node with the fewest pixels of the deepest level into it. The
+    /// children of the deepest inner nodes are all leaves.
+    fn reduce(&mut self) {
+        let Some(level) = self.levels.iter_mut().rev().find(|level| !level.is_empty()) else {
+            return;
+        };
+        let Some((i, _)) = level
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &node)| self.nodes[node as usize].count)
+        else {
+            return;
+        };
+        let node = &mut self.nodes[level.swap_remove(i) as usize];
+
+        for child in node.children.into_iter().filter(|&c| c != ROOT) {
+            self.free.push(child);
+            self.leaves -= 1;
+        }
+        node.children = [ROOT; 8];
+        node.leaf = true;
+        self.leaves += 1;
+    }
+}
+
+/// Assigns palette indices to the leaves, in depth first order, collecting their colors.
+fn index(nodes: &mut [Node], node: u32, colors: &mut Vec<Rgb>) {
+    let current = &mut nodes[node as usize];
+    if current.leaf {
+        current.index = colors.len();
+        let [r, g, b] = current
+            .sum
+            .map(|s| ((s + current.count / 2) / current.count) as u8);
+        colors.push(Rgb::new(r, g, b));
+        return;
+    }
+
+    for child in current.children {
+        if child != ROOT {
+            index(nodes, child, colors);
+        }
+    }
+}
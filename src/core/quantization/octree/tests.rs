@@ -0,0 +1,153 @@
+use super::*;
+use crate::quantization::tests::{self, row};
+use crate::quantization::{Quantizer, UniformQuantization};
+
+/// An image with many distinct colors
+fn gradient() -> GreenfieldResult<Image> {
+    tests::gradient(64, 64, UniformQuantization::new(8, 8, 8)?, |i| {
+        Rgb::new((i % 64 * 4) as u8, (i / 64 * 4) as u8, (i * 13) as u8)
+    })
+}
+
+/// Should select the octant by one bit of each component
+#[test]
+fn octree_octant() {
+    let color = Rgb::new(0b1000_0001, 0b0100_0001, 0b1100_0000);
+    assert_eq!(octant(&color, 0), 0b101);
+    assert_eq!(octant(&color, 1), 0b011);
+    assert_eq!(octant(&color, 7), 0b110);
+}
+
+/// With enough colors, the palette is exactly the colors of the image
+#[test]
+fn octree_exact() -> GreenfieldResult<()> {
+    let colors = vec![
+        Rgb::new(1, 2, 3),
+        Rgb::new(200, 100, 0),
+        Rgb::new(1, 2, 3),
+        Rgb::new(0, 0, 255),
+    ];
+    let image = row(colors.clone())?;
+
+    let octree = Octree::new(&image, 16)?;
+    assert_eq!(octree.palette().colors().len(), 3);
+    for color in &colors {
+        assert_eq!(&octree.palette().colors()[octree.index(color)], color);
+    }
+
+    Ok(())
+}
+
+/// Should merge the colors with the fewest pixels
+#[test]
+fn octree_reduce() -> GreenfieldResult<()> {
+    let mut colors = vec![Rgb::new(255, 255, 255); 4];
+    colors.extend([Rgb::new(0, 0, 0), Rgb::new(0, 0, 1), Rgb::new(0, 0, 3)]);
+    let image = row(colors)?;
+
+    let octree = Octree::new(&image, 2)?;
+    assert_eq!(
+        octree.palette().colors(),
+        &[Rgb::new(0, 0, 1), Rgb::new(255, 255, 255)]
+    );
+    assert_eq!(octree.index(&Rgb::new(0, 0, 3)), 0);
+    assert_eq!(octree.index(&Rgb::new(255, 255, 255)), 1);
+
+    Ok(())
+}
+
+/// Should never build a palette with more than `size` colors
+#[test]
+fn octree_size() -> GreenfieldResult<()> {
+    let image = gradient()?;
+
+    for size in [1, 2, 7, 8, 64, 256] {
+        let octree = Octree::new(&image, size)?;
+        let colors = octree.palette().colors().len();
+        assert!(colors <= size && colors > 0, "{colors} colors for {size}");
+    }
+
+    Ok(())
+}
+
+/// The tree should stay bounded by the palette size, not the number of distinct colors
+#[test]
+fn octree_bounded() -> GreenfieldResult<()> {
+    let image = gradient()?;
+
+    for size in [1, 8, 64] {
+        let octree = Octree::new(&image, size)?;
+        assert!(
+            octree.nodes.len() <= (size + 1) * (DEPTH + 1),
+            "{} nodes for {size}",
+            octree.nodes.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// The lookup should agree with the tree, and fall back to the nearest color outside of it
+#[test]
+fn octree_index() -> GreenfieldResult<()> {
+    let image = row(vec![Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)])?;
+    let octree = Octree::new(&image, 2)?;
+
+    assert_eq!(octree.index(&Rgb::new(0, 0, 0)), 0);
+    assert_eq!(octree.index(&Rgb::new(255, 255, 255)), 1);
+    // Not in the tree
+    assert_eq!(octree.index(&Rgb::new(10, 20, 30)), 0);
+    assert_eq!(octree.index(&Rgb::new(200, 220, 240)), 1);
+
+    Ok(())
+}
+
+/// Should quantize an image to the palette in place
+#[test]
+fn octree_quantize() -> GreenfieldResult<()> {
+    let mut image = gradient()?;
+    let octree = Octree::new(&image, 16)?;
+    let indices = image
+        .colors()
+        .map(|c| octree.index(c))
+        .collect::<Vec<usize>>();
+
+    octree.quantize(&mut image);
+    assert_eq!(image.dimensions(), (64, 64));
+    assert_eq!(
+        image.quantizer(),
+        &Quantization::Palette(octree.palette().clone())
+    );
+    for (color, index) in image.colors().zip(indices) {
+        assert_eq!(color, &octree.palette().colors()[index]);
+        assert_eq!(image.quantizer().quantize(color) as usize, index);
+    }
+
+    let serialized = image.clone().serialize()?;
+    assert_eq!(Image::deserialize(&serialized)?, image);
+
+    // Large palettes encode the image to the same indices as the tree
+    let mut image = gradient()?;
+    let octree = Octree::new(&image, 4096)?;
+    let indices = image.colors().map(|c| octree.index(c)).collect::<Vec<_>>();
+    octree.quantize(&mut image);
+    let quantization = image.quantizer();
+    for (color, index) in image.colors().zip(indices) {
+        assert_eq!(quantization.quantize(color) as usize, index);
+    }
+
+    Ok(())
+}
+
+/// Should fail on invalid sizes and empty images
+#[test]
+fn octree_err() -> GreenfieldResult<()> {
+    let image = row(vec![Rgb::new(1, 2, 3)])?;
+    assert!(Octree::new(&image, 0).is_err());
+    assert!(Octree::new(&image, Palette::MAX_SIZE + 1).is_err());
+
+    let empty = row(vec![])?;
+    assert!(Octree::new(&empty, 4).is_err());
+
+    Ok(())
+}
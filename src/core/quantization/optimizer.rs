@@ -0,0 +1,297 @@
+//! ## Automatic quantization selection
+//!
+//! Picks the [`UniformQuantization`] tuple that best fits a [`Target`]: a maximum size of the
+//! encoded image, a minimum PSNR or a maximum number of bits per pixel. Every one of the 512
+//! tuples is evaluated on the image:
+//!
+//! - The error of a tuple is the sum of the errors of its components, which only depend on the
+//!   bits of each one. So, the errors of the 8 bit depths of each component are computed once,
+//!   from the histogram of the component.
+//! - The size of a tuple is the size of its header plus its packed colors. The colors are packed
+//!   with [`Quantizer::compress`], once for each number of bits per pixel, so the sizes are exactly
+//!   the ones of [`Image::serialize`].
+//!
+//! Besides the best tuple for a target, an [`Evaluation`] has the Pareto frontier of size against
+//! error: the tuples that no other tuple beats in both.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use greenfield::quantization::{Optimizer, Target};
+//!
+//! let colors = (0..64 * 64)
+//!     .map(|i| Rgb::new((i % 64 * 4) as u8, (i / 64 * 4) as u8, 64))
+//!     .collect();
+//! let image = Image::new(64, 64, UniformQuantization::default(), colors)?;
+//!
+//! // The smallest tuple with a PSNR of 30 dB
+//! let best = Optimizer::new().run(&image, Target::Psnr(30.0)).unwrap();
+//! assert!(best.psnr() >= 30.0);
+//!
+//! // Blue is constant, and 64 is the reconstruction of a single bit
+//! assert_eq!(best.quantization.bits_b, 1);
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::vec::Vec;
+
+use super::{
+    Bounds, ColorSpace, Companding, Quantizer, Reconstruction, Rounding, UniformQuantization,
+};
+use crate::color::Rgb;
+use crate::image::{Header, Image};
+use crate::parallel;
+
+#[cfg(test)]
+mod tests;
+
+/// Bit depths of a component.
+const DEPTHS: core::ops::RangeInclusive<u8> = 1..=8;
+
+/// ## What the optimizer aims for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// The lowest error with at most this many bytes, header included.
+    Size(usize),
+    /// The smallest size with at least this PSNR, in dB.
+    Psnr(f64),
+    /// The lowest error with at most this many bits per pixel.
+    BitsPerPixel(usize),
+}
+
+/// ## A quantization tuple, evaluated on an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub quantization: UniformQuantization,
+    /// Size of the encoded image, in bytes
+    pub size: usize,
+    /// Mean squared error of the components
+    pub mse: f64,
+}
+
+impl Candidate {
+    /// ## Peak signal-to-noise ratio, in dB
+    ///
+    /// Infinite for lossless quantizations.
+    pub fn psnr(&self) -> f64 {
+        if self.mse == 0.0 {
+            return f64::INFINITY;
+        }
+        10.0 * (255.0 * 255.0 / self.mse).log10()
+    }
+
+    /// ## Number of bits of each pixel
+    pub fn bits_per_pixel(&self) -> usize {
+        self.quantization.bits_per_pixel()
+    }
+}
+
+/// ## Quantization optimizer
+///
+/// Evaluates every tuple with the same rounding and reconstruction policies (see
+/// [`policy`](super::policy)), the defaults unless set. Lookup tables only fit the tuples with
+/// their bits, so the other tuples are skipped. The tuples quantize the RGB components: in other
+/// color spaces, the error of a tuple isn't the sum of the errors of its components.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Optimizer {
+    rounding: Rounding,
+    reconstruction: Reconstruction,
+}
+
+impl Optimizer {
+    /// ## Creates an optimizer with the default policies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ## Sets the rounding policy of the tuples
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// ## Sets the reconstruction policy of the tuples
+    pub fn reconstruction(mut self, reconstruction: Reconstruction) -> Self {
+        self.reconstruction = reconstruction;
+        self
+    }
+
+    /// ## Returns the best tuple for a target
+    ///
+    /// Ties are broken by the other measure (the size or the error).
+    ///
+    /// ## Returns
+    /// `None` if no tuple reaches the target.
+    pub fn run(&self, image: &Image, target: Target) -> Option<Candidate> {
+        self.evaluate(image).best(target).cloned()
+    }
+
+    /// ## Evaluates every tuple on an image
+    pub fn evaluate(&self, image: &Image) -> Evaluation {
+        let (width, height) = image.dimensions();
+        let colors = image.colors().cloned().collect::<Vec<Rgb>>();
+
+        let errors = self.squared_errors(&colors);
+        let tuples = DEPTHS
+            .flat_map(|r| DEPTHS.flat_map(move |g| DEPTHS.map(move |b| [r, g, b])))
+            .filter(|bits| (0..3).all(|channel| self.fits(channel, bits[channel])))
+            .map(|[bits_r, bits_g, bits_b]| self.quantization(bits_r, bits_g, bits_b))
+            .collect::<Vec<_>>();
+
+        // The payload only depends on the bits per pixel
+        let mut depths = tuples
+            .iter()
+            .map(|q| q.bits_per_pixel())
+            .collect::<Vec<usize>>();
+        depths.sort_unstable();
+        depths.dedup();
+        let payload = |bits: &usize| {
+            tuples
+                .iter()
+                .find(|q| q.bits_per_pixel() == *bits)
+                .map_or(0, |q| Quantizer::compress(q, &colors).len())
+        };
+        let payloads = parallel::map(&depths, payload);
+
+        let components = (colors.len() * 3).max(1) as f64;
+        let candidates = tuples
+            .into_iter()
+            .map(|quantization| {
+                let header = Header {
+                    width,
+                    height,
+                    quantization: quantization.clone().into(),
+                }
+                .bits();
+                let depth = depths.binary_search(&quantization.bits_per_pixel());
+                let payload = depth.map_or(0, |depth| payloads[depth]);
+
+                let error = [
+                    quantization.bits_r,
+                    quantization.bits_g,
+                    quantization.bits_b,
+                ]
+                .iter()
+                .enumerate()
+                .map(|(channel, &bits)| errors[channel][bits as usize - 1])
+                .sum::<u64>();
+
+                Candidate {
+                    quantization,
+                    size: (header + payload).div_ceil(8),
+                    mse: error as f64 / components,
+                }
+            })
+            .collect();
+
+        Evaluation { candidates }
+    }
+
+    /// The tuple, with the policies of the optimizer.
+    fn quantization(&self, bits_r: u8, bits_g: u8, bits_b: u8) -> UniformQuantization {
+        UniformQuantization {
+            bits_r,
+            bits_g,
+            bits_b,
+            rounding: self.rounding,
+            reconstruction: self.reconstruction.clone(),
+            color_space: ColorSpace::Rgb,
+            companding: Companding::default(),
+            bounds: Bounds::default(),
+        }
+    }
+
+    /// Whether a component can be quantized to `bits` bits: its lookup table must have `2^bits`
+    /// entries.
+    fn fits(&self, channel: usize, bits: u8) -> bool {
+        match &self.reconstruction {
+            Reconstruction::Lookup(lut) => lut.tables()[channel].len() == 1 << bits,
+            _ => true,
+        }
+    }
+
+    /// The squared error of each component quantized to each depth, from its histogram.
+    fn squared_errors(&self, colors: &[Rgb]) -> [[u64; 8]; 3] {
+        let mut histograms = [[0u64; 256]; 3];
+        for color in colors {
+            for (histogram, value) in histograms.iter_mut().zip(color.bytes()) {
+                histogram[value as usize] += 1;
+            }
+        }
+
+        [0, 1, 2].map(|channel| {
+            core::array::from_fn(|depth| {
+                let bits = depth as u8 + 1;
+                if !self.fits(channel, bits) {
+                    return u64::MAX;
+                }
+
+                let quantization = self.quantization(bits, bits, bits);
+                (0..=u8::MAX)
+                    .map(|value| {
+                        let index = quantization.quantize_component(channel, value);
+                        let reconstructed = quantization.dequantize_component(channel, index);
+                        let error = reconstructed.abs_diff(value) as u64;
+                        histograms[channel][value as usize] * error * error
+                    })
+                    .sum()
+            })
+        })
+    }
+}
+
+/// ## Every tuple, evaluated on an image
+///
+/// Returned by [`Optimizer::evaluate`], to pick the best tuples for several targets without
+/// evaluating them again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    candidates: Vec<Candidate>,
+}
+
+impl Evaluation {
+    /// ## The evaluated tuples, from `(1, 1, 1)` to `(8, 8, 8)`
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// ## Returns the best tuple for a target
+    ///
+    /// Ties are broken by the other measure (the size or the error), and then by the order of the
+    /// tuples.
+    ///
+    /// ## Returns
+    /// `None` if no tuple reaches the target.
+    pub fn best(&self, target: Target) -> Option<&Candidate> {
+        let by_error =
+            |a: &&Candidate, b: &&Candidate| a.mse.total_cmp(&b.mse).then(a.size.cmp(&b.size));
+        let by_size =
+            |a: &&Candidate, b: &&Candidate| a.size.cmp(&b.size).then(a.mse.total_cmp(&b.mse));
+        let candidates = self.candidates.iter();
+
+        match target {
+            Target::Size(size) => candidates.filter(|c| c.size <= size).min_by(by_error),
+            Target::Psnr(psnr) => candidates.filter(|c| c.psnr() >= psnr).min_by(by_size),
+            Target::BitsPerPixel(bits) => candidates
+                .filter(|c| c.bits_per_pixel() <= bits)
+                .min_by(by_error),
+        }
+    }
+
+    /// ## The Pareto frontier of size against error
+    ///
+    /// The tuples that no other tuple beats in both size and error (keeping the first of equal
+    /// ones), from the smallest to the largest. Their errors strictly decrease.
+    pub fn frontier(&self) -> Vec<&Candidate> {
+        let mut candidates = self.candidates.iter().collect::<Vec<_>>();
+        candidates.sort_by(|a, b| a.size.cmp(&b.size).then(a.mse.total_cmp(&b.mse)));
+
+        let mut frontier = Vec::<&Candidate>::new();
+        for candidate in candidates {
+            if frontier.last().is_none_or(|last| candidate.mse < last.mse) {
+                frontier.push(candidate);
+            }
+        }
+        frontier
+    }
+}
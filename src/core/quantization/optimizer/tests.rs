@@ -0,0 +1,165 @@
+use super::*;
+use crate::error::GreenfieldResult;
+use crate::quantization::{tests, Lut};
+
+/// A small image with many distinct colors
+fn gradient() -> GreenfieldResult<Image> {
+    tests::gradient(7, 5, UniformQuantization::default(), |i| {
+        Rgb::new((i * 7) as u8, (i * 29 % 256) as u8, (255 - i * 3) as u8)
+    })
+}
+
+/// The mean squared error of an image quantized with a tuple, computed directly
+fn mse(image: &Image, quantization: &UniformQuantization) -> f64 {
+    let error = image
+        .colors()
+        .flat_map(|c| {
+            let reconstructed = quantization.reconstruct(c);
+            c.bytes()
+                .into_iter()
+                .zip(reconstructed.bytes())
+                .map(|(a, b)| (a.abs_diff(b) as u64).pow(2))
+        })
+        .sum::<u64>();
+    error as f64 / (image.colors().count() * 3) as f64
+}
+
+/// Every tuple should have the size and error of actually encoding the image with it
+#[test]
+fn optimizer_evaluate() -> GreenfieldResult<()> {
+    let image = gradient()?;
+    let colors = image.colors().cloned().collect::<Vec<_>>();
+
+    for optimizer in [
+        Optimizer::new(),
+        Optimizer::new()
+            .rounding(Rounding::Nearest)
+            .reconstruction(Reconstruction::Replicate),
+    ] {
+        let evaluation = optimizer.evaluate(&image);
+        assert_eq!(evaluation.candidates().len(), 512);
+
+        for candidate in evaluation.candidates() {
+            let encoded = Image::new(7, 5, candidate.quantization.clone(), colors.clone())?;
+            assert_eq!(candidate.size, encoded.serialize()?.len());
+            assert_eq!(candidate.mse, mse(&image, &candidate.quantization));
+        }
+    }
+
+    Ok(())
+}
+
+/// Lookup tables should only be evaluated with the bits of their tables
+#[test]
+fn optimizer_lookup() -> GreenfieldResult<()> {
+    let image = gradient()?;
+    let lut = Lut::new([vec![0, 90, 160, 255], vec![0; 8], vec![100, 200]])?;
+    let optimizer = Optimizer::new().reconstruction(Reconstruction::Lookup(lut.clone()));
+
+    let evaluation = optimizer.evaluate(&image);
+    let [candidate] = evaluation.candidates() else {
+        panic!("{} candidates", evaluation.candidates().len());
+    };
+
+    let quantization =
+        UniformQuantization::new(2, 3, 1)?.with_reconstruction(Reconstruction::Lookup(lut))?;
+    let colors = image.colors().cloned().collect();
+    let encoded = Image::new(7, 5, quantization.clone(), colors)?;
+    assert_eq!(candidate.quantization, quantization);
+    assert_eq!(candidate.size, encoded.serialize()?.len());
+    assert_eq!(candidate.mse, mse(&image, &quantization));
+
+    Ok(())
+}
+
+/// The best tuple should reach the target, and no other tuple that reaches it should be better
+#[test]
+fn optimizer_best() -> GreenfieldResult<()> {
+    let image = gradient()?;
+    let evaluation = Optimizer::new().evaluate(&image);
+    let candidates = evaluation.candidates();
+
+    for size in [40, 60, 80, 100, 120] {
+        let best = evaluation.best(Target::Size(size)).unwrap();
+        assert!(best.size <= size);
+        assert!(candidates
+            .iter()
+            .filter(|c| c.size <= size)
+            .all(|c| c.mse >= best.mse));
+    }
+
+    for psnr in [10.0, 20.0, 30.0, 40.0, f64::INFINITY] {
+        let best = evaluation.best(Target::Psnr(psnr)).unwrap();
+        assert!(best.psnr() >= psnr);
+        assert!(candidates
+            .iter()
+            .filter(|c| c.psnr() >= psnr)
+            .all(|c| c.size >= best.size));
+    }
+    let lossless = evaluation.best(Target::Psnr(f64::INFINITY)).unwrap();
+    assert_eq!(lossless.quantization, UniformQuantization::default());
+
+    for bits in 3..=24 {
+        let best = evaluation.best(Target::BitsPerPixel(bits)).unwrap();
+        assert!(best.bits_per_pixel() <= bits);
+        assert!(candidates
+            .iter()
+            .filter(|c| c.bits_per_pixel() <= bits)
+            .all(|c| c.mse >= best.mse));
+    }
+
+    assert_eq!(
+        Optimizer::new().run(&image, Target::Size(60)).as_ref(),
+        evaluation.best(Target::Size(60))
+    );
+
+    Ok(())
+}
+
+/// Targets that no tuple reaches have no best tuple
+#[test]
+fn optimizer_unreachable() -> GreenfieldResult<()> {
+    let image = gradient()?;
+    let evaluation = Optimizer::new().evaluate(&image);
+
+    assert_eq!(evaluation.best(Target::Size(10)), None);
+    assert_eq!(evaluation.best(Target::BitsPerPixel(2)), None);
+
+    Ok(())
+}
+
+/// The frontier should be sorted, and every tuple should be beaten or matched by one of it
+#[test]
+fn optimizer_frontier() -> GreenfieldResult<()> {
+    let image = gradient()?;
+    let evaluation = Optimizer::new().evaluate(&image);
+    let frontier = evaluation.frontier();
+
+    assert!(frontier
+        .windows(2)
+        .all(|w| w[0].size < w[1].size && w[0].mse > w[1].mse));
+    assert_eq!(frontier.last().map(|c| c.mse), Some(0.0));
+    for candidate in evaluation.candidates() {
+        assert!(frontier
+            .iter()
+            .any(|f| f.size <= candidate.size && f.mse <= candidate.mse));
+    }
+
+    Ok(())
+}
+
+/// The PSNR of lossless tuples is infinite
+#[test]
+fn optimizer_psnr() -> GreenfieldResult<()> {
+    let candidate = |mse| Candidate {
+        quantization: UniformQuantization::default(),
+        size: 0,
+        mse,
+    };
+
+    assert_eq!(candidate(0.0).psnr(), f64::INFINITY);
+    assert!((candidate(65025.0).psnr()).abs() < 1e-9);
+    assert!((candidate(650.25).psnr() - 20.0).abs() < 1e-9);
+
+    Ok(())
+}
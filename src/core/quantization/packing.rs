@@ -0,0 +1,162 @@
+//! Packing of codes into bits, MSB first, one code after the other.
+//!
+//! Going through a [`BitSlice`] for every code is slow, so codes are shifted in and out of a
+//! 64-bit word instead, and written and read 32 bits at a time. The byte-aligned widths (8, 16 and
+//! 24 bits, and 12 bits as pairs of codes in 3 bytes) are copied byte by byte.
+//!
+//! Large runs of codes are split in chunks, packed and unpacked in parallel (see [`parallel`]).
+use alloc::vec::Vec;
+
+use deku::bitvec::{BitSlice, BitVec, Msb0};
+
+use crate::parallel;
+
+#[cfg(test)]
+mod tests;
+
+/// Packs `count` codes of `bits` bits each (at most 32), which must all fit their bits.
+pub(crate) fn pack(
+    codes: impl IntoIterator<Item = u32>,
+    count: usize,
+    bits: usize,
+) -> BitVec<u8, Msb0> {
+    let size = count * bits;
+    let mut bytes = Vec::with_capacity(size.div_ceil(8));
+    let mut codes = codes.into_iter().take(count);
+
+    match bits {
+        8 | 16 | 24 | 32 => {
+            for code in codes {
+                bytes.extend_from_slice(&code.to_be_bytes()[4 - bits / 8..]);
+            }
+        }
+        12 => {
+            while let Some(first) = codes.next() {
+                let second = codes.next().unwrap_or(0);
+                bytes.extend_from_slice(&((first << 12) | second).to_be_bytes()[1..]);
+            }
+        }
+        _ => {
+            // The `filled` low bits of `word` are still to be written
+            let (mut word, mut filled) = (0u64, 0);
+            for code in codes {
+                word = (word << bits) | code as u64;
+                filled += bits;
+                if filled >= 32 {
+                    filled -= 32;
+                    bytes.extend_from_slice(&((word >> filled) as u32).to_be_bytes());
+                }
+            }
+            while filled >= 8 {
+                filled -= 8;
+                bytes.push((word >> filled) as u8);
+            }
+            if filled > 0 {
+                bytes.push((word << (8 - filled)) as u8);
+            }
+        }
+    }
+
+    let mut packed = BitVec::from_vec(bytes);
+    packed.truncate(size);
+    packed
+}
+
+/// Unpacks every whole code of `bits` bits each (at most 32) of `data`, mapping them with `map`.
+pub(crate) fn unpack<T>(
+    data: &BitSlice<u8, Msb0>,
+    bits: usize,
+    mut map: impl FnMut(u32) -> T,
+) -> Vec<T> {
+    let count = data.len() / bits;
+    let mut unpacked = Vec::with_capacity(count);
+
+    // Moves the data to the start of its bytes, so they can be read directly
+    let mut aligned = data[..count * bits].to_bitvec();
+    aligned.force_align();
+    let bytes = aligned.as_raw_slice();
+
+    match bits {
+        8 => unpacked.extend(bytes.iter().map(|&byte| map(byte as u32))),
+        16 | 24 | 32 => unpacked.extend(bytes.chunks_exact(bits / 8).map(|chunk| {
+            map(chunk
+                .iter()
+                .fold(0u32, |code, &byte| (code << 8) | byte as u32))
+        })),
+        12 => {
+            for chunk in bytes.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| chunk.get(i).copied().unwrap_or(0) as u32);
+                unpacked.push(map((a << 4) | (b >> 4)));
+                if chunk.len() == 3 {
+                    unpacked.push(map(((b & 0x0F) << 8) | c));
+                }
+            }
+        }
+        _ => {
+            let mask = (1u64 << bits) - 1;
+            // The `filled` low bits of `word` are still to be read
+            let (mut word, mut filled) = (0u64, 0);
+            let mut read = |word: &mut u64, filled: &mut usize, unpacked: &mut Vec<T>| {
+                while *filled >= bits && unpacked.len() < count {
+                    *filled -= bits;
+                    unpacked.push(map(((*word >> *filled) & mask) as u32));
+                }
+            };
+
+            let chunks = bytes.chunks_exact(4);
+            let rest = chunks.remainder();
+            for chunk in chunks {
+                word = (word << 32)
+                    | u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as u64;
+                filled += 32;
+                read(&mut word, &mut filled, &mut unpacked);
+            }
+            for &byte in rest {
+                word = (word << 8) | byte as u64;
+                filled += 8;
+                read(&mut word, &mut filled, &mut unpacked);
+            }
+        }
+    }
+
+    unpacked
+}
+
+/// Packs the codes of `items` like [`pack`], a chunk of items at a time.
+pub(crate) fn pack_chunks<T: Sync>(
+    items: &[T],
+    bits: usize,
+    code: impl Fn(&T) -> u32 + Sync,
+) -> BitVec<u8, Msb0> {
+    let mut parts = parallel::map_chunks(items.len(), |range| {
+        let chunk = &items[range];
+        pack(chunk.iter().map(&code), chunk.len(), bits)
+    });
+    if parts.len() <= 1 {
+        return parts.pop().unwrap_or_default();
+    }
+
+    // Every chunk but the last has a multiple of 8 codes, so it fills its bytes
+    let mut bytes = Vec::with_capacity((items.len() * bits).div_ceil(8));
+    for part in &parts {
+        bytes.extend_from_slice(part.as_raw_slice());
+    }
+    let mut packed = BitVec::from_vec(bytes);
+    packed.truncate(items.len() * bits);
+    packed
+}
+
+/// Unpacks the codes of `data` like [`unpack`], a chunk of codes at a time.
+pub(crate) fn unpack_chunks<T: Send>(
+    data: &BitSlice<u8, Msb0>,
+    bits: usize,
+    map: impl Fn(u32) -> T + Sync,
+) -> Vec<T> {
+    let mut parts = parallel::map_chunks(data.len() / bits, |range| {
+        unpack(&data[range.start * bits..range.end * bits], bits, &map)
+    });
+    match parts.len() {
+        0 | 1 => parts.pop().unwrap_or_default(),
+        _ => parts.into_iter().flatten().collect(),
+    }
+}
@@ -0,0 +1,58 @@
+use super::*;
+
+/// Packs codes one bit range at a time, as a reference
+fn reference(codes: &[u32], bits: usize) -> BitVec<u8, Msb0> {
+    let mut packed = BitVec::<u8, Msb0>::repeat(false, codes.len() * bits);
+    for (chunk, &code) in packed.chunks_exact_mut(bits).zip(codes) {
+        for (i, mut bit) in chunk.iter_mut().enumerate() {
+            *bit = (code >> (bits - 1 - i)) & 1 == 1;
+        }
+    }
+    packed
+}
+
+/// Codes of every width, with every bit pattern
+fn codes(bits: usize, count: usize) -> Vec<u32> {
+    let mask = ((1u64 << bits) - 1) as u32;
+    (0..count as u32)
+        .map(|i| i.wrapping_mul(0x9E37_79B9).rotate_left(i) & mask)
+        .collect()
+}
+
+/// Should pack codes of every width like a bit at a time
+#[test]
+fn packing_pack() {
+    for bits in 1..=32 {
+        for count in [0, 1, 2, 3, 7, 8, 9, 33] {
+            let codes = codes(bits, count);
+            let packed = pack(codes.iter().copied(), count, bits);
+            assert_eq!(
+                packed,
+                reference(&codes, bits),
+                "{bits} bits, {count} codes"
+            );
+        }
+    }
+}
+
+/// Should unpack codes of every width, from slices starting at any bit
+#[test]
+fn packing_unpack() {
+    for bits in 1..=32 {
+        for count in [0, 1, 2, 3, 7, 8, 9, 33] {
+            let codes = codes(bits, count);
+            for offset in 0..8 {
+                let mut data = BitVec::<u8, Msb0>::repeat(true, offset);
+                data.extend_from_bitslice(&reference(&codes, bits));
+                // Trailing bits that don't make a whole code
+                data.extend_from_bitslice(&BitVec::<u8, Msb0>::repeat(true, bits - 1));
+
+                let unpacked = unpack(&data[offset..], bits, |code| code);
+                assert_eq!(
+                    unpacked, codes,
+                    "{bits} bits, {count} codes, offset {offset}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! ## Palette quantization
+//!
+//! Instead of dividing the color space in equal intervals, a palette quantization picks the colors
+//! that best represent a particular image, and stores for each pixel the index of the nearest
+//! palette color. With 256 colors, a photo quantized to a palette looks way better than one
+//! uniformly quantized to `(3, 3, 2)`, for the same 8 bits per pixel.
+//!
+//! The palette is stored in the image header: a 4 bit tag (`0`), the number of colors minus one
+//! (16 bits) and the colors (24 bits each).
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use greenfield::quantization::Palette;
+//!
+//! let colors = vec![
+//!     Rgb::new(250, 10, 10),
+//!     Rgb::new(255, 0, 0),
+//!     Rgb::new(0, 0, 250),
+//!     Rgb::new(10, 10, 255),
+//! ];
+//! let image = Image::new(2, 2, UniformQuantization::default(), colors.clone())?;
+//!
+//! // Two colors: one for the reds, one for the blues
+//! let palette = Palette::median_cut(&image, 2)?;
+//! let image = Image::new(2, 2, palette, colors)?;
+//!
+//! assert_eq!(image.quantizer().bits_per_pixel(), 1);
+//! assert_eq!(image.colors().next(), Some(&Rgb::new(253, 5, 5)));
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use deku::bitvec::{BitSlice, BitVec, Msb0};
+use deku::prelude::*;
+
+use super::Quantizer;
+use crate::color::{Difference, Rgb};
+use crate::error::{GreenfieldError, GreenfieldResult};
+use crate::image::Image;
+
+#[cfg(test)]
+mod tests;
+
+/// ## A palette of colors
+///
+/// Colors are quantized to the index of the nearest palette color (in the RGB space), so a
+/// palette of N colors takes `ceil(log2(N))` bits per pixel. Palettes have between 1 and
+/// [`Palette::MAX_SIZE`] colors.
+#[derive(Debug, Eq, Clone, PartialEq, DekuRead, DekuWrite)]
+#[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
+pub struct Palette {
+    /// Index of the last color, so every 16 bit value is a valid size.
+    #[deku(bits = "16")]
+    last: u16,
+    #[deku(count = "*last as usize + 1")]
+    colors: Vec<Rgb>,
+    /// Indexes of the colors sorted by color, to search the nearest color without comparing it to
+    /// the whole palette (see [`Palette::nearest`]).
+    #[deku(skip, default = "sorted(colors)")]
+    sorted: Vec<u16>,
+}
+
+impl Palette {
+    /// Maximum number of colors of a palette.
+    pub const MAX_SIZE: usize = 1 << 16;
+
+    /// ## Creates a palette from its colors
+    ///
+    /// ## Errors
+    /// - If there are no colors, or more than [`Palette::MAX_SIZE`].
+    pub fn new(colors: Vec<Rgb>) -> GreenfieldResult<Self> {
+        match colors.len() {
+            1..=Self::MAX_SIZE => Ok(Self {
+                last: (colors.len() - 1) as u16,
+                sorted: sorted(&colors),
+                colors,
+            }),
+            size => Err(GreenfieldError::InvalidPaletteSize(size)),
+        }
+    }
+
+    /// ## Builds a palette of at most `size` colors with the median cut algorithm
+    ///
+    /// The colors of the image are put in a box, which is recursively split at the median of its
+    /// longest side (weighted by how many pixels have each color), until there are `size` boxes.
+    /// Each palette color is the mean of the pixels in a box. If the image has less than `size`
+    /// distinct colors, the palette is exactly those colors.
+    ///
+    /// ## Errors
+    /// - If `size` is 0 or more than [`Palette::MAX_SIZE`].
+    /// - If the image is empty.
+    pub fn median_cut(image: &Image, size: usize) -> GreenfieldResult<Self> {
+        if !(1..=Self::MAX_SIZE).contains(&size) {
+            return Err(GreenfieldError::InvalidPaletteSize(size));
+        }
+
+        let mut boxes = match histogram(image.colors()) {
+            colors if colors.is_empty() => return Err(GreenfieldError::InvalidPaletteSize(0)),
+            colors => alloc::vec![ColorBox::new(colors)],
+        };
+
+        while boxes.len() < size {
+            // The box with the longest side is split, if any can be
+            let Some((i, _)) = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| b.colors.len() > 1)
+                .max_by_key(|(i, b)| (b.range().1, core::cmp::Reverse(*i)))
+            else {
+                break;
+            };
+
+            let (a, b) = boxes.swap_remove(i).split();
+            boxes.push(a);
+            boxes.push(b);
+        }
+
+        Self::new(boxes.iter().map(ColorBox::mean).collect())
+    }
+
+    /// ## The colors of the palette
+    pub fn colors(&self) -> &[Rgb] {
+        &self.colors
+    }
+
+    /// ## Returns the index of the palette color nearest to `color`
+    ///
+    /// Ties are resolved to the lowest index.
+    ///
+    /// Palette colors are found with a binary search, so encoding an image already quantized to
+    /// the palette (e.g. by an [`Octree`](super::Octree)) doesn't compare its colors at all. Other
+    /// colors are searched from there in both directions, until the difference in red alone is
+    /// farther than the nearest color so far, so large palettes only compare a fraction of their
+    /// colors.
+    pub fn nearest(&self, color: &Rgb) -> usize {
+        let start = self
+            .sorted
+            .partition_point(|&i| self.colors[i as usize].bytes() < color.bytes());
+        // The first of equal colors has the lowest index
+        match self.sorted.get(start) {
+            Some(&i) if self.colors[i as usize] == *color => return i as usize,
+            _ => (),
+        }
+
+        // The distance and index of the nearest color, to break ties by index. Returns false when
+        // the rest of the colors on that side are even farther away in red.
+        let mut nearest = (u32::MAX, 0);
+        let mut visit = |i: u16| {
+            let c = &self.colors[i as usize];
+            if (c.r as i32 - color.r as i32).pow(2) as u32 > nearest.0 {
+                return false;
+            }
+            nearest = nearest.min((distance(c, color), i as usize));
+            true
+        };
+        for &i in &self.sorted[start..] {
+            if !visit(i) {
+                break;
+            }
+        }
+        for &i in self.sorted[..start].iter().rev() {
+            if !visit(i) {
+                break;
+            }
+        }
+        nearest.1
+    }
+
+    /// ## Returns the index of the palette color nearest to `color` by a perceptual difference
+    ///
+    /// Like [`Palette::nearest`], but comparing colors with `difference` (see [`Difference`]).
+    /// Ties are resolved to the lowest index.
+    pub fn nearest_by(&self, color: &Rgb, difference: Difference) -> usize {
+        self.colors
+            .iter()
+            .map(|c| difference.between(c, color))
+            .enumerate()
+            .fold((0, f64::INFINITY), |nearest, (i, d)| match d < nearest.1 {
+                true => (i, d),
+                false => nearest,
+            })
+            .0
+    }
+}
+
+impl Display for Palette {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[{} colors]", self.colors.len())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Palette {
+    /// ## Serializes the palette as a list of colors
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.colors.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Palette {
+    /// ## Deserializes the palette from a list of colors
+    ///
+    /// The size is validated the same way as in [`Palette::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let colors = Vec::<Rgb>::deserialize(deserializer)?;
+        Self::new(colors).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Quantizer for Palette {
+    fn bits_per_pixel(&self) -> usize {
+        // ceil(log2(size)), but at least a bit
+        (u16::BITS - self.last.leading_zeros()).max(1) as usize
+    }
+
+    fn quantize(&self, color: &Rgb) -> u32 {
+        self.nearest(color) as u32
+    }
+
+    /// Codes past the end of the palette are decoded as its last color.
+    fn dequantize(&self, code: u32) -> Rgb {
+        let index = (code as usize).min(self.last as usize);
+        self.colors[index].clone()
+    }
+
+    fn write_params(&self, output: &mut BitVec<u8, Msb0>) -> GreenfieldResult<()> {
+        Ok(self.write(output, deku::ctx::Endian::Big)?)
+    }
+
+    fn read_params(input: &BitSlice<u8, Msb0>) -> GreenfieldResult<(&BitSlice<u8, Msb0>, Self)> {
+        Ok(Self::read(input, deku::ctx::Endian::Big)?)
+    }
+}
+
+/// Squared euclidean distance between two colors.
+pub(crate) fn distance(a: &Rgb, b: &Rgb) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.r, b.r) + d(a.g, b.g) + d(a.b, b.b)
+}
+
+/// The indexes of `colors` sorted by color, then by index.
+fn sorted(colors: &[Rgb]) -> Vec<u16> {
+    let mut indexes = (0..colors.len() as u32)
+        .map(|i| i as u16)
+        .collect::<Vec<_>>();
+    indexes.sort_by_key(|&i| colors[i as usize].bytes());
+    indexes
+}
+
+/// The distinct colors, with how many times each appears, sorted.
+pub(crate) fn histogram<'a>(colors: impl Iterator<Item = &'a Rgb>) -> Vec<(Rgb, usize)> {
+    let mut histogram = BTreeMap::new();
+    for color in colors {
+        *histogram.entry(color.bytes()).or_insert(0) += 1;
+    }
+
+    histogram
+        .into_iter()
+        .map(|([r, g, b], count)| (Rgb::new(r, g, b), count))
+        .collect()
+}
+
+/// A box of the median cut: a set of colors with their counts.
+struct ColorBox {
+    colors: Vec<(Rgb, usize)>,
+}
+
+impl ColorBox {
+    fn new(colors: Vec<(Rgb, usize)>) -> Self {
+        Self { colors }
+    }
+
+    /// The channel with the widest range, and that range.
+    fn range(&self) -> (usize, u8) {
+        (0..3)
+            .map(|channel| {
+                let values = self.colors.iter().map(|(c, _)| c.bytes()[channel]);
+                let (min, max) =
+                    values.fold((u8::MAX, u8::MIN), |(min, max), v| (min.min(v), max.max(v)));
+                (channel, max.saturating_sub(min))
+            })
+            .max_by_key(|&(channel, range)| (range, core::cmp::Reverse(channel)))
+            .unwrap_or_default()
+    }
+
+    /// Splits the box at the weighted median of its widest channel. Both halves are non empty.
+    fn split(mut self) -> (Self, Self) {
+        let (channel, _) = self.range();
+        self.colors.sort_by_key(|(c, _)| c.bytes()[channel]);
+
+        let total = self.colors.iter().map(|(_, count)| count).sum::<usize>();
+        let mut seen = 0;
+        let median = self
+            .colors
+            .iter()
+            .position(|(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or_default();
+
+        // The median color goes to the lower half, unless it's the last one
+        let at = (median + 1).min(self.colors.len() - 1);
+        let upper = self.colors.split_off(at);
+        (self, Self::new(upper))
+    }
+
+    /// The mean of the colors, weighted by their counts.
+    fn mean(&self) -> Rgb {
+        let mut sum = [0usize; 3];
+        let mut total = 0;
+        for (color, count) in &self.colors {
+            color
+                .bytes()
+                .iter()
+                .zip(sum.iter_mut())
+                .for_each(|(c, s)| *s += *c as usize * count);
+            total += count;
+        }
+
+        let [r, g, b] = sum.map(|s| ((s + total / 2) / total) as u8);
+        Rgb::new(r, g, b)
+    }
+}
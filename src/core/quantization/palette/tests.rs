@@ -0,0 +1,243 @@
+use super::*;
+use crate::image::Header;
+use crate::quantization::tests::row;
+use crate::quantization::Quantization;
+
+/// Palettes have between 1 and 65536 colors
+#[test]
+fn palette_new() -> GreenfieldResult<()> {
+    let palette = Palette::new(vec![Rgb::new(1, 2, 3)])?;
+    assert_eq!(palette.colors(), &[Rgb::new(1, 2, 3)]);
+
+    assert!(Palette::new(vec![Rgb::new(0, 0, 0); Palette::MAX_SIZE]).is_ok());
+    assert!(matches!(
+        Palette::new(vec![]),
+        Err(GreenfieldError::InvalidPaletteSize(0))
+    ));
+    assert!(matches!(
+        Palette::new(vec![Rgb::new(0, 0, 0); Palette::MAX_SIZE + 1]),
+        Err(GreenfieldError::InvalidPaletteSize(65537))
+    ));
+
+    Ok(())
+}
+
+/// Should take the smallest number of bits that can index every color
+#[test]
+fn palette_bits_per_pixel() -> GreenfieldResult<()> {
+    let bits =
+        |size: usize| Palette::new(vec![Rgb::new(0, 0, 0); size]).map(|p| p.bits_per_pixel());
+
+    assert_eq!(bits(1)?, 1);
+    assert_eq!(bits(2)?, 1);
+    assert_eq!(bits(3)?, 2);
+    assert_eq!(bits(4)?, 2);
+    assert_eq!(bits(5)?, 3);
+    assert_eq!(bits(256)?, 8);
+    assert_eq!(bits(257)?, 9);
+    assert_eq!(bits(Palette::MAX_SIZE)?, 16);
+
+    Ok(())
+}
+
+/// Should map colors to the nearest palette color
+#[test]
+fn palette_nearest() -> GreenfieldResult<()> {
+    let palette = Palette::new(vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(255, 255, 255),
+        Rgb::new(255, 0, 0),
+    ])?;
+
+    assert_eq!(palette.nearest(&Rgb::new(10, 20, 30)), 0);
+    assert_eq!(palette.nearest(&Rgb::new(200, 220, 240)), 1);
+    assert_eq!(palette.nearest(&Rgb::new(200, 50, 40)), 2);
+    assert_eq!(palette.quantize(&Rgb::new(200, 50, 40)), 2);
+    assert_eq!(palette.dequantize(2), Rgb::new(255, 0, 0));
+    // Codes past the end are the last color
+    assert_eq!(palette.dequantize(3), Rgb::new(255, 0, 0));
+
+    // Ties go to the lowest index
+    let palette = Palette::new(vec![Rgb::new(0, 0, 0), Rgb::new(2, 0, 0)])?;
+    assert_eq!(palette.nearest(&Rgb::new(1, 0, 0)), 0);
+
+    Ok(())
+}
+
+/// Should find the same colors as comparing the whole palette, in large palettes
+#[test]
+fn palette_nearest_large() -> GreenfieldResult<()> {
+    // Scattered colors, with repeated ones and reds
+    let colors = (0..5000u32)
+        .map(|i| i.wrapping_mul(2654435761) >> 8)
+        .map(|x| Rgb::new((x >> 16) as u8 & 0xf0, (x >> 8) as u8, x as u8))
+        .collect::<Vec<_>>();
+    let palette = Palette::new(colors.clone())?;
+    let linear = |color: &Rgb| {
+        (colors.iter().enumerate())
+            .min_by_key(|(_, c)| distance(c, color))
+            .map(|(i, _)| i)
+    };
+
+    for r in (0..=255).step_by(15) {
+        for g in (0..=255).step_by(15) {
+            for b in (0..=255).step_by(15) {
+                let color = Rgb::new(r, g, b);
+                assert_eq!(Some(palette.nearest(&color)), linear(&color), "{color:?}");
+            }
+        }
+    }
+    for color in &colors {
+        assert_eq!(Some(palette.nearest(color)), linear(color), "{color:?}");
+    }
+
+    Ok(())
+}
+
+/// Should map colors to the nearest palette color by a perceptual difference
+#[test]
+fn palette_nearest_by() -> GreenfieldResult<()> {
+    let palette = Palette::new(vec![Rgb::new(0, 0, 80), Rgb::new(0, 50, 0)])?;
+    let black = Rgb::new(0, 0, 0);
+
+    // The green is closer in RGB, but the blue looks closer
+    assert_eq!(palette.nearest(&black), 1);
+    assert_eq!(palette.nearest_by(&black, Difference::Rgb), 1);
+    assert_eq!(palette.nearest_by(&black, Difference::Oklab), 0);
+
+    // Ties go to the lowest index
+    let palette = Palette::new(vec![Rgb::new(0, 0, 0), Rgb::new(0, 0, 0)])?;
+    assert_eq!(palette.nearest_by(&black, Difference::DeltaE2000), 0);
+
+    Ok(())
+}
+
+/// Should split the colors at the median of the widest channel
+#[test]
+fn palette_median_cut() -> GreenfieldResult<()> {
+    let image = row(vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(10, 0, 0),
+        Rgb::new(0, 200, 0),
+        Rgb::new(0, 210, 0),
+    ])?;
+
+    // Green is the widest channel, so the first cut separates the greens
+    let palette = Palette::median_cut(&image, 2)?;
+    assert_eq!(palette.colors(), &[Rgb::new(5, 0, 0), Rgb::new(0, 205, 0)]);
+
+    // A single color is the mean of the image
+    let palette = Palette::median_cut(&image, 1)?;
+    assert_eq!(palette.colors(), &[Rgb::new(3, 103, 0)]);
+
+    // With enough colors, the palette is exactly the colors of the image
+    let palette = Palette::median_cut(&image, 16)?;
+    assert_eq!(palette.colors().len(), 4);
+    for color in image.colors() {
+        assert!(palette.colors().contains(color));
+    }
+
+    Ok(())
+}
+
+/// The median should be weighted by how many pixels have each color
+#[test]
+fn palette_median_cut_weighted() -> GreenfieldResult<()> {
+    let mut colors = vec![Rgb::new(0, 0, 0); 6];
+    colors.extend([Rgb::new(100, 0, 0), Rgb::new(200, 0, 0)]);
+    let image = row(colors)?;
+
+    // The median is black, so it's alone in its box
+    let palette = Palette::median_cut(&image, 2)?;
+    assert_eq!(palette.colors(), &[Rgb::new(0, 0, 0), Rgb::new(150, 0, 0)]);
+
+    Ok(())
+}
+
+/// Should fail on invalid sizes and empty images
+#[test]
+fn palette_median_cut_err() -> GreenfieldResult<()> {
+    let image = row(vec![Rgb::new(1, 2, 3)])?;
+    assert!(Palette::median_cut(&image, 0).is_err());
+    assert!(Palette::median_cut(&image, Palette::MAX_SIZE + 1).is_err());
+
+    let empty = row(vec![])?;
+    assert!(Palette::median_cut(&empty, 4).is_err());
+
+    Ok(())
+}
+
+/// Should write the palette size and colors, behind the palette tag
+#[test]
+fn palette_params() -> GreenfieldResult<()> {
+    let palette = Palette::new(vec![Rgb::new(1, 2, 3), Rgb::new(4, 5, 6)])?;
+    let quantization = Quantization::from(palette.clone());
+
+    let mut params = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut params)?;
+    assert_eq!(params.len(), 4 + 16 + 2 * 24);
+    // Tag 0, then the index of the last color
+    assert!(params[..19].not_any());
+    assert!(params[19]);
+
+    let (rest, read) = Quantization::read_params(&params)?;
+    assert!(rest.is_empty());
+    assert_eq!(read, quantization);
+
+    // Truncated palettes
+    assert!(Quantization::read_params(&params[..40]).is_err());
+
+    Ok(())
+}
+
+/// Should round trip images quantized to a palette
+#[test]
+fn palette_image() -> GreenfieldResult<()> {
+    let colors = vec![
+        Rgb::new(250, 10, 10),
+        Rgb::new(10, 250, 10),
+        Rgb::new(10, 10, 250),
+        Rgb::new(255, 0, 0),
+        Rgb::new(0, 255, 0),
+        Rgb::new(0, 0, 255),
+    ];
+    let palette = Palette::median_cut(&row(colors.clone())?, 3)?;
+    let image = Image::new(3, 2, palette.clone(), colors)?;
+
+    // Every pixel is a palette color
+    assert!(image.colors().all(|c| palette.colors().contains(c)));
+
+    let header = image.header();
+    assert_eq!(header.bits(), Header::BITS - 12 + 4 + 16 + 3 * 24);
+
+    let serialized = image.clone().serialize()?;
+    // Header, then 6 pixels of 2 bits
+    assert_eq!(serialized.len(), (header.bits() + 6 * 2).div_ceil(8));
+    assert_eq!(Header::deserialize(&serialized)?, header);
+    assert_eq!(Image::deserialize(&serialized)?, image);
+
+    Ok(())
+}
+
+/// Palettes should be serialized as lists of colors, and validated on deserialization
+#[cfg(feature = "serde")]
+#[test]
+fn palette_serde() -> GreenfieldResult<()> {
+    use crate::quantization::UniformQuantization;
+
+    let quantization = Quantization::from(Palette::new(vec![Rgb::new(255, 0, 0)])?);
+    let serialized = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(serialized, r##"["#ff0000"]"##);
+
+    let deserialized = serde_json::from_str::<Quantization>(&serialized).unwrap();
+    assert_eq!(deserialized, quantization);
+
+    // Tuples are still uniform quantizations
+    let deserialized = serde_json::from_str::<Quantization>("[5,6,5]").unwrap();
+    assert_eq!(deserialized, UniformQuantization::new(5, 6, 5)?);
+
+    assert!(serde_json::from_str::<Quantization>("[]").is_err());
+    assert!(serde_json::from_str::<Palette>("[]").is_err());
+
+    Ok(())
+}
@@ -0,0 +1,788 @@
+//! ## Quantization and reconstruction policies
+//!
+//! A [`UniformQuantization`](super::UniformQuantization) divides each component in `2^bits`
+//! intervals. How a component is mapped to the index of its interval is its [`Rounding`], and how
+//! an index is mapped back to a component is its [`Reconstruction`]:
+//!
+//! - [`Reconstruction::Midpoint`] (the default) reconstructs the middle of each interval. With 1
+//!   bit, the components are reconstructed to 64 and 192, so pure black and white are lost.
+//! - [`Reconstruction::Replicate`] repeats the bits of the index until the 8 bits are filled,
+//!   spreading the reconstructions over the full range: with 1 bit, 0 and 255.
+//! - [`Reconstruction::Lookup`] reconstructs each component with a custom table (see [`Lut`]).
+//!
+//! [`Rounding::Truncate`] (the default) keeps the most significant bits of each component, while
+//! [`Rounding::Nearest`] picks the index whose reconstruction is nearest to the component, whatever
+//! the reconstruction is.
+//!
+//! The components are the ones of a [`ColorSpace`]: RGB by default, or YCbCr and Oklab, which
+//! separate the lightness, to which the eye is more sensitive, from the chroma. A `(6, 3, 3)`
+//! YCbCr quantization looks way better than a `(4, 4, 4)` RGB one.
+//!
+//! Before being quantized, the components can be companded with a [`Curve`] each (see
+//! [`Companding`]), so that the uniform intervals aren't uniform over the components: gamma and
+//! logarithmic curves spend more of them on the shadows. And with [`Bounds`], the intervals are
+//! spread over the range of each component that an image actually uses.
+//!
+//! Quantizations with the default policies are stored as a plain `(bits_r, bits_g, bits_b)` tuple,
+//! so their images are the same as before policies existed. Otherwise, the tuple is preceded by a
+//! `15` tag and followed by the policies that aren't the default: each one is a 4 bit tag and its
+//! value, and a `0` tag ends the list.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//! use greenfield::quantization::{Reconstruction, Rounding};
+//!
+//! let quantization = UniformQuantization::new(1, 1, 1)?
+//!     .with_rounding(Rounding::Nearest)
+//!     .with_reconstruction(Reconstruction::Replicate)?;
+//!
+//! // Pure black and white are kept
+//! let image = Image::new(2, 1, quantization, vec![Rgb::new(0, 0, 0), Rgb::new(255, 255, 255)])?;
+//! assert_eq!(image.colors().nth(0), Some(&Rgb::new(0, 0, 0)));
+//! assert_eq!(image.colors().nth(1), Some(&Rgb::new(255, 255, 255)));
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::boxed::Box;
+#[allow(unused_imports)] // Used by the deku derives without std
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use deku::bitvec::{BitSlice, BitVec, Msb0};
+use deku::prelude::*;
+
+use crate::color::{space, Rgb};
+use crate::error::{GreenfieldError, GreenfieldResult};
+
+#[cfg(test)]
+mod tests;
+
+/// ## How components are mapped to the indexes of their intervals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "4",
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian"
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Rounding {
+    /// Keeps the most significant bits of the component: the interval it falls in.
+    #[default]
+    #[deku(id = "0")]
+    Truncate,
+    /// Picks the index whose reconstruction is nearest to the component.
+    #[deku(id = "1")]
+    Nearest,
+}
+
+impl Display for Rounding {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Truncate => write!(f, "truncate"),
+            Self::Nearest => write!(f, "nearest"),
+        }
+    }
+}
+
+/// ## How indexes are mapped back to components
+///
+/// The tables of [`Reconstruction::Lookup`] depend on the bits of each component, so they're
+/// passed as context to read it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "4",
+    ctx = "endian: deku::ctx::Endian, bits: [u8; 3]"
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Reconstruction {
+    /// The middle of the interval: `(index << (8 - bits)) + (1 << (7 - bits))`.
+    #[default]
+    #[deku(id = "0")]
+    Midpoint,
+    /// The bits of the index, repeated: `0b101` is reconstructed to `0b10110110`.
+    #[deku(id = "1")]
+    Replicate,
+    /// A custom table per component.
+    #[deku(id = "2")]
+    Lookup(#[deku(ctx = "endian, bits")] Lut),
+}
+
+impl Reconstruction {
+    /// ## Reconstructs the index of a component of `bits` bits
+    ///
+    /// `channel` is the component (0 for red, 1 for green and 2 for blue), which selects the
+    /// table of [`Reconstruction::Lookup`].
+    ///
+    /// ## Errors
+    /// If the table of a [`Reconstruction::Lookup`] doesn't have `2^bits` entries, e.g. because the
+    /// bits of the quantization changed after the tables were set.
+    pub fn component(&self, channel: usize, index: u8, bits: u8) -> GreenfieldResult<u8> {
+        match self {
+            Self::Midpoint => {
+                let width = 1u16 << (8 - bits);
+                Ok((((index as u16) << (8 - bits)) + width / 2) as u8)
+            }
+            Self::Replicate => {
+                let mut value = (index as u16) << (8 - bits);
+                let mut filled = bits;
+                while filled < 8 {
+                    value |= value >> filled;
+                    filled *= 2;
+                }
+                Ok(value as u8)
+            }
+            Self::Lookup(lut) => {
+                let table = &lut.tables[channel];
+                match table.get(index as usize) {
+                    Some(&value) if table.len() == 1 << bits => Ok(value),
+                    _ => Err(GreenfieldError::InvalidLookupTable(table.len())),
+                }
+            }
+        }
+    }
+}
+
+impl Display for Reconstruction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Midpoint => write!(f, "midpoint"),
+            Self::Replicate => write!(f, "replicate"),
+            Self::Lookup(_) => write!(f, "lookup"),
+        }
+    }
+}
+
+/// ## The color space whose components are quantized
+///
+/// Colors are converted to the components of the space before being quantized, and back to RGB
+/// once reconstructed, so the bits of a quantization apply to the components of the space.
+///
+/// The chroma components of neutral colors are 128. That's the boundary of two intervals, so with
+/// [`Reconstruction::Midpoint`] chroma is quantized half an interval up and reconstructed half an
+/// interval down: grays are reconstructed to grays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "4",
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian"
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum ColorSpace {
+    /// Red, green and blue.
+    #[default]
+    #[deku(id = "0")]
+    Rgb,
+    /// Luma and blue and red chroma, full range (BT.601, as in JPEG).
+    #[deku(id = "1")]
+    YCbCr,
+    /// Perceptual lightness, and green-red and blue-yellow chroma. `L` is scaled to bytes, and `a`
+    /// and `b` are scaled by 408 and centered on 128, to cover the RGB colors.
+    #[deku(id = "2")]
+    Oklab,
+}
+
+impl ColorSpace {
+    /// ## Converts a color to the components of the space, as bytes
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::ColorSpace;
+    ///
+    /// let [y, cb, cr] = ColorSpace::YCbCr.components(&Rgb::new(255, 255, 255));
+    /// assert_eq!([y, cb, cr], [255, 128, 128]);
+    /// ```
+    pub fn components(&self, color: &Rgb) -> [u8; 3] {
+        match self {
+            Self::Rgb => color.bytes(),
+            Self::YCbCr => space::to_ycbcr(color).map(space::byte),
+            Self::Oklab => space::to_oklab_bytes(color),
+        }
+    }
+
+    /// ## Converts components of the space back to a color
+    ///
+    /// Components out of the RGB cube are clamped to it.
+    pub fn color(&self, components: [u8; 3]) -> Rgb {
+        let [r, g, b] = components;
+        match self {
+            Self::Rgb => Rgb::new(r, g, b),
+            Self::YCbCr => space::from_ycbcr(components.map(f64::from)),
+            Self::Oklab => space::from_oklab_bytes(components),
+        }
+    }
+}
+
+impl Display for ColorSpace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Rgb => write!(f, "rgb"),
+            Self::YCbCr => write!(f, "ycbcr"),
+            Self::Oklab => write!(f, "oklab"),
+        }
+    }
+}
+
+/// ## A companding curve of a component
+///
+/// Curves map the components to the values that are uniformly quantized, and back once
+/// reconstructed. Uniform intervals over sRGB components are as wide in the shadows as in the
+/// highlights, while the eye tells dark shades apart much better: a gamma or logarithmic curve
+/// spends more of the intervals on the shadows.
+///
+/// Every curve is turned into a table of the 256 components, and its inverse maps each value to the
+/// middle of the components mapped nearest to it. The tables are computed with only the basic
+/// operations of IEEE 754, so they're the same for every decoder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "4",
+    endian = "endian",
+    ctx = "endian: deku::ctx::Endian"
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Curve {
+    /// The component itself.
+    #[default]
+    #[deku(id = "0")]
+    Identity,
+    /// `255 * (c / 255)^(1 / gamma)`, with the gamma in hundredths: `Gamma(220)` is a gamma of 2.2.
+    /// Gammas above 1 spend more intervals on the shadows.
+    #[deku(id = "1")]
+    Gamma(u16),
+    /// Linear light: the sRGB transfer function is undone, so the intervals are uniform in physical
+    /// intensity. Spends more intervals on the highlights.
+    #[deku(id = "2")]
+    Linear,
+    /// The µ-law: `255 * ln(1 + µ c / 255) / ln(1 + µ)`, with `Log(µ)`. The larger µ, the more
+    /// intervals are spent on the shadows (255 is the one of telephony).
+    #[deku(id = "3")]
+    Log(u16),
+    /// A custom table, with the value of each component. It can't decrease.
+    #[deku(id = "4")]
+    Table(
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                serialize_with = "serialize_table",
+                deserialize_with = "deserialize_table"
+            )
+        )]
+        Box<[u8; 256]>,
+    ),
+}
+
+impl Curve {
+    /// ## The value of every component
+    ///
+    /// ## Errors
+    /// If a gamma or µ is 0, or a table decreases.
+    pub fn table(&self) -> GreenfieldResult<[u8; 256]> {
+        let invalid = |reason: &str| Err(GreenfieldError::InvalidCurve(reason.into()));
+        let curve = |f: &dyn Fn(f64) -> f64| {
+            core::array::from_fn(|c| space::byte(255.0 * f(c as f64 / 255.0)))
+        };
+
+        match self {
+            Self::Identity => Ok(core::array::from_fn(|c| c as u8)),
+            Self::Gamma(0) => invalid("the gamma must be positive"),
+            Self::Gamma(gamma) => Ok(curve(&|c| libm::pow(c, 100.0 / *gamma as f64))),
+            Self::Linear => Ok(core::array::from_fn(|c| {
+                space::byte(255.0 * space::to_linear(c as u8))
+            })),
+            Self::Log(0) => invalid("µ must be positive"),
+            Self::Log(mu) => {
+                let mu = *mu as f64;
+                Ok(curve(&|c| libm::log(1.0 + mu * c) / libm::log(1.0 + mu)))
+            }
+            Self::Table(table) if table.windows(2).any(|w| w[0] > w[1]) => {
+                invalid("tables can't decrease")
+            }
+            Self::Table(table) => Ok(**table),
+        }
+    }
+}
+
+impl Display for Curve {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Identity => write!(f, "identity"),
+            Self::Gamma(gamma) => write!(f, "gamma {}.{:02}", gamma / 100, gamma % 100),
+            Self::Linear => write!(f, "linear"),
+            Self::Log(mu) => write!(f, "log {mu}"),
+            Self::Table(_) => write!(f, "table"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_table<S: serde::Serializer>(
+    table: &[u8; 256],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(&table[..], serializer)
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_table<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Box<[u8; 256]>, D::Error> {
+    let table = <Vec<u8> as serde::Deserialize>::deserialize(deserializer)?;
+    let len = table.len();
+    table
+        .into_boxed_slice()
+        .try_into()
+        .map_err(|_| serde::de::Error::invalid_length(len, &"256 entries"))
+}
+
+/// ## The companding curves of the red, green and blue components
+///
+/// The components are companded after being converted to the [`ColorSpace`] of the quantization,
+/// and expanded before being converted back.
+///
+/// ## Examples
+///
+/// ```rust
+/// use greenfield::prelude::*;
+/// use greenfield::quantization::{Companding, Curve};
+///
+/// let companding = Companding::new([Curve::Gamma(220), Curve::Gamma(220), Curve::Gamma(220)])?;
+/// let quantization = UniformQuantization::new(4, 4, 4)?.with_companding(companding);
+///
+/// // Dark shades are told apart, while uniform intervals merge them
+/// let dark = |v| quantization.reconstruct(&Rgb::new(v, v, v)).r;
+/// assert_ne!(dark(4), dark(12));
+/// let uniform = UniformQuantization::new(4, 4, 4)?;
+/// assert_eq!(uniform.reconstruct(&Rgb::new(4, 4, 4)), uniform.reconstruct(&Rgb::new(12, 12, 12)));
+/// # Ok::<(), GreenfieldError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Companding {
+    curves: [Curve; 3],
+    /// The table of each curve and its inverse, unless every curve is the identity
+    tables: Option<Box<[[[u8; 256]; 2]; 3]>>,
+}
+
+impl Companding {
+    /// ## Creates the companding of the red, green and blue components
+    ///
+    /// ## Errors
+    /// If a curve is invalid (see [`Curve::table`]).
+    pub fn new(curves: [Curve; 3]) -> GreenfieldResult<Self> {
+        if curves.iter().all(|curve| *curve == Curve::Identity) {
+            return Ok(Self::default());
+        }
+
+        let mut tables = Box::new([[[0u8; 256]; 2]; 3]);
+        for (tables, curve) in tables.iter_mut().zip(&curves) {
+            let forward = curve.table()?;
+            // The middle of the components whose values are nearest. The table doesn't decrease,
+            // so they're consecutive.
+            let inverse = core::array::from_fn(|value| {
+                let distance = |c: &usize| forward[*c].abs_diff(value as u8);
+                let nearest = (0..256).map(|c| distance(&c)).min().unwrap_or(0);
+                let first = (0..256).find(|c| distance(c) == nearest).unwrap_or(0);
+                let last = (0..256).rfind(|c| distance(c) == nearest).unwrap_or(0);
+                ((first + last) / 2) as u8
+            });
+            *tables = [forward, inverse];
+        }
+
+        Ok(Self {
+            curves,
+            tables: Some(tables),
+        })
+    }
+
+    /// ## The curves of the red, green and blue components
+    pub fn curves(&self) -> &[Curve; 3] {
+        &self.curves
+    }
+
+    /// ## Whether every curve is the identity
+    pub fn is_identity(&self) -> bool {
+        self.tables.is_none()
+    }
+
+    /// ## Maps a component to the value that is quantized
+    pub fn compand(&self, channel: usize, value: u8) -> u8 {
+        match &self.tables {
+            Some(tables) => tables[channel][0][value as usize],
+            None => value,
+        }
+    }
+
+    /// ## Maps a reconstructed value back to a component
+    pub fn expand(&self, channel: usize, value: u8) -> u8 {
+        match &self.tables {
+            Some(tables) => tables[channel][1][value as usize],
+            None => value,
+        }
+    }
+}
+
+impl Display for Companding {
+    /// A single curve if they're all the same, e.g. `gamma 2.20`, or the three of them, e.g.
+    /// `log 255/identity/identity`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [r, g, b] = &self.curves;
+        if r == g && g == b {
+            write!(f, "{r}")
+        } else {
+            write!(f, "{r}/{g}/{b}")
+        }
+    }
+}
+
+impl<'a> DekuRead<'a, deku::ctx::Endian> for Companding {
+    /// The curves are validated the same way as in [`Companding::new`].
+    fn read(
+        input: &'a BitSlice<u8, Msb0>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(&'a BitSlice<u8, Msb0>, Self), DekuError> {
+        let (rest, curves) = <[Curve; 3]>::read(input, endian)?;
+        Ok((rest, Self::new(curves)?))
+    }
+}
+
+impl DekuWrite<deku::ctx::Endian> for Companding {
+    fn write(
+        &self,
+        output: &mut BitVec<u8, Msb0>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(), DekuError> {
+        self.curves.write(output, endian)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Companding {
+    /// ## Serializes the curves of the three components
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.curves.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Companding {
+    /// ## Deserializes the curves of the three components
+    ///
+    /// The curves are validated the same way as in [`Companding::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let curves = <[Curve; 3]>::deserialize(deserializer)?;
+        Self::new(curves).map_err(serde::de::Error::custom)
+    }
+}
+
+/// ## The range of each component that the intervals are spread over
+///
+/// By default, the intervals of a component cover all of its values, from 0 to 255. A dark image
+/// that only uses values up to 60 wastes most of them: with bounds, the components are stretched
+/// from their range to the full one before being quantized, and shrunk back once reconstructed.
+/// Components out of their range are clamped to it.
+///
+/// The bounds are stored in the image header, one byte each.
+///
+/// ## Examples
+///
+/// ```rust
+/// use greenfield::prelude::*;
+/// use greenfield::quantization::Bounds;
+///
+/// let bounds = Bounds::new([0, 0, 0], [60, 60, 60])?;
+/// let quantization = UniformQuantization::new(2, 2, 2)?.with_bounds(bounds);
+///
+/// // The 4 intervals are 15 components wide, instead of 64
+/// assert_eq!(quantization.reconstruct(&Rgb::new(10, 20, 40)), Rgb::new(8, 23, 38));
+/// # Ok::<(), GreenfieldError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bounds {
+    min: [u8; 3],
+    max: [u8; 3],
+}
+
+impl Default for Bounds {
+    /// The full range of every component.
+    fn default() -> Self {
+        Self {
+            min: [u8::MIN; 3],
+            max: [u8::MAX; 3],
+        }
+    }
+}
+
+impl Bounds {
+    /// ## Creates the bounds of the red, green and blue components
+    ///
+    /// ## Errors
+    /// If a minimum is above its maximum.
+    pub fn new(min: [u8; 3], max: [u8; 3]) -> GreenfieldResult<Self> {
+        match min.into_iter().zip(max).find(|(min, max)| min > max) {
+            Some((min, max)) => Err(GreenfieldError::InvalidBounds(min, max)),
+            None => Ok(Self { min, max }),
+        }
+    }
+
+    /// ## The minimums of the red, green and blue components
+    pub fn min(&self) -> [u8; 3] {
+        self.min
+    }
+
+    /// ## The maximums of the red, green and blue components
+    pub fn max(&self) -> [u8; 3] {
+        self.max
+    }
+
+    /// ## Whether every range is the full one
+    pub fn is_full(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// ## Maps a component from its range to the full one
+    pub fn stretch(&self, channel: usize, value: u8) -> u8 {
+        let (min, max) = (self.min[channel], self.max[channel]);
+        let width = (max - min) as u32;
+        if width == 0 {
+            return 0;
+        }
+        let value = (value.clamp(min, max) - min) as u32;
+        ((value * 255 + width / 2) / width) as u8
+    }
+
+    /// ## Maps a value from the full range back to the range of its component
+    pub fn shrink(&self, channel: usize, value: u8) -> u8 {
+        let (min, max) = (self.min[channel], self.max[channel]);
+        let width = (max - min) as u32;
+        min + ((value as u32 * width + 127) / 255) as u8
+    }
+}
+
+impl Display for Bounds {
+    /// The range of each component, e.g. `0-60/0-80/10-255`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [r, g, b] = [0, 1, 2].map(|c| (self.min[c], self.max[c]));
+        write!(f, "{}-{}/{}-{}/{}-{}", r.0, r.1, g.0, g.1, b.0, b.1)
+    }
+}
+
+impl<'a> DekuRead<'a, deku::ctx::Endian> for Bounds {
+    /// The minimums, and then the maximums. They're validated the same way as in [`Bounds::new`].
+    fn read(
+        input: &'a BitSlice<u8, Msb0>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(&'a BitSlice<u8, Msb0>, Self), DekuError> {
+        let (rest, min) = <[u8; 3]>::read(input, endian)?;
+        let (rest, max) = <[u8; 3]>::read(rest, endian)?;
+        Ok((rest, Self::new(min, max)?))
+    }
+}
+
+impl DekuWrite<deku::ctx::Endian> for Bounds {
+    fn write(
+        &self,
+        output: &mut BitVec<u8, Msb0>,
+        endian: deku::ctx::Endian,
+    ) -> Result<(), DekuError> {
+        self.min.write(output, endian)?;
+        self.max.write(output, endian)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Bounds {
+    /// ## Serializes the bounds as the minimums and the maximums
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.min, self.max).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Bounds {
+    /// ## Deserializes the bounds from the minimums and the maximums
+    ///
+    /// The bounds are validated the same way as in [`Bounds::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (min, max) = <([u8; 3], [u8; 3])>::deserialize(deserializer)?;
+        Self::new(min, max).map_err(serde::de::Error::custom)
+    }
+}
+
+/// ## Custom reconstruction tables
+///
+/// One table per component, with the reconstruction of each index: a component quantized to N
+/// bits needs a table of `2^N` entries. The tables don't need to be sorted.
+///
+/// The tables are stored in the image header, one byte per entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lut {
+    tables: [Vec<u8>; 3],
+    /// The index of the nearest entry of each table, for every component value
+    nearest: Box<[[u8; 256]; 3]>,
+}
+
+impl Lut {
+    /// ## Creates the tables of the red, green and blue components
+    ///
+    /// ## Errors
+    /// If a table doesn't have `2^N` entries, with N between 1 and 8.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use greenfield::prelude::*;
+    /// use greenfield::quantization::{Lut, Reconstruction};
+    ///
+    /// // A darker red
+    /// let lut = Lut::new([vec![0, 200], vec![0, 255], vec![0, 255]])?;
+    /// let quantization =
+    ///     UniformQuantization::new(1, 1, 1)?.with_reconstruction(Reconstruction::Lookup(lut))?;
+    ///
+    /// assert_eq!(quantization.get_dequantized_color(&Rgb::new(1, 1, 0)), Rgb::new(200, 255, 0));
+    /// # Ok::<(), GreenfieldError>(())
+    /// ```
+    pub fn new(tables: [Vec<u8>; 3]) -> GreenfieldResult<Self> {
+        if let Some(table) = tables
+            .iter()
+            .find(|table| !(2..=256).contains(&table.len()) || !table.len().is_power_of_two())
+        {
+            return Err(GreenfieldError::InvalidLookupTable(table.len()));
+        }
+
+        let nearest = [0, 1, 2].map(|channel| {
+            let table = &tables[channel];
+            core::array::from_fn(|value| {
+                // The first of the nearest entries
+                (0..table.len())
+                    .min_by_key(|&index| table[index].abs_diff(value as u8))
+                    .unwrap_or(0) as u8
+            })
+        });
+
+        Ok(Self {
+            tables,
+            nearest: Box::new(nearest),
+        })
+    }
+
+    /// ## The tables of the red, green and blue components
+    pub fn tables(&self) -> &[Vec<u8>; 3] {
+        &self.tables
+    }
+
+    /// ## Index of the entry of a table nearest to `value`
+    pub fn nearest(&self, channel: usize, value: u8) -> u8 {
+        self.nearest[channel][value as usize]
+    }
+
+    /// Checks that each table has `2^bits` entries, for the bits of its component.
+    pub(crate) fn check(&self, bits: [u8; 3]) -> GreenfieldResult<()> {
+        match self
+            .tables
+            .iter()
+            .zip(bits)
+            .find(|(t, bits)| t.len() != 1 << bits)
+        {
+            Some((table, _)) => Err(GreenfieldError::InvalidLookupTable(table.len())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> DekuRead<'a, (deku::ctx::Endian, [u8; 3])> for Lut {
+    /// Each table has `2^bits` entries, for the bits of its component.
+    fn read(
+        input: &'a BitSlice<u8, Msb0>,
+        (endian, bits): (deku::ctx::Endian, [u8; 3]),
+    ) -> Result<(&'a BitSlice<u8, Msb0>, Self), DekuError> {
+        let mut rest = input;
+        let mut tables: [Vec<u8>; 3] = Default::default();
+        for (table, bits) in tables.iter_mut().zip(bits) {
+            for _ in 0..1usize << bits {
+                let (remaining, entry) = u8::read(rest, endian)?;
+                table.push(entry);
+                rest = remaining;
+            }
+        }
+
+        Ok((rest, Self::new(tables)?))
+    }
+}
+
+impl DekuWrite<(deku::ctx::Endian, [u8; 3])> for Lut {
+    fn write(
+        &self,
+        output: &mut BitVec<u8, Msb0>,
+        (endian, bits): (deku::ctx::Endian, [u8; 3]),
+    ) -> Result<(), DekuError> {
+        // Otherwise, the tables couldn't be read back
+        self.check(bits)?;
+        self.tables
+            .iter()
+            .flatten()
+            .try_for_each(|entry| entry.write(output, endian))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Lut {
+    /// ## Serializes the tables, as three lists of components
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.tables.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Lut {
+    /// ## Deserializes the tables from three lists of components
+    ///
+    /// The tables are validated the same way as in [`Lut::new`].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tables = <[Vec<u8>; 3]>::deserialize(deserializer)?;
+        Self::new(tables).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A policy of an extended uniform quantization header, or the end of the list.
+#[derive(Debug, Clone, PartialEq, Eq, DekuRead, DekuWrite)]
+#[deku(
+    type = "u8",
+    bits = "4",
+    ctx = "endian: deku::ctx::Endian, bits: [u8; 3]"
+)]
+pub(crate) enum Param {
+    #[deku(id = "0")]
+    End,
+    #[deku(id = "1")]
+    Rounding(#[deku(ctx = "endian")] Rounding),
+    #[deku(id = "2")]
+    Reconstruction(#[deku(ctx = "endian, bits")] Reconstruction),
+    #[deku(id = "3")]
+    ColorSpace(#[deku(ctx = "endian")] ColorSpace),
+    #[deku(id = "4")]
+    Companding(#[deku(ctx = "endian")] Companding),
+    #[deku(id = "5")]
+    Bounds(#[deku(ctx = "endian")] Bounds),
+}
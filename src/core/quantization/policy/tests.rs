@@ -0,0 +1,737 @@
+use super::*;
+use crate::color::Rgb;
+use crate::image::{Header, Image};
+use crate::quantization::{Quantization, Quantizer, UniformQuantization};
+
+/// Every value of a component
+fn values() -> impl Iterator<Item = u8> {
+    0..=u8::MAX
+}
+
+/// Midpoints should match the original reconstruction, without overflowing with 8 bits
+#[test]
+fn policy_midpoint() -> GreenfieldResult<()> {
+    for bits in 1..8 {
+        for index in 0..1u8 << bits {
+            let expected = (index << (8 - bits)) + (1 << (7 - bits));
+            assert_eq!(
+                Reconstruction::Midpoint.component(0, index, bits)?,
+                expected
+            );
+        }
+    }
+
+    for index in values() {
+        assert_eq!(Reconstruction::Midpoint.component(0, index, 8)?, index);
+    }
+
+    Ok(())
+}
+
+/// Bit replication should span the full range
+#[test]
+fn policy_replicate() -> GreenfieldResult<()> {
+    let replicate = |index, bits| Reconstruction::Replicate.component(0, index, bits);
+
+    assert_eq!((replicate(0, 1)?, replicate(1, 1)?), (0, 255));
+    assert_eq!(replicate(0b101, 3)?, 0b10110110);
+    assert_eq!(replicate(0b11111, 5)?, 255);
+    assert_eq!(replicate(0b10000, 5)?, 0b10000100);
+    assert_eq!(replicate(0b100000, 6)?, 0b10000010);
+    for bits in 1..=8 {
+        assert_eq!(replicate(((1u16 << bits) - 1) as u8, bits)?, 255);
+    }
+    for index in values() {
+        assert_eq!(replicate(index, 8)?, index);
+    }
+
+    Ok(())
+}
+
+/// Mixed 8 bit tuples used to overflow with midpoint reconstruction
+#[test]
+fn policy_mixed_8_bits() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(8, 4, 8)?;
+    let color = Rgb::new(255, 255, 255);
+    assert_eq!(
+        quantization.reconstruct(&color),
+        Rgb::new(255, 0b11111000, 255)
+    );
+
+    Ok(())
+}
+
+/// With midpoints, the nearest index is the one of the interval: rounding doesn't change anything
+#[test]
+fn policy_nearest_midpoint() -> GreenfieldResult<()> {
+    for bits in 1..=8 {
+        let truncate = UniformQuantization::new(bits, bits, bits)?;
+        let nearest = truncate.clone().with_rounding(Rounding::Nearest);
+        for value in values() {
+            let color = Rgb::new(value, value, value);
+            assert_eq!(
+                truncate.get_quantized_color(&color),
+                nearest.get_quantized_color(&color)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Rounding to nearest should minimize the error of any reconstruction
+#[test]
+fn policy_nearest_error() -> GreenfieldResult<()> {
+    let lut = Lut::new([
+        vec![200, 10, 90, 255],
+        vec![0, 1, 2, 3],
+        vec![128, 64, 0, 32],
+    ])?;
+
+    for (bits, reconstruction) in (1..=8)
+        .map(|bits| (bits, Reconstruction::Replicate))
+        .chain([(2, Reconstruction::Lookup(lut))])
+    {
+        let quantization = UniformQuantization::new(bits, bits, bits)?
+            .with_rounding(Rounding::Nearest)
+            .with_reconstruction(reconstruction.clone())?;
+
+        for value in values() {
+            let best = (0..=((1u16 << bits) - 1) as u8)
+                .map(|index| Ok(reconstruction.component(2, index, bits)?.abs_diff(value)))
+                .collect::<GreenfieldResult<Vec<u8>>>()?
+                .into_iter()
+                .min();
+            let color = Rgb::new(value, value, value);
+            let error = quantization.reconstruct(&color).b.abs_diff(value);
+            assert_eq!(Some(error), best, "{value} with {bits} bits");
+        }
+    }
+
+    Ok(())
+}
+
+/// Rounding to nearest with bit replication should keep the ends of the range
+#[test]
+fn policy_nearest_replicate() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 2, 2)?
+        .with_rounding(Rounding::Nearest)
+        .with_reconstruction(Reconstruction::Replicate)?;
+
+    assert_eq!(
+        quantization.get_quantized_color(&Rgb::new(42, 43, 60)),
+        Rgb::new(0, 1, 1)
+    );
+    assert_eq!(
+        quantization.reconstruct(&Rgb::new(0, 128, 255)),
+        Rgb::new(0, 170, 255)
+    );
+    assert_eq!(quantization.spacing(), [85; 3]);
+
+    Ok(())
+}
+
+/// Each component should be reconstructed with its table
+#[test]
+fn policy_lookup() -> GreenfieldResult<()> {
+    let lut = Lut::new([vec![10, 20], vec![0, 85, 170, 255], vec![255, 0]])?;
+    assert_eq!(lut.nearest(0, 16), 1);
+    assert_eq!(lut.nearest(2, 10), 1);
+
+    let quantization =
+        UniformQuantization::new(1, 2, 1)?.with_reconstruction(Reconstruction::Lookup(lut))?;
+    assert_eq!(
+        quantization.get_dequantized_color(&Rgb::new(1, 2, 1)),
+        Rgb::new(20, 170, 0)
+    );
+    // 8 bit lookups aren't the identity
+    let identity = Lut::new([(0..=255).collect(), (0..=255).collect(), vec![0; 256]])?;
+    let quantization =
+        UniformQuantization::default().with_reconstruction(Reconstruction::Lookup(identity))?;
+    assert_eq!(
+        quantization.reconstruct(&Rgb::new(1, 2, 3)),
+        Rgb::new(1, 2, 0)
+    );
+
+    Ok(())
+}
+
+/// Tables must have 2^bits entries
+#[test]
+fn policy_lookup_err() -> GreenfieldResult<()> {
+    for size in [0, 1, 3, 100, 512] {
+        assert!(Lut::new([vec![0; size], vec![0; 2], vec![0; 2]]).is_err());
+    }
+
+    let lut = Lut::new([vec![0; 2], vec![0; 4], vec![0; 2]])?;
+    let quantization = UniformQuantization::new(1, 1, 1)?;
+    assert!(matches!(
+        quantization.with_reconstruction(Reconstruction::Lookup(lut)),
+        Err(GreenfieldError::InvalidLookupTable(4))
+    ));
+
+    Ok(())
+}
+
+/// Changing the bits after setting the tables shouldn't panic, and the tables should be rejected
+#[test]
+fn policy_lookup_bits_changed() -> GreenfieldResult<()> {
+    let lut = Lut::new([vec![10, 20], vec![30, 40], vec![50, 60]])?;
+    let mut quantization =
+        UniformQuantization::new(1, 1, 1)?.with_reconstruction(Reconstruction::Lookup(lut))?;
+    quantization.bits_r = 3;
+
+    assert!(matches!(
+        quantization.reconstruction().component(0, 7, 3),
+        Err(GreenfieldError::InvalidLookupTable(2))
+    ));
+    // Red is reconstructed to midpoints instead
+    assert_eq!(
+        quantization.get_dequantized_color(&Rgb::new(7, 1, 0)),
+        Rgb::new(240, 40, 50)
+    );
+    assert!(matches!(
+        Image::new(1, 1, quantization.clone(), vec![Rgb::new(255, 0, 0)]),
+        Err(GreenfieldError::InvalidLookupTable(2))
+    ));
+    assert!(quantization.write_params(&mut BitVec::new()).is_err());
+
+    Ok(())
+}
+
+/// Default policies should be stored as before, and the others after a 15 tag
+#[test]
+fn policy_header() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(5, 6, 5)?;
+    let mut bits = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut bits)?;
+    assert_eq!(
+        bits,
+        deku::bitvec::bits![u8, Msb0; 0,1,0,1, 0,1,1,0, 0,1,0,1]
+    );
+
+    let quantization = quantization.with_rounding(Rounding::Nearest);
+    let mut bits = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut bits)?;
+    assert_eq!(
+        bits,
+        deku::bitvec::bits![u8, Msb0; 1,1,1,1, 0,1,0,1, 0,1,1,0, 0,1,0,1, 0,0,0,1, 0,0,0,1, 0,0,0,0]
+    );
+    let (rest, read) = Quantization::read_params(&bits)?;
+    assert!(rest.is_empty());
+    assert_eq!(read, quantization);
+
+    Ok(())
+}
+
+/// Images should keep their policies through serialization
+#[test]
+fn policy_image() -> GreenfieldResult<()> {
+    let lut = Lut::new([vec![0, 100, 200, 255], vec![7, 8], vec![0, 255]])?;
+    let colors = vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(80, 200, 255),
+        Rgb::new(255, 1, 100),
+    ];
+
+    for quantization in [
+        UniformQuantization::new(2, 1, 1)?.with_reconstruction(Reconstruction::Replicate)?,
+        UniformQuantization::new(2, 1, 1)?
+            .with_rounding(Rounding::Nearest)
+            .with_reconstruction(Reconstruction::Lookup(lut.clone()))?,
+    ] {
+        let image = Image::new(3, 1, quantization.clone(), colors.clone())?;
+        let expected = colors.iter().map(|c| quantization.reconstruct(c));
+        assert!(image.colors().cloned().eq(expected));
+
+        let serialized = image.clone().serialize()?;
+        let deserialized = Image::deserialize(&serialized)?;
+        assert_eq!(deserialized, image);
+        assert_eq!(deserialized.quantizer(), &quantization);
+        assert!(deserialized.header().bits() > Header::BITS);
+    }
+
+    Ok(())
+}
+
+/// Policies should only be shown when they aren't the default
+#[test]
+fn policy_display() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(1, 2, 3)?;
+    assert_eq!(quantization.to_string(), "(1, 2, 3)");
+
+    let quantization = quantization.with_reconstruction(Reconstruction::Replicate)?;
+    assert_eq!(quantization.to_string(), "(1, 2, 3; truncate, replicate)");
+
+    let quantization = UniformQuantization::new(6, 3, 3)?.with_color_space(ColorSpace::YCbCr);
+    assert_eq!(
+        quantization.to_string(),
+        "(6, 3, 3; truncate, midpoint, ycbcr)"
+    );
+
+    let curves = [Curve::Gamma(180), Curve::Gamma(180), Curve::Gamma(180)];
+    let quantization = quantization.with_companding(Companding::new(curves)?);
+    assert_eq!(
+        quantization.to_string(),
+        "(6, 3, 3; truncate, midpoint, ycbcr, gamma 1.80)"
+    );
+
+    let bounds = Bounds::new([0, 0, 10], [60, 80, 255])?;
+    let quantization = UniformQuantization::new(3, 3, 3)?.with_bounds(bounds);
+    assert_eq!(
+        quantization.to_string(),
+        "(3, 3, 3; truncate, midpoint, 0-60/0-80/10-255)"
+    );
+
+    Ok(())
+}
+
+/// Grays should stay gray with few bits of chroma
+#[test]
+fn policy_color_space_gray() -> GreenfieldResult<()> {
+    for color_space in [ColorSpace::YCbCr, ColorSpace::Oklab] {
+        let quantization = UniformQuantization::new(6, 3, 3)?.with_color_space(color_space);
+        for value in values() {
+            let color = quantization.reconstruct(&Rgb::new(value, value, value));
+            assert!(
+                color.r == color.g && color.g == color.b,
+                "{value}: {color:?}"
+            );
+            assert!(color.r.abs_diff(value) <= 8, "{value}: {color:?}");
+        }
+    }
+
+    Ok(())
+}
+
+/// With 8 bits, other color spaces should only lose the precision of their conversion
+#[test]
+fn policy_color_space_8_bits() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::default().with_color_space(ColorSpace::YCbCr);
+    for value in values().step_by(5) {
+        for color in [Rgb::new(value, 255 - value, 40), Rgb::new(7, value, value)] {
+            let reconstructed = quantization.reconstruct(&color);
+            for (a, b) in color.bytes().into_iter().zip(reconstructed.bytes()) {
+                assert!(a.abs_diff(b) <= 2, "{color:?}: {reconstructed:?}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Images should keep their color space through serialization
+#[test]
+fn policy_color_space_image() -> GreenfieldResult<()> {
+    let colors = vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(80, 200, 255),
+        Rgb::new(255, 1, 100),
+        Rgb::new(128, 128, 128),
+    ];
+
+    for quantization in [
+        UniformQuantization::new(4, 2, 2)?.with_color_space(ColorSpace::YCbCr),
+        UniformQuantization::new(5, 3, 3)?
+            .with_rounding(Rounding::Nearest)
+            .with_reconstruction(Reconstruction::Replicate)?
+            .with_color_space(ColorSpace::Oklab),
+    ] {
+        let image = Image::new(2, 2, quantization.clone(), colors.clone())?;
+        let expected = colors.iter().map(|c| quantization.reconstruct(c));
+        assert!(image.colors().cloned().eq(expected));
+
+        let serialized = image.clone().serialize()?;
+        let deserialized = Image::deserialize(&serialized)?;
+        assert_eq!(deserialized, image);
+        assert_eq!(deserialized.quantizer(), &quantization);
+    }
+
+    Ok(())
+}
+
+/// Curves should match their definitions
+#[test]
+fn policy_curve_table() -> GreenfieldResult<()> {
+    let identity = Curve::Identity.table()?;
+    assert!(values().all(|v| identity[v as usize] == v));
+
+    let gamma = Curve::Gamma(220).table()?;
+    let log = Curve::Log(255).table()?;
+    let linear = Curve::Linear.table()?;
+    for value in values() {
+        let c = value as f64 / 255.0;
+        let expected = 255.0 * c.powf(1.0 / 2.2);
+        assert!((gamma[value as usize] as f64 - expected).abs() <= 0.5);
+        let expected = 255.0 * (1.0 + 255.0 * c).ln() / 256f64.ln();
+        assert!((log[value as usize] as f64 - expected).abs() <= 0.5);
+    }
+    assert_eq!((gamma[0], gamma[1], gamma[255]), (0, 21, 255));
+    assert_eq!((linear[0], linear[128], linear[255]), (0, 55, 255));
+    assert_eq!(Curve::Gamma(100).table()?, identity);
+
+    let mut table = identity;
+    table.reverse();
+    assert!(matches!(
+        Curve::Table(Box::new(table)).table(),
+        Err(GreenfieldError::InvalidCurve(_))
+    ));
+    assert!(Curve::Gamma(0).table().is_err());
+    assert!(Curve::Log(0).table().is_err());
+
+    Ok(())
+}
+
+/// Expanding should invert companding, to the middle of the components companded together
+#[test]
+fn policy_companding_inverse() -> GreenfieldResult<()> {
+    let steps = Box::new(core::array::from_fn(|c| (c / 64 * 85) as u8));
+    let companding = Companding::new([Curve::Gamma(220), Curve::Log(100), Curve::Table(steps)])?;
+
+    for value in values() {
+        // The gamma and the µ-law expand the shadows, so they're inverted exactly
+        for channel in 0..2 {
+            if value < 32 {
+                assert_eq!(
+                    companding.expand(channel, companding.compand(channel, value)),
+                    value
+                );
+            }
+        }
+    }
+    assert_eq!(companding.compand(2, 100), 85);
+    assert_eq!(
+        [0, 40, 85, 170, 255].map(|v| companding.expand(2, v)),
+        [31, 31, 95, 159, 223]
+    );
+    assert!(Companding::new(Default::default())?.is_identity());
+
+    Ok(())
+}
+
+/// Companding should spend more intervals on the shadows
+#[test]
+fn policy_companding_shadows() -> GreenfieldResult<()> {
+    let distinct = |quantization: &UniformQuantization, range: core::ops::Range<u8>| {
+        let mut reconstructed = range
+            .map(|v| quantization.reconstruct(&Rgb::new(v, v, v)))
+            .collect::<Vec<_>>();
+        reconstructed.dedup();
+        reconstructed.len()
+    };
+
+    let uniform = UniformQuantization::new(4, 4, 4)?;
+    for curve in [Curve::Gamma(220), Curve::Log(255)] {
+        let companding = Companding::new([curve.clone(), curve.clone(), curve])?;
+        let companded = uniform.clone().with_companding(companding);
+        assert!(distinct(&companded, 0..64) > distinct(&uniform, 0..64));
+        assert!(distinct(&companded, 192..255) < distinct(&uniform, 192..255));
+    }
+
+    // Linear light does the opposite
+    let linear = Companding::new([Curve::Linear, Curve::Linear, Curve::Linear])?;
+    let companded = uniform.clone().with_companding(linear);
+    assert!(distinct(&companded, 0..64) < distinct(&uniform, 0..64));
+
+    Ok(())
+}
+
+/// Images should keep their curves through serialization
+#[test]
+fn policy_companding_image() -> GreenfieldResult<()> {
+    let colors = vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(3, 20, 41),
+        Rgb::new(255, 1, 100),
+        Rgb::new(128, 128, 128),
+    ];
+    let table = Box::new(core::array::from_fn(|c| (c / 2 + 64) as u8));
+
+    for quantization in [
+        UniformQuantization::new(4, 4, 4)?.with_companding(Companding::new([
+            Curve::Gamma(220),
+            Curve::Identity,
+            Curve::Identity,
+        ])?),
+        UniformQuantization::new(5, 3, 3)?
+            .with_rounding(Rounding::Nearest)
+            .with_color_space(ColorSpace::YCbCr)
+            .with_companding(Companding::new([
+                Curve::Log(255),
+                Curve::Identity,
+                Curve::Identity,
+            ])?),
+        UniformQuantization::new(6, 6, 6)?.with_companding(Companding::new([
+            Curve::Linear,
+            Curve::Table(table),
+            Curve::Log(20),
+        ])?),
+    ] {
+        let image = Image::new(2, 2, quantization.clone(), colors.clone())?;
+        let expected = colors.iter().map(|c| quantization.reconstruct(c));
+        assert!(image.colors().cloned().eq(expected));
+
+        let serialized = image.clone().serialize()?;
+        let deserialized = Image::deserialize(&serialized)?;
+        assert_eq!(deserialized, image);
+        assert_eq!(deserialized.quantizer(), &quantization);
+    }
+
+    Ok(())
+}
+
+/// Curves should be stored as a tag per component, and their values
+#[test]
+fn policy_companding_header() -> GreenfieldResult<()> {
+    let companding = Companding::new([Curve::Gamma(0x0102), Curve::Identity, Curve::Linear])?;
+    let quantization = UniformQuantization::new(1, 1, 1)?.with_companding(companding);
+    let mut bits = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut bits)?;
+    assert_eq!(
+        bits,
+        deku::bitvec::bits![u8, Msb0;
+            1,1,1,1, 0,0,0,1, 0,0,0,1, 0,0,0,1,
+            0,1,0,0, 0,0,0,1, 0,0,0,0,0,0,0,1, 0,0,0,0,0,0,1,0, 0,0,0,0, 0,0,1,0,
+            0,0,0,0]
+    );
+
+    // Invalid curves aren't read
+    let mut bits = bits.clone();
+    bits[24..40].fill(false);
+    assert!(Quantization::read_params(&bits).is_err());
+
+    Ok(())
+}
+
+/// Stretching and shrinking should map the bounds to the full range and back
+#[test]
+fn policy_bounds() -> GreenfieldResult<()> {
+    let bounds = Bounds::new([0, 100, 7], [60, 100, 255])?;
+    assert_eq!(
+        [0, 30, 60, 61, 255].map(|v| bounds.stretch(0, v)),
+        [0, 128, 255, 255, 255]
+    );
+    assert_eq!([0, 100, 255].map(|v| bounds.stretch(1, v)), [0; 3]);
+    assert_eq!([0, 255].map(|v| bounds.shrink(1, v)), [100, 100]);
+    assert_eq!([0, 128, 255].map(|v| bounds.shrink(0, v)), [0, 30, 60]);
+
+    // Values in the bounds are kept
+    for channel in 0..3 {
+        let (min, max) = (bounds.min()[channel], bounds.max()[channel]);
+        for value in min..=max {
+            assert_eq!(
+                bounds.shrink(channel, bounds.stretch(channel, value)),
+                value
+            );
+        }
+    }
+
+    let full = Bounds::default();
+    assert!(full.is_full() && !bounds.is_full());
+    assert!(values().all(|v| full.stretch(2, v) == v && full.shrink(2, v) == v));
+
+    assert!(matches!(
+        Bounds::new([0, 10, 0], [255, 9, 255]),
+        Err(GreenfieldError::InvalidBounds(10, 9))
+    ));
+
+    Ok(())
+}
+
+/// With 8 bits, colors in the bounds should be kept, and the others clamped
+#[test]
+fn policy_bounds_8_bits() -> GreenfieldResult<()> {
+    let bounds = Bounds::new([10, 20, 30], [200, 210, 220])?;
+    let quantization = UniformQuantization::default().with_bounds(bounds);
+
+    for value in 30..=200 {
+        let color = Rgb::new(value, value, value);
+        assert_eq!(quantization.reconstruct(&color), color);
+    }
+    assert_eq!(
+        quantization.reconstruct(&Rgb::new(0, 255, 0)),
+        Rgb::new(10, 210, 30)
+    );
+
+    Ok(())
+}
+
+/// Adapting should spread the intervals over the range or percentiles of the image
+#[test]
+fn policy_adapt() -> GreenfieldResult<()> {
+    let mut colors = (0..99u8)
+        .map(|v| Rgb::new(v / 2, 100 + v / 4, 255 - v))
+        .collect::<Vec<_>>();
+    colors.push(Rgb::new(255, 0, 0));
+    let image = Image::new(10, 10, UniformQuantization::default(), colors.clone())?;
+
+    let quantization = UniformQuantization::new(3, 3, 3)?.adapt(&image);
+    assert_eq!(quantization.bounds.min(), [0, 0, 0]);
+    assert_eq!(quantization.bounds.max(), [255, 124, 255]);
+
+    let quantization = UniformQuantization::new(3, 3, 3)?.adapt_percentiles(&image, 2.0, 98.0)?;
+    assert_eq!(quantization.bounds.min(), [0, 100, 157]);
+    assert_eq!(quantization.bounds.max(), [49, 124, 254]);
+
+    // Narrower ranges reconstruct the image better
+    let error = |quantization: &UniformQuantization| {
+        colors
+            .iter()
+            .take(99)
+            .map(|c| {
+                let r = quantization.reconstruct(c);
+                (c.r.abs_diff(r.r) + c.g.abs_diff(r.g) + c.b.abs_diff(r.b)) as u32
+            })
+            .sum::<u32>()
+    };
+    assert!(error(&quantization) < error(&UniformQuantization::new(3, 3, 3)?));
+
+    // The bounds are the ones of the components of the color space
+    let quantization = UniformQuantization::new(3, 3, 3)?
+        .with_color_space(ColorSpace::YCbCr)
+        .adapt(&image);
+    // The lowest blue chroma is the one of the red outlier
+    assert_eq!(quantization.bounds.min()[1], 85);
+
+    assert!(UniformQuantization::new(3, 3, 3)?
+        .adapt_percentiles(&image, 90.0, 10.0)
+        .is_err());
+    let empty = Image::new(0, 0, UniformQuantization::default(), vec![])?;
+    assert!(UniformQuantization::default()
+        .adapt(&empty)
+        .bounds
+        .is_full());
+
+    Ok(())
+}
+
+/// Images should keep their bounds through serialization
+#[test]
+fn policy_bounds_image() -> GreenfieldResult<()> {
+    let colors = vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(3, 20, 41),
+        Rgb::new(50, 1, 100),
+        Rgb::new(60, 70, 80),
+    ];
+    let image = Image::new(2, 2, UniformQuantization::default(), colors.clone())?;
+
+    for quantization in [
+        UniformQuantization::new(3, 3, 3)?.adapt(&image),
+        UniformQuantization::new(2, 3, 4)?
+            .with_reconstruction(Reconstruction::Replicate)?
+            .with_bounds(Bounds::new([0, 10, 20], [30, 40, 50])?),
+    ] {
+        let image = Image::new(2, 2, quantization.clone(), colors.clone())?;
+        let expected = colors.iter().map(|c| quantization.reconstruct(c));
+        assert!(image.colors().cloned().eq(expected));
+
+        let serialized = image.clone().serialize()?;
+        let deserialized = Image::deserialize(&serialized)?;
+        assert_eq!(deserialized, image);
+        assert_eq!(deserialized.quantizer(), &quantization);
+    }
+
+    Ok(())
+}
+
+/// Bounds should be stored as the minimums and then the maximums, and validated
+#[test]
+fn policy_bounds_header() -> GreenfieldResult<()> {
+    let bounds = Bounds::new([1, 2, 3], [4, 5, 6])?;
+    let quantization = UniformQuantization::new(1, 1, 1)?.with_bounds(bounds);
+    let mut bits = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut bits)?;
+
+    // Tags and bits 15, 1, 1, 1, 5, the bounds, and 0
+    assert_eq!(
+        bits.as_raw_slice(),
+        [0xF1, 0x11, 0x50, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60]
+    );
+
+    let (_, read) = Quantization::read_params(&bits)?;
+    assert_eq!(read, quantization);
+
+    // A red minimum of 129, above its maximum
+    let mut bits = bits.clone();
+    bits.set(20, true);
+    assert!(Quantization::read_params(&bits).is_err());
+
+    Ok(())
+}
+
+/// Policies should be serialized after the bits, and validated
+#[cfg(feature = "serde")]
+#[test]
+fn policy_serde() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(1, 1, 2)?;
+    assert_eq!(serde_json::to_string(&quantization).unwrap(), "[1,1,2]");
+
+    let lut = Lut::new([vec![0, 1], vec![2, 3], vec![4, 5, 6, 7]])?;
+    let quantization = quantization
+        .with_rounding(Rounding::Nearest)
+        .with_reconstruction(Reconstruction::Lookup(lut))?;
+    let json = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(
+        json,
+        r#"[1,1,2,"nearest",{"lookup":[[0,1],[2,3],[4,5,6,7]]}]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<UniformQuantization>(&json).unwrap(),
+        quantization
+    );
+    assert_eq!(
+        serde_json::from_str::<Quantization>(&json).unwrap(),
+        quantization
+    );
+
+    let json = r#"[1,1,1,"truncate",{"lookup":[[0,1],[2,3],[4,5,6,7]]}]"#;
+    assert!(serde_json::from_str::<UniformQuantization>(json).is_err());
+
+    let quantization = UniformQuantization::new(6, 3, 3)?.with_color_space(ColorSpace::Oklab);
+    let json = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(json, r#"[6,3,3,"truncate","midpoint","oklab"]"#);
+    assert_eq!(
+        serde_json::from_str::<UniformQuantization>(&json).unwrap(),
+        quantization
+    );
+
+    let companding = Companding::new([Curve::Gamma(220), Curve::Log(255), Curve::Linear])?;
+    let quantization = UniformQuantization::new(4, 4, 4)?.with_companding(companding);
+    let json = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(
+        json,
+        r#"[4,4,4,"truncate","midpoint","rgb",[{"gamma":220},{"log":255},"linear"]]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<UniformQuantization>(&json).unwrap(),
+        quantization
+    );
+
+    let table = serde_json::to_string(&(0..=255).collect::<Vec<u8>>()).unwrap();
+    let json =
+        format!(r#"[4,4,4,"truncate","midpoint","rgb",[{{"table":{table}}},"linear","linear"]]"#);
+    let quantization = serde_json::from_str::<UniformQuantization>(&json).unwrap();
+    assert_eq!(serde_json::to_string(&quantization).unwrap(), json);
+
+    let bounds = Bounds::new([0, 0, 10], [60, 80, 255])?;
+    let quantization = UniformQuantization::new(3, 3, 3)?.with_bounds(bounds);
+    let json = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(
+        json,
+        r#"[3,3,3,"truncate","midpoint","rgb",["identity","identity","identity"],[[0,0,10],[60,80,255]]]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<UniformQuantization>(&json).unwrap(),
+        quantization
+    );
+    let json = r#"[3,3,3,"truncate","midpoint","rgb",["identity","identity","identity"],[[90,0,0],[60,80,255]]]"#;
+    assert!(serde_json::from_str::<UniformQuantization>(json).is_err());
+
+    let json = r#"[4,4,4,"truncate","midpoint","rgb",[{"table":[1,2,3]},"linear","linear"]]"#;
+    assert!(serde_json::from_str::<UniformQuantization>(json).is_err());
+    let json = r#"[4,4,4,"truncate","midpoint","rgb",[{"gamma":0},"linear","linear"]]"#;
+    assert!(serde_json::from_str::<UniformQuantization>(json).is_err());
+
+    Ok(())
+}
@@ -1,5 +1,22 @@
 #![allow(unused_imports)]
-use super::{color, BitVec, GreenfieldResult, Msb0, UniformQuantization};
+use super::{color, BitVec, GreenfieldResult, Msb0, Quantization, Quantizer, UniformQuantization};
+use crate::image::Image;
+
+/// An image of the given colors, in a single row
+pub(super) fn row(colors: Vec<color::Rgb>) -> GreenfieldResult<Image> {
+    Image::new(colors.len(), 1, UniformQuantization::new(8, 8, 8)?, colors)
+}
+
+/// A `width` x `height` image, with the color of each pixel given by its index
+pub(super) fn gradient(
+    width: usize,
+    height: usize,
+    quantization: UniformQuantization,
+    color: impl Fn(usize) -> color::Rgb,
+) -> GreenfieldResult<Image> {
+    let colors = (0..width * height).map(color).collect();
+    Image::new(width, height, quantization, colors)
+}
 
 /// All quantizations fields correctly set
 #[test]
@@ -10,7 +27,8 @@ fn quantization_new_ok() -> GreenfieldResult<()> {
         UniformQuantization {
             bits_r: 1,
             bits_g: 1,
-            bits_b: 1
+            bits_b: 1,
+            ..Default::default()
         }
     );
 
@@ -50,7 +68,8 @@ fn quantization_default() -> GreenfieldResult<()> {
         UniformQuantization {
             bits_r: 8,
             bits_g: 8,
-            bits_b: 8
+            bits_b: 8,
+            ..Default::default()
         }
     );
 
@@ -274,3 +293,179 @@ fn quantization_compress() -> GreenfieldResult<()> {
 
     Ok(())
 }
+
+/// A quantization should be serialized as a tuple, and validated on deserialization
+#[cfg(feature = "serde")]
+#[test]
+fn quantization_serde() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(5, 6, 5)?;
+    let serialized = serde_json::to_string(&quantization).unwrap();
+    assert_eq!(serialized, "[5,6,5]");
+
+    let deserialized = serde_json::from_str::<UniformQuantization>(&serialized).unwrap();
+    assert_eq!(deserialized, quantization);
+
+    assert!(serde_json::from_str::<UniformQuantization>("[0,6,5]").is_err());
+    assert!(serde_json::from_str::<UniformQuantization>("[5,6]").is_err());
+
+    Ok(())
+}
+
+/// The uniform quantizer codes should match the packed components
+#[test]
+fn quantizer_uniform() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(5, 6, 5)?;
+    assert_eq!(Quantizer::bits_per_pixel(&quantization), 16);
+
+    let color = color::Rgb::new(12, 6, 12);
+    let code = Quantizer::quantize(&quantization, &color);
+    assert_eq!(code, (1 << 11) | (1 << 5) | 1);
+    assert_eq!(
+        Quantizer::dequantize(&quantization, code),
+        quantization.get_dequantized_color(&color::Rgb::new(1, 1, 1))
+    );
+
+    // The provided compression is the same as the uniform one
+    let colors = (0..=255u8)
+        .map(|c| color::Rgb::new(c, 255 - c, c / 2))
+        .collect::<Vec<color::Rgb>>();
+    for quantization in [
+        UniformQuantization::new(1, 1, 1)?,
+        UniformQuantization::new(3, 3, 2)?,
+        UniformQuantization::new(5, 6, 5)?,
+        UniformQuantization::new(8, 8, 8)?,
+    ] {
+        let compressed = quantization.compress(&colors);
+        let mut packed = BitVec::<u8, Msb0>::new();
+        for color in &colors {
+            let code = Quantizer::quantize(&quantization, color);
+            let bits = Quantizer::bits_per_pixel(&quantization);
+            packed.extend((0..bits).rev().map(|i| code >> i & 1 == 1));
+        }
+        assert_eq!(compressed, packed);
+        assert_eq!(
+            Quantizer::decompress(&quantization, &compressed),
+            quantization.decompress(&compressed)
+        );
+
+        // Index space
+        for color in &colors {
+            assert_eq!(
+                Quantizer::quantize_color(&quantization, color),
+                quantization.get_quantized_color(color)
+            );
+            assert_eq!(
+                Quantizer::reconstruct(&quantization, color),
+                Quantizer::dequantize(&quantization, Quantizer::quantize(&quantization, color))
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The quantization parameters should be written and read back, and validated
+#[test]
+fn quantizer_params() -> GreenfieldResult<()> {
+    let quantization = Quantization::from(UniformQuantization::new(5, 6, 5)?);
+
+    // Uniform quantizations are stored as before: 4 bits per component, without a tag
+    let mut params = BitVec::<u8, Msb0>::new();
+    quantization.write_params(&mut params)?;
+    assert_eq!(params, bitvec::bits![u8, Msb0; 0,1,0,1, 0,1,1,0, 0,1,0,1]);
+
+    let (rest, read) = Quantization::read_params(&params)?;
+    assert!(rest.is_empty());
+    assert_eq!(read, quantization);
+    assert_eq!(read, UniformQuantization::new(5, 6, 5)?);
+
+    let (_, read) = UniformQuantization::read_params(&params)?;
+    assert_eq!(read, UniformQuantization::new(5, 6, 5)?);
+
+    // Invalid levels
+    let params = bitvec::bits![u8, Msb0; 0,1,0,1, 0,0,0,0, 0,1,0,1];
+    assert!(Quantization::read_params(params).is_err());
+    assert!(UniformQuantization::read_params(params).is_err());
+
+    // Unknown tag
+    let params = bitvec::bits![u8, Msb0; 1,1,1,1, 0,1,1,0, 0,1,0,1];
+    assert!(Quantization::read_params(params).is_err());
+
+    Ok(())
+}
+
+/// The lookup tables and the packer should give the same bits and colors as quantizing and
+/// packing the pixels one by one
+#[test]
+fn quantization_tables() -> GreenfieldResult<()> {
+    use super::{Bounds, ColorSpace, Companding, Curve, Reconstruction, Rounding};
+
+    let colors: Vec<_> = (0..=255u8)
+        .step_by(17)
+        .flat_map(|r| (0..=255u8).step_by(51).map(move |g| (r, g)))
+        .flat_map(|(r, g)| {
+            (0..=255u8)
+                .step_by(85)
+                .map(move |b| color::Rgb::new(r, g, b))
+        })
+        .collect();
+
+    let mut quantizations = Vec::new();
+    for bits in 1..=8 {
+        for (r, g, b) in [(bits, bits, bits), (bits, 9 - bits, bits), (8, bits, 1)] {
+            quantizations.push(UniformQuantization::new(r, g, b)?);
+        }
+    }
+    for space in [ColorSpace::Rgb, ColorSpace::YCbCr, ColorSpace::Oklab] {
+        for (r, g, b) in [(3, 3, 2), (4, 4, 4), (5, 6, 5), (8, 8, 8)] {
+            let quantization = UniformQuantization::new(r, g, b)?
+                .with_rounding(Rounding::Nearest)
+                .with_reconstruction(Reconstruction::Replicate)?
+                .with_color_space(space);
+            quantizations.push(quantization.clone());
+            quantizations.push(
+                quantization
+                    .with_companding(Companding::new([
+                        Curve::Gamma(220),
+                        Curve::Linear,
+                        Curve::Log(255),
+                    ])?)
+                    .with_bounds(Bounds::new([10, 0, 40], [200, 255, 90])?),
+            );
+        }
+    }
+
+    for quantization in quantizations {
+        let bits = Quantizer::bits_per_pixel(&quantization);
+        let mut packed = BitVec::<u8, Msb0>::new();
+        for color in &colors {
+            let code = Quantizer::quantize(&quantization, color);
+            packed.extend((0..bits).rev().map(|i| code >> i & 1 == 1));
+        }
+        let compressed = quantization.compress(&colors);
+        assert_eq!(compressed, packed, "{quantization}");
+
+        let expected: Vec<_> = colors
+            .iter()
+            .map(|color| {
+                quantization.get_dequantized_color(&quantization.get_quantized_color(color))
+            })
+            .collect();
+        assert_eq!(
+            quantization.decompress(&compressed),
+            expected,
+            "{quantization}"
+        );
+
+        // Data that doesn't start at a byte, as after a header
+        let mut shifted = BitVec::<u8, Msb0>::repeat(true, 3);
+        shifted.extend_from_bitslice(&compressed);
+        assert_eq!(
+            quantization.decompress(&shifted[3..]),
+            expected,
+            "{quantization}"
+        );
+    }
+
+    Ok(())
+}
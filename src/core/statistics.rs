@@ -0,0 +1,335 @@
+//! ## Image statistics
+//!
+//! Summarizes the colors of an image: a [`Histogram`] of each channel, and [`Statistics`] with
+//! the number of unique colors and the mean, variance, minimum and maximum of each channel.
+//!
+//! Images with a uniform quantization also have a histogram of the indexes of their components
+//! (the quantized colors of [`UniformQuantization::get_quantized_color`]), with a bin for each
+//! level of the channel, which shows how many of the levels the image actually uses.
+//!
+//! [`UniformQuantization::get_quantized_color`]: crate::quantization::UniformQuantization::get_quantized_color
+//!
+//! The colors are counted a chunk at a time, in parallel if enabled (see [`parallel`]).
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::prelude::*;
+//!
+//! let colors = (0..64).map(|i| Rgb::new(i * 4, 128, 0)).collect();
+//! let image = Image::new(8, 8, UniformQuantization::new(5, 6, 5)?, colors)?;
+//!
+//! let statistics = image.statistics();
+//! assert_eq!(statistics.pixels(), 64);
+//! assert_eq!(statistics.min(), [4, 130, 4]);
+//! assert_eq!(statistics.used_levels(), Some([32, 1, 1]));
+//!
+//! // A 256x100 plot of the histogram
+//! let plot = image.histogram().render(256, 100)?;
+//! assert_eq!(plot.dimensions(), (256, 100));
+//! # Ok::<(), GreenfieldError>(())
+//! ```
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use crate::color::Rgb;
+use crate::error::GreenfieldResult;
+use crate::image::Image;
+use crate::parallel;
+use crate::quantization::{Palette, Quantization};
+
+#[cfg(test)]
+mod tests;
+
+/// ## Histogram of the channels of an image
+///
+/// Counts the pixels with each value (a component, or the index of a level) of every channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    bins: [Vec<usize>; 3],
+}
+
+impl Histogram {
+    /// Counts the values of `colors` given by `values`, in `sizes` bins per channel.
+    pub(crate) fn count(
+        colors: &[Rgb],
+        sizes: [usize; 3],
+        values: impl Fn(&Rgb) -> [u8; 3] + Sync,
+    ) -> Self {
+        let parts = parallel::map_chunks(colors.len(), |range| {
+            let mut bins = sizes.map(|size| vec![0; size]);
+            for color in &colors[range] {
+                for (bins, value) in bins.iter_mut().zip(values(color)) {
+                    bins[value as usize] += 1;
+                }
+            }
+            bins
+        });
+
+        let mut bins = sizes.map(|size| vec![0; size]);
+        for part in parts {
+            for (bins, part) in bins.iter_mut().zip(part) {
+                bins.iter_mut()
+                    .zip(part)
+                    .for_each(|(bin, count)| *bin += count);
+            }
+        }
+        Self { bins }
+    }
+
+    /// ## The bins of a channel (0 to 2)
+    ///
+    /// ## Panics
+    /// If the channel is not 0, 1 or 2.
+    pub fn channel(&self, channel: usize) -> &[usize] {
+        &self.bins[channel]
+    }
+
+    /// ## The bins of every channel
+    pub fn bins(&self) -> &[Vec<usize>; 3] {
+        &self.bins
+    }
+
+    /// ## The number of pixels counted
+    pub fn pixels(&self) -> usize {
+        self.bins[0].iter().sum()
+    }
+
+    /// ## The number of values of each channel with any pixel
+    pub fn used(&self) -> [usize; 3] {
+        self.bins
+            .each_ref()
+            .map(|bins| bins.iter().filter(|&&count| count > 0).count())
+    }
+
+    /// ## The mean value of each channel
+    ///
+    /// 0 without pixels.
+    pub fn mean(&self) -> [f64; 3] {
+        let pixels = self.pixels().max(1) as f64;
+        self.bins.each_ref().map(|bins| {
+            let sum = bins
+                .iter()
+                .enumerate()
+                .map(|(value, &count)| (value * count) as f64)
+                .sum::<f64>();
+            sum / pixels
+        })
+    }
+
+    /// ## The (population) variance of the values of each channel
+    ///
+    /// 0 without pixels.
+    pub fn variance(&self) -> [f64; 3] {
+        let pixels = self.pixels().max(1) as f64;
+        let mean = self.mean();
+        [0, 1, 2].map(|channel| {
+            let sum = self.bins[channel]
+                .iter()
+                .enumerate()
+                .map(|(value, &count)| {
+                    let deviation = value as f64 - mean[channel];
+                    count as f64 * deviation * deviation
+                })
+                .sum::<f64>();
+            sum / pixels
+        })
+    }
+
+    /// ## The smallest value of each channel
+    ///
+    /// 0 without pixels.
+    pub fn min(&self) -> [u8; 3] {
+        self.bins
+            .each_ref()
+            .map(|bins| bins.iter().position(|&count| count > 0).unwrap_or(0) as u8)
+    }
+
+    /// ## The largest value of each channel
+    ///
+    /// 0 without pixels.
+    pub fn max(&self) -> [u8; 3] {
+        self.bins
+            .each_ref()
+            .map(|bins| bins.iter().rposition(|&count| count > 0).unwrap_or(0) as u8)
+    }
+
+    /// ## Renders the histogram as an image
+    ///
+    /// Plots the bins of each channel as bars from the bottom of the image, in red, green and
+    /// blue, added together where they overlap, over a black background. The bars are scaled to
+    /// the tallest one, and every bin with pixels is at least a pixel tall. When the image is
+    /// narrower than a channel has bins, each column adds up the bins it covers.
+    ///
+    /// The image has a [`Palette`] of the 8 colors of the plot, so it takes 3 bits per pixel.
+    ///
+    /// ## Errors
+    /// None in practice: the plot is always a valid [`Image`].
+    pub fn render(&self, width: usize, height: usize) -> GreenfieldResult<Image> {
+        let columns = self.bins.each_ref().map(|bins| {
+            (0..width)
+                .map(|x| {
+                    let (start, end) = (x * bins.len() / width, (x + 1) * bins.len() / width);
+                    bins[start..end.max(start + 1)].iter().sum::<usize>()
+                })
+                .collect::<Vec<_>>()
+        });
+        let tallest = columns.iter().flatten().copied().max().unwrap_or(0).max(1);
+        let bars = columns.map(|columns| {
+            columns
+                .into_iter()
+                .map(|count| (count * height).div_ceil(tallest))
+                .collect::<Vec<_>>()
+        });
+
+        let colors = (0..width * height)
+            .map(|i| {
+                let (x, row) = (i % width, height - i / width);
+                let [r, g, b] = [0, 1, 2].map(|c| if bars[c][x] >= row { 255 } else { 0 });
+                Rgb::new(r, g, b)
+            })
+            .collect();
+        let palette = (0..8)
+            .map(|i| Rgb::new(((i >> 2) & 1) * 255, ((i >> 1) & 1) * 255, (i & 1) * 255))
+            .collect();
+        Image::new(width, height, Palette::new(palette)?, colors)
+    }
+}
+
+/// ## Statistics of the colors of an image
+///
+/// See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statistics {
+    histogram: Histogram,
+    levels: Option<Histogram>,
+    unique_colors: usize,
+}
+
+impl Statistics {
+    /// Measures the colors of an image with a quantization.
+    pub(crate) fn new(colors: &[Rgb], quantization: &Quantization) -> Self {
+        let histogram = Histogram::count(colors, [256; 3], Rgb::bytes);
+        let levels = match quantization {
+            Quantization::Uniform(quantization) => {
+                let sizes = [
+                    quantization.bits_r,
+                    quantization.bits_g,
+                    quantization.bits_b,
+                ];
+                Some(Histogram::count(colors, sizes.map(|bits| 1 << bits), |c| {
+                    quantization.get_quantized_color(c).bytes()
+                }))
+            }
+            _ => None,
+        };
+
+        // The unique colors of each chunk, merged
+        let mut unique = parallel::map_chunks(colors.len(), |range| {
+            let mut keys = colors[range]
+                .iter()
+                .map(|c| u32::from_be_bytes([0, c.r, c.g, c.b]))
+                .collect::<Vec<_>>();
+            keys.sort_unstable();
+            keys.dedup();
+            keys
+        });
+        let unique_colors = match unique.len() {
+            0 | 1 => unique.pop().map_or(0, |keys| keys.len()),
+            _ => {
+                let mut keys = unique.concat();
+                keys.sort_unstable();
+                keys.dedup();
+                keys.len()
+            }
+        };
+
+        Self {
+            histogram,
+            levels,
+            unique_colors,
+        }
+    }
+
+    /// ## The number of pixels
+    pub fn pixels(&self) -> usize {
+        self.histogram.pixels()
+    }
+
+    /// ## The number of different colors
+    pub fn unique_colors(&self) -> usize {
+        self.unique_colors
+    }
+
+    /// ## The histogram of the components of each channel
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// ## The histogram of the quantization levels of each channel
+    ///
+    /// Only for images with a [`Quantization::Uniform`], with `2^bits` bins per channel.
+    pub fn levels(&self) -> Option<&Histogram> {
+        self.levels.as_ref()
+    }
+
+    /// ## The number of quantization levels of each channel in use
+    ///
+    /// Only for images with a [`Quantization::Uniform`].
+    pub fn used_levels(&self) -> Option<[usize; 3]> {
+        self.levels.as_ref().map(Histogram::used)
+    }
+
+    /// ## The mean component of each channel
+    pub fn mean(&self) -> [f64; 3] {
+        self.histogram.mean()
+    }
+
+    /// ## The variance of the components of each channel
+    pub fn variance(&self) -> [f64; 3] {
+        self.histogram.variance()
+    }
+
+    /// ## The smallest component of each channel
+    pub fn min(&self) -> [u8; 3] {
+        self.histogram.min()
+    }
+
+    /// ## The largest component of each channel
+    pub fn max(&self) -> [u8; 3] {
+        self.histogram.max()
+    }
+}
+
+impl Display for Statistics {
+    /// Shows the counts and the statistics of every channel, e.g. `4 pixels, 2 colors,
+    /// mean 1.5/2.0/2.5, variance 0.8/0.0/0.8, range 1-3/2-2/1-3, levels 2/1/2`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [mean, variance] = [self.mean(), self.variance()];
+        let [min, max] = [self.min(), self.max()];
+        write!(
+            f,
+            "{} pixels, {} colors, mean {:.1}/{:.1}/{:.1}, variance {:.1}/{:.1}/{:.1}, \
+             range {}-{}/{}-{}/{}-{}",
+            self.pixels(),
+            self.unique_colors,
+            mean[0],
+            mean[1],
+            mean[2],
+            variance[0],
+            variance[1],
+            variance[2],
+            min[0],
+            max[0],
+            min[1],
+            max[1],
+            min[2],
+            max[2],
+        )?;
+        if let Some([r, g, b]) = self.used_levels() {
+            write!(f, ", levels {r}/{g}/{b}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use super::*;
+use crate::error::GreenfieldResult;
+use crate::quantization::{LevelsQuantization, Quantizer, UniformQuantization};
+use alloc::collections::BTreeSet;
+
+/// An image of the given colors, in a single row
+fn row(quantization: impl Into<Quantization>, colors: Vec<Rgb>) -> GreenfieldResult<Image> {
+    Image::new(colors.len(), 1, quantization, colors)
+}
+
+/// A gradient over every component, large enough to be split in chunks
+fn gradient(quantization: impl Into<Quantization>) -> GreenfieldResult<Image> {
+    let colors = (0..3 * parallel::CHUNK)
+        .map(|i| Rgb::new(i as u8, (i / 256) as u8, 200))
+        .collect::<Vec<_>>();
+    Image::new(parallel::CHUNK, 3, quantization, colors)
+}
+
+/// Should count the components of every channel
+#[test]
+fn statistics_histogram() -> GreenfieldResult<()> {
+    let image = row(
+        UniformQuantization::new(8, 8, 8)?,
+        vec![
+            Rgb::new(0, 0, 255),
+            Rgb::new(0, 10, 255),
+            Rgb::new(4, 10, 255),
+            Rgb::new(8, 10, 255),
+        ],
+    )?;
+    let histogram = image.histogram();
+
+    assert_eq!(histogram.pixels(), 4);
+    assert_eq!(histogram.bins().each_ref().map(Vec::len), [256; 3]);
+    assert_eq!((histogram.channel(0)[0], histogram.channel(0)[4]), (2, 1));
+    assert_eq!((histogram.channel(1)[0], histogram.channel(1)[10]), (1, 3));
+    assert_eq!(histogram.channel(2)[255], 4);
+    assert_eq!(histogram.used(), [3, 2, 1]);
+    assert_eq!(histogram.min(), [0, 0, 255]);
+    assert_eq!(histogram.max(), [8, 10, 255]);
+    assert_eq!(histogram.mean(), [3.0, 7.5, 255.0]);
+    assert_eq!(histogram.variance(), [11.0, 18.75, 0.0]);
+
+    Ok(())
+}
+
+/// Should count the unique colors and summarize the channels
+#[test]
+fn statistics_image() -> GreenfieldResult<()> {
+    let image = row(
+        UniformQuantization::new(8, 8, 8)?,
+        vec![
+            Rgb::new(1, 2, 3),
+            Rgb::new(1, 2, 3),
+            Rgb::new(3, 2, 1),
+            Rgb::new(1, 2, 3),
+        ],
+    )?;
+    let statistics = image.statistics();
+
+    assert_eq!(statistics.pixels(), 4);
+    assert_eq!(statistics.unique_colors(), 2);
+    assert_eq!(statistics.histogram(), &image.histogram());
+    assert_eq!(statistics.mean(), [1.5, 2.0, 2.5]);
+    assert_eq!(statistics.variance(), [0.75, 0.0, 0.75]);
+    assert_eq!(statistics.min(), [1, 2, 1]);
+    assert_eq!(statistics.max(), [3, 2, 3]);
+    assert_eq!(
+        statistics.to_string(),
+        "4 pixels, 2 colors, mean 1.5/2.0/2.5, variance 0.8/0.0/0.8, range 1-3/2-2/1-3, \
+         levels 2/1/2"
+    );
+
+    Ok(())
+}
+
+/// Uniform quantizations should also count the levels in use, in index space
+#[test]
+fn statistics_levels() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(2, 3, 1)?;
+    let colors = vec![
+        Rgb::new(0, 0, 0),
+        Rgb::new(255, 0, 0),
+        Rgb::new(255, 255, 0),
+        Rgb::new(100, 255, 0),
+    ];
+    let image = row(quantization.clone(), colors.clone())?;
+    let statistics = image.statistics();
+
+    let levels = statistics.levels().expect("uniform quantization");
+    assert_eq!(levels.bins().each_ref().map(Vec::len), [4, 8, 2]);
+    for (channel, values) in [(0, [0, 3, 3, 1]), (1, [0, 0, 7, 7]), (2, [0; 4])] {
+        let mut expected = vec![0; levels.channel(channel).len()];
+        values.iter().for_each(|&v| expected[v] += 1);
+        assert_eq!(levels.channel(channel), expected, "channel {channel}");
+    }
+    assert_eq!(statistics.used_levels(), Some([3, 2, 1]));
+    assert_eq!(levels.max(), [3, 7, 0]);
+
+    // The indexes are the quantized colors
+    for color in &colors {
+        let [r, g, b] = quantization.get_quantized_color(color).bytes();
+        assert!(levels.channel(0)[r as usize] > 0);
+        assert!(levels.channel(1)[g as usize] > 0);
+        assert!(levels.channel(2)[b as usize] > 0);
+    }
+
+    // Other quantizations don't have levels
+    let palette = Palette::new(colors.clone())?;
+    assert_eq!(row(palette, colors.clone())?.statistics().levels(), None);
+    let levels = LevelsQuantization::new(6, 7, 6)?;
+    assert_eq!(row(levels, colors)?.statistics().used_levels(), None);
+
+    Ok(())
+}
+
+/// Should give the same statistics on any number of threads
+#[test]
+fn statistics_parallel() -> GreenfieldResult<()> {
+    let quantization = UniformQuantization::new(4, 4, 4)?;
+    let image = gradient(quantization.clone())?;
+    let statistics = image.statistics();
+    let colors = image.colors().cloned().collect::<Vec<_>>();
+
+    assert_eq!(statistics.pixels(), 3 * parallel::CHUNK);
+    let unique = colors.iter().map(Rgb::bytes).collect::<BTreeSet<_>>();
+    assert_eq!(statistics.unique_colors(), unique.len());
+    let levels = |range: &[Rgb]| {
+        let indexes = range
+            .iter()
+            .map(|c| quantization.get_quantized_color(c).bytes())
+            .collect::<Vec<_>>();
+        [0, 1, 2].map(|c| indexes.iter().map(|i| i[c]).collect::<BTreeSet<_>>().len())
+    };
+    assert_eq!(statistics.used_levels(), Some(levels(&colors)));
+
+    // The same as counting the chunks separately
+    let serial = Histogram::count(&colors[..parallel::CHUNK], [256; 3], Rgb::bytes);
+    let rest = Histogram::count(&colors[parallel::CHUNK..], [256; 3], Rgb::bytes);
+    for channel in 0..3 {
+        let sum = serial
+            .channel(channel)
+            .iter()
+            .zip(rest.channel(channel))
+            .map(|(a, b)| a + b)
+            .collect::<Vec<_>>();
+        assert_eq!(statistics.histogram().channel(channel), sum);
+    }
+
+    Ok(())
+}
+
+/// Empty images should have empty statistics
+#[test]
+fn statistics_empty() -> GreenfieldResult<()> {
+    let statistics = row(UniformQuantization::new(5, 6, 5)?, vec![])?.statistics();
+
+    assert_eq!(statistics.pixels(), 0);
+    assert_eq!(statistics.unique_colors(), 0);
+    assert_eq!(statistics.mean(), [0.0; 3]);
+    assert_eq!(statistics.variance(), [0.0; 3]);
+    assert_eq!((statistics.min(), statistics.max()), ([0; 3], [0; 3]));
+    assert_eq!(statistics.used_levels(), Some([0; 3]));
+
+    Ok(())
+}
+
+/// Should plot the bins as bars from the bottom, scaled to the tallest one
+#[test]
+fn statistics_render() -> GreenfieldResult<()> {
+    let image = row(
+        UniformQuantization::new(8, 8, 8)?,
+        vec![
+            Rgb::new(0, 0, 255),
+            Rgb::new(0, 0, 255),
+            Rgb::new(0, 0, 255),
+            Rgb::new(0, 255, 255),
+        ],
+    )?;
+    let plot = image.histogram().render(256, 4)?;
+    assert_eq!(plot.dimensions(), (256, 4));
+    assert_eq!(plot.quantizer().bits_per_pixel(), 3);
+
+    let at = |x: usize, y: usize| plot.colors().nth(y * 256 + x).cloned();
+    // Red has 4 pixels at 0, the tallest bar, and green 3
+    assert_eq!(at(0, 0), Some(Rgb::new(255, 0, 0)));
+    assert_eq!(at(0, 1), Some(Rgb::new(255, 255, 0)));
+    // Green has one pixel at 255, a bar of a pixel
+    assert_eq!(at(255, 3), Some(Rgb::new(0, 255, 255)));
+    assert_eq!(at(255, 2), Some(Rgb::new(0, 0, 255)));
+    assert_eq!(at(128, 3), Some(Rgb::new(0, 0, 0)));
+
+    // Narrower plots add up the bins of each column
+    let plot = image.histogram().render(2, 4)?;
+    let colors = plot.colors().cloned().collect::<Vec<_>>();
+    assert_eq!(colors[0], Rgb::new(255, 0, 0));
+    assert_eq!(colors[1], Rgb::new(0, 0, 255));
+    assert_eq!(colors[6], Rgb::new(255, 255, 0));
+    assert_eq!(colors[7], Rgb::new(0, 255, 255));
+
+    // Empty plots
+    assert_eq!(image.histogram().render(0, 0)?.dimensions(), (0, 0));
+
+    Ok(())
+}
@@ -1,9 +1,11 @@
 //! Error handling and custom Result types for the greenfield crate.
 //!
 //! This module contains the error types for the greenfield crate. It also contains the custom
-//! [`Result`] type for the crate, which is a [`std::result::Result`] with the error type set to
+//! [`Result`] type for the crate, which is a [`core::result::Result`] with the error type set to
 //! [`GreenfieldError`].
+use alloc::string::{String, ToString};
 use deku::DekuError;
+#[cfg(feature = "image")]
 use image::ImageError;
 use thiserror::Error;
 
@@ -18,19 +20,56 @@ pub enum GreenfieldError {
     #[error("Each color should be represented by {0} bits, but {1} bits has been found instead.")]
     InvalidDataSize(usize, usize),
 
+    #[error("Invalid hex color: {0:?}. Colors must be in the form #rrggbb.")]
+    InvalidHexColor(String),
+
     #[error("Invalid image dimensions: {0} Pixels found (expected {1})")]
     InvalidImageDimension(usize, usize),
 
+    #[error("Image too large: {0}x{1}. Its size can't be represented in memory.")]
+    ImageTooLarge(usize, usize),
+
+    #[error("Invalid palette size: {0}. Palettes must have between 1 and 65536 colors.")]
+    InvalidPaletteSize(usize),
+
+    #[error("Invalid dithering kernel: {0}.")]
+    InvalidDitheringKernel(String),
+
+    #[error("Invalid lookup table size: {0}. Tables must have 2^bits entries, with bits between 1 and 8.")]
+    InvalidLookupTable(usize),
+
+    #[error("Invalid companding curve: {0}.")]
+    InvalidCurve(String),
+
+    #[error("Invalid bounds: {0} to {1}. The minimum can't be above the maximum.")]
+    InvalidBounds(u8, u8),
+
+    #[error("Invalid level count: {0}. Components must have between 1 and 256 levels.")]
+    InvalidLevelCount(u16),
+
+    #[error("Mismatched image dimensions: {0}x{1} and {2}x{3}. Compared images must have the same dimensions.")]
+    MismatchedDimensions(usize, usize, usize, usize),
+
+    #[cfg(feature = "std")]
     #[error("Error while io: {0}")]
     IoError(#[from] std::io::Error),
 
     #[error("Deku error: {0}")]
-    DekuError(#[from] DekuError),
+    DekuError(#[cfg_attr(feature = "std", from)] DekuError),
 
+    #[cfg(feature = "image")]
     #[error("Image error: {0}")]
     ImageError(#[from] ImageError),
 }
 
+// Without std, DekuError doesn't implement Error, so it can't be the source of a GreenfieldError
+#[cfg(not(feature = "std"))]
+impl From<DekuError> for GreenfieldError {
+    fn from(e: DekuError) -> Self {
+        GreenfieldError::DekuError(e)
+    }
+}
+
 // Not sure why this is needed, but it is
 impl From<GreenfieldError> for DekuError {
     fn from(e: GreenfieldError) -> Self {
@@ -38,5 +77,5 @@ impl From<GreenfieldError> for DekuError {
     }
 }
 
-/// A wrapper around [`std::result::Result`] with the error type set to [`GreenfieldError`].
+/// A wrapper around [`core::result::Result`] with the error type set to [`GreenfieldError`].
 pub type GreenfieldResult<T> = Result<T, GreenfieldError>;
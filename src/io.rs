@@ -13,6 +13,7 @@
 //! use std::path::PathBuf;
 //!
 //! /// Should convert images between formats
+//! # #[cfg(feature = "image")]
 //! fn io_image() -> GreenfieldResult<()> {
 //!     let base_path = env::current_dir()?.join("src").join("io").join("assets");
 //!
@@ -49,19 +50,31 @@
 //!     Ok(())
 //! }
 //! ````
+#[cfg(feature = "image")]
+use crate::dithering::Dithering;
+#[cfg(feature = "image")]
 use crate::prelude::*;
+#[cfg(feature = "image")]
 use image::GenericImageView;
+#[cfg(feature = "image")]
 use std::path::PathBuf;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "image"))]
 mod tests;
 
+#[cfg(all(feature = "image", feature = "rayon"))]
+pub mod batch;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
 /// ## Convert a image to a Greenfield image and returns it
 ///
 /// Uses the `image` crate to read an image from a file and convert it to a Greenfield image.
 ///
 /// ## Arguments
 /// * `path` - The path to the image file
+/// * `quantization` - The quantization of the Greenfield image, ignored if the file already is one
 ///
 /// ## Returns
 /// A Greenfield image
@@ -114,9 +127,50 @@ mod tests;
 ///     Ok(())
 /// }
 /// ````
+#[cfg(feature = "image")]
 pub fn load_image(
     path: &PathBuf,
-    uniform_quantization: UniformQuantization,
+    quantization: impl Into<Quantization>,
+) -> GreenfieldResult<Image> {
+    load_image_dithered(path, quantization, Dithering::None)
+}
+
+/// ## Convert a image to a dithered Greenfield image and returns it
+///
+/// Like [`load_image`], but the colors are quantized with `dithering` (see
+/// [`Image::new_dithered`]).
+///
+/// ## Arguments
+/// * `path` - The path to the image file
+/// * `quantization` - The quantization of the Greenfield image, ignored if the file already is one
+/// * `dithering` - The dithering algorithm, ignored if the file already is a Greenfield image
+///
+/// ## Returns
+/// A Greenfield image
+///
+/// ## Errors
+/// * If the image cannot be read
+/// * If the image cannot be converted to a Greenfield image
+///
+/// ## Examples
+///
+/// ```rust,no_run
+/// use greenfield::dithering::Dithering;
+/// use greenfield::io::load_image_dithered;
+/// use greenfield::prelude::*;
+///
+/// let image = load_image_dithered(
+///     &"photo.png".into(),
+///     UniformQuantization::new(2, 2, 2)?,
+///     Dithering::FloydSteinberg,
+/// )?;
+/// # Ok::<(), GreenfieldError>(())
+/// ```
+#[cfg(feature = "image")]
+pub fn load_image_dithered(
+    path: &PathBuf,
+    quantization: impl Into<Quantization>,
+    dithering: Dithering,
 ) -> GreenfieldResult<Image> {
     let input_image = image::open(path);
 
@@ -134,13 +188,14 @@ pub fn load_image(
                 )),
             }?;
 
-            Image::new(
+            Image::new_dithered(
                 width as usize,
                 height as usize,
-                uniform_quantization,
+                quantization,
                 data.chunks(3)
                     .map(|c| Rgb::new(c[0] as u8, c[1] as u8, c[2] as u8))
                     .collect::<Vec<Rgb>>(),
+                dithering,
             )
         }
         // It's a greenfield image
@@ -162,7 +217,7 @@ pub fn load_image(
 ///
 /// ## Errors
 /// * If the image cannot be converted to a image
-/// * If the image cannot be saved to a file
+/// * If the image cannot be saved to a file, in a common format or as a greenfield image
 ///
 /// ## Examples
 ///
@@ -208,6 +263,7 @@ pub fn load_image(
 ///     Ok(())
 /// }
 /// ````
+#[cfg(feature = "image")]
 pub fn save_image(image: &Image, path: &PathBuf) -> GreenfieldResult<()> {
     let (width, height) = image.dimensions();
 
@@ -225,6 +281,6 @@ pub fn save_image(image: &Image, path: &PathBuf) -> GreenfieldResult<()> {
     );
     match res {
         Err(image::ImageError::Unsupported(_)) => Ok(image.clone().to_file(path)?),
-        _ => Ok(()),
+        res => Ok(res?),
     }
 }
@@ -0,0 +1,425 @@
+//! ## Parallel batch conversion
+//!
+//! Converts whole directory trees between formats, at one or more quantizations. The input
+//! directories are walked recursively and mirrored into the output directory, and the files are
+//! converted in parallel with [`load_image`] and [`save_image`].
+//!
+//! Files whose outputs are newer than them (and, for greenfield outputs, have the same
+//! quantization) are skipped, and a failed file doesn't abort the others: every file gets an
+//! entry in the [`BatchReport`].
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use greenfield::io::batch::{BatchConversion, BatchStatus};
+//! use greenfield::prelude::*;
+//!
+//! fn main() -> GreenfieldResult<()> {
+//!     // assets/a/b.png -> out/565/a/b.gfd and out/332/a/b.gfd
+//!     let report = BatchConversion::new(
+//!         "out",
+//!         vec![
+//!             UniformQuantization::new(5, 6, 5)?,
+//!             UniformQuantization::new(3, 3, 2)?,
+//!         ],
+//!     )
+//!     .run(&["assets"])?;
+//!
+//!     for entry in report.failed() {
+//!         if let BatchStatus::Failed(e) = &entry.status {
+//!             eprintln!("{}: {}", entry.input.display(), e);
+//!         }
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use deku::bitvec::{BitVec, Msb0};
+use deku::{DekuError, DekuWrite};
+
+use crate::image::Header;
+use crate::parallel;
+use crate::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// ## Conversion of directory trees
+///
+/// Each input file is converted to `output/<relative path>.<extension>`. With more than one
+/// quantization, each one gets its own subdirectory named after the tuple (e.g. `output/565/`),
+/// followed by a hash of its policies when they aren't the default (e.g. `output/565-1f3a9c20/`).
+///
+/// Inputs that would be converted to the same output (e.g. `a.png` and `a.jpg`) aren't converted,
+/// and are reported as failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchConversion {
+    output: PathBuf,
+    quantizations: Vec<UniformQuantization>,
+    extension: String,
+    force: bool,
+}
+
+/// ## The outcome of converting a file
+#[derive(Debug)]
+pub enum BatchStatus {
+    /// The file was converted.
+    Converted,
+    /// The output is newer than the input, so it was skipped.
+    UpToDate,
+    /// The conversion failed. Failures to load a file are shared by all its outputs.
+    Failed(Arc<GreenfieldError>),
+}
+
+/// ## A converted file
+#[derive(Debug)]
+pub struct BatchEntry {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub quantization: UniformQuantization,
+    pub status: BatchStatus,
+}
+
+/// ## Per-file report of a batch conversion
+///
+/// The entries are sorted by input, then by output.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+}
+
+/// ## An input that couldn't be read, or a directory that couldn't be walked
+#[derive(Debug, thiserror::Error)]
+#[error("{}: {error}", .path.display())]
+pub struct BatchError {
+    pub path: PathBuf,
+    #[source]
+    pub error: GreenfieldError,
+}
+
+impl BatchError {
+    fn new(path: &Path, error: impl Into<GreenfieldError>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            error: error.into(),
+        }
+    }
+}
+
+impl From<BatchError> for GreenfieldError {
+    fn from(e: BatchError) -> Self {
+        e.error
+    }
+}
+
+/// A file to be converted, with its outputs.
+struct Job {
+    input: PathBuf,
+    outputs: Vec<(PathBuf, UniformQuantization)>,
+}
+
+impl BatchConversion {
+    /// ## Creates a new batch conversion to greenfield images
+    ///
+    /// ## Arguments
+    /// * `output` - The directory where the input trees are mirrored
+    /// * `quantizations` - The quantizations to convert each file to
+    pub fn new(output: impl Into<PathBuf>, quantizations: Vec<UniformQuantization>) -> Self {
+        Self {
+            output: output.into(),
+            quantizations,
+            extension: String::from("gfd"),
+            force: false,
+        }
+    }
+
+    /// ## Sets the extension, and so the format, of the outputs
+    ///
+    /// `gfd` by default. Any extension supported by [`save_image`] works.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into();
+        self
+    }
+
+    /// ## Converts the files even if their outputs are up to date
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// ## Converts the input files and directory trees
+    ///
+    /// Directories are walked recursively, and only the files with an image extension (common
+    /// formats or `gfd`) are converted. The output directory is skipped if it's inside an input
+    /// directory, so earlier outputs aren't converted again. Files given explicitly are converted whatever their
+    /// extension, and are placed at the root of the output directory.
+    ///
+    /// ## Returns
+    /// The per-file report. The conversion errors are in the report, they don't abort the batch.
+    ///
+    /// ## Errors
+    /// * If an input can't be read, or a directory can't be walked, with its path (see
+    ///   [`BatchError`]). Nothing is converted then.
+    pub fn run<P: AsRef<Path>>(&self, inputs: &[P]) -> Result<BatchReport, BatchError> {
+        // Outputs inside an input directory aren't inputs. It doesn't exist before the first run
+        let output = fs::canonicalize(&self.output).ok();
+        let mut files = Vec::new();
+        for input in inputs {
+            let input = input.as_ref();
+            match input.is_dir() {
+                true => walk(input, input, output.as_deref(), &mut files)?,
+                false => {
+                    // Surface missing inputs as IO errors
+                    fs::metadata(input).map_err(|e| BatchError::new(input, e))?;
+                    let name = input.file_name().map(PathBuf::from).unwrap_or_default();
+                    files.push((input.to_path_buf(), name));
+                }
+            }
+        }
+
+        let jobs = files
+            .into_iter()
+            .map(|(input, relative)| Job {
+                outputs: self.outputs(&relative),
+                input,
+            })
+            .collect::<Vec<Job>>();
+
+        // Outputs shared by several inputs would be overwritten concurrently
+        let mut counts = HashMap::<&Path, usize>::new();
+        for (output, _) in jobs.iter().flat_map(|job| &job.outputs) {
+            *counts.entry(output).or_default() += 1;
+        }
+        let collisions = counts
+            .into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|(output, _)| output.to_path_buf())
+            .collect::<HashSet<PathBuf>>();
+
+        let mut entries = parallel::map(&jobs, |job| self.convert(job, &collisions))
+            .into_iter()
+            .flatten()
+            .collect::<Vec<BatchEntry>>();
+        entries.sort_by(|a, b| (&a.input, &a.output).cmp(&(&b.input, &b.output)));
+
+        Ok(BatchReport { entries })
+    }
+
+    /// The outputs of a file, relative to the input directory.
+    fn outputs(&self, relative: &Path) -> Vec<(PathBuf, UniformQuantization)> {
+        let relative = relative.with_extension(&self.extension);
+
+        self.quantizations
+            .iter()
+            .map(|quantization| match self.quantizations.len() {
+                1 => (self.output.join(&relative), quantization.clone()),
+                _ => (
+                    self.output.join(directory(quantization)).join(&relative),
+                    quantization.clone(),
+                ),
+            })
+            .collect()
+    }
+
+    /// Converts a file to its stale outputs, but the `collisions`. The input is loaded at most
+    /// once.
+    fn convert(&self, job: &Job, collisions: &HashSet<PathBuf>) -> Vec<BatchEntry> {
+        let mut source: Option<Result<Image, Arc<GreenfieldError>>> = None;
+
+        job.outputs
+            .iter()
+            .map(|(output, quantization)| {
+                let status = match !self.force && is_up_to_date(&job.input, output, quantization) {
+                    _ if collisions.contains(output) => {
+                        let e = std::io::Error::new(
+                            std::io::ErrorKind::AlreadyExists,
+                            format!("{} is the output of other inputs too", output.display()),
+                        );
+                        BatchStatus::Failed(Arc::new(e.into()))
+                    }
+                    true => BatchStatus::UpToDate,
+                    false => {
+                        // Load at full precision, so every quantization starts from the same colors
+                        let image = source.get_or_insert_with(|| {
+                            load_image(&job.input, UniformQuantization::default()).map_err(Arc::new)
+                        });
+
+                        match image {
+                            Ok(image) => match requantize(image, quantization, output) {
+                                Ok(()) => BatchStatus::Converted,
+                                Err(e) => BatchStatus::Failed(Arc::new(e)),
+                            },
+                            Err(e) => BatchStatus::Failed(Arc::clone(e)),
+                        }
+                    }
+                };
+
+                BatchEntry {
+                    input: job.input.clone(),
+                    output: output.clone(),
+                    quantization: quantization.clone(),
+                    status,
+                }
+            })
+            .collect()
+    }
+}
+
+impl BatchReport {
+    /// ## The converted files
+    pub fn converted(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.status, BatchStatus::Converted))
+    }
+
+    /// ## The skipped files, which were up to date
+    pub fn up_to_date(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.status, BatchStatus::UpToDate))
+    }
+
+    /// ## The files that failed to convert
+    pub fn failed(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries
+            .iter()
+            .filter(|e| matches!(e.status, BatchStatus::Failed(_)))
+    }
+
+    /// ## Whether every file was converted or up to date
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+/// Collects the image files under `directory`, with their paths relative to `root`, but the ones
+/// in the `output` directory (canonicalized).
+fn walk(
+    root: &Path,
+    directory: &Path,
+    output: Option<&Path>,
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), BatchError> {
+    let mut entries = fs::read_dir(directory)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| BatchError::new(directory, e))?;
+    entries.sort_by_key(|e| e.path());
+    let is_output = |path: &Path| {
+        output.is_some_and(|output| fs::canonicalize(path).is_ok_and(|path| path == output))
+    };
+
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|e| BatchError::new(&path, e))?;
+        match file_type.is_dir() {
+            true if is_output(&path) => {}
+            true => walk(root, &path, output, files)?,
+            false if is_image(&path) => {
+                let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+                files.push((path, relative));
+            }
+            false => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The subdirectory of a quantization: its bits, and a hash of its serialized policies unless
+/// they're the default.
+fn directory(quantization: &UniformQuantization) -> String {
+    let UniformQuantization {
+        bits_r,
+        bits_g,
+        bits_b,
+        ..
+    } = quantization;
+    let bits = format!("{}{}{}", bits_r, bits_g, bits_b);
+
+    let default = UniformQuantization {
+        bits_r: *bits_r,
+        bits_g: *bits_g,
+        bits_b: *bits_b,
+        ..Default::default()
+    };
+    if *quantization == default {
+        return bits;
+    }
+
+    // FNV-1a, stable across runs and platforms
+    let mut serialized = BitVec::<u8, Msb0>::new();
+    let hash = match quantization.write(&mut serialized, deku::ctx::Endian::Big) {
+        Ok(()) => serialized
+            .as_raw_slice()
+            .iter()
+            .fold(0x811c9dc5u32, |hash, &byte| {
+                (hash ^ byte as u32).wrapping_mul(0x01000193)
+            }),
+        Err(_) => 0,
+    };
+    format!("{bits}-{hash:08x}")
+}
+
+/// Whether a file has the extension of a common image format, or of a greenfield image.
+fn is_image(path: &Path) -> bool {
+    let gfd = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gfd"));
+
+    gfd || image::ImageFormat::from_path(path).is_ok()
+}
+
+/// Whether `output` exists, is newer than `input` and, for greenfield outputs, has the
+/// quantization. Other formats don't record it, so they're only compared by date.
+fn is_up_to_date(input: &Path, output: &Path, quantization: &UniformQuantization) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let newer = match (modified(input), modified(output)) {
+        (Some(input), Some(output)) => output >= input,
+        _ => false,
+    };
+    let gfd = output
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gfd"));
+
+    newer && (!gfd || header(output).is_some_and(|h| h.quantization == *quantization))
+}
+
+/// Reads the header of a greenfield image, without reading its colors.
+fn header(path: &Path) -> Option<Header> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut bytes = Vec::new();
+
+    // Policies make headers longer, so more bytes are read until the header is complete
+    loop {
+        let limit = bytes.len().max(64) as u64;
+        let read = (&mut file).take(limit).read_to_end(&mut bytes).ok()?;
+        match Header::deserialize(&bytes) {
+            Err(GreenfieldError::DekuError(DekuError::Incomplete(_))) if read > 0 => continue,
+            header => return header.ok(),
+        }
+    }
+}
+
+/// Quantizes an image and saves it, creating the parent directories.
+fn requantize(
+    image: &Image,
+    quantization: &UniformQuantization,
+    output: &Path,
+) -> GreenfieldResult<()> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let (width, height) = image.dimensions();
+    let colors = image.colors().cloned().collect::<Vec<Rgb>>();
+    let image = Image::new(width, height, quantization.clone(), colors)?;
+
+    save_image(&image, &output.to_path_buf())
+}
@@ -0,0 +1,254 @@
+use super::*;
+use std::env;
+
+/// Temporary directory for the tests, removed on drop
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("greenfield-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes a 2x2 png to `path`, creating the parent directories.
+fn png(path: &Path) -> GreenfieldResult<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    let image = Image::new(
+        2,
+        2,
+        UniformQuantization::new(8, 8, 8)?,
+        vec![
+            Rgb::new(255, 0, 0),
+            Rgb::new(0, 255, 0),
+            Rgb::new(0, 0, 255),
+            Rgb::new(255, 255, 255),
+        ],
+    )?;
+    save_image(&image, &path.to_path_buf())
+}
+
+#[test]
+/// Should mirror a directory tree, converting every image
+fn batch_convert_tree() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch-tree");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    png(&input.join("a.png"))?;
+    png(&input.join("nested").join("b.png"))?;
+    fs::write(input.join("notes.txt"), "not an image")?;
+
+    let quantization = UniformQuantization::new(5, 6, 5)?;
+    let report = BatchConversion::new(&output, vec![quantization.clone()]).run(&[&input])?;
+
+    assert!(report.is_success());
+    assert_eq!(report.converted().count(), 2);
+    assert_eq!(report.entries[0].input, input.join("a.png"));
+    assert_eq!(report.entries[0].output, output.join("a.gfd"));
+    assert_eq!(
+        report.entries[1].output,
+        output.join("nested").join("b.gfd")
+    );
+
+    let converted = Image::from_file(&output.join("nested").join("b.gfd"))?;
+    assert_eq!(*converted.quantizer(), quantization);
+    assert!(!output.join("notes.gfd").exists());
+
+    Ok(())
+}
+
+#[test]
+/// Should skip the output directory when it's inside the input one
+fn batch_convert_nested_output() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch-nested-output");
+    let input = dir.0.join("input");
+    png(&input.join("a.png"))?;
+    png(&input.join("nested").join("b.png"))?;
+    // Another path to the same directory
+    let output = input.join("nested").join("..").join("output");
+
+    let batch = BatchConversion::new(&output, vec![UniformQuantization::new(5, 6, 5)?]);
+    let report = batch.run(&[&input])?;
+    assert_eq!(report.converted().count(), 2);
+
+    // The outputs of the first run aren't inputs of the second
+    let report = batch.run(&[&input])?;
+    assert_eq!(report.entries.len(), 2);
+    assert_eq!(report.up_to_date().count(), 2);
+    assert!(!input.join("output").join("output").exists());
+
+    Ok(())
+}
+
+#[test]
+/// Should convert to one subdirectory per quantization, and skip up to date files
+fn batch_convert_quantizations() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch-quantizations");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    png(&input.join("a.png"))?;
+
+    let batch = BatchConversion::new(
+        &output,
+        vec![
+            UniformQuantization::new(5, 6, 5)?,
+            UniformQuantization::new(3, 3, 2)?,
+        ],
+    );
+    let report = batch.run(&[&input])?;
+    assert_eq!(report.converted().count(), 2);
+    assert!(output.join("565").join("a.gfd").exists());
+    assert!(output.join("332").join("a.gfd").exists());
+
+    // The outputs are newer than the input
+    let report = batch.run(&[&input])?;
+    assert_eq!(report.up_to_date().count(), 2);
+    assert_eq!(report.converted().count(), 0);
+
+    // Unless forced
+    let report = batch.force(true).run(&[&input])?;
+    assert_eq!(report.converted().count(), 2);
+
+    Ok(())
+}
+
+#[test]
+/// Should report the failed files without aborting the others
+fn batch_convert_failures() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch-failures");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    png(&input.join("a.png"))?;
+    fs::write(input.join("broken.png"), "not a png")?;
+    fs::write(input.join("broken.gfd"), "not a gfd")?;
+
+    let report = BatchConversion::new(&output, vec![UniformQuantization::new(5, 6, 5)?])
+        .extension("bmp")
+        .run(&[&input])?;
+
+    assert!(!report.is_success());
+    assert_eq!(report.converted().count(), 1);
+    assert_eq!(report.failed().count(), 2);
+    assert!(output.join("a.bmp").exists());
+
+    // A load failure is shared by all the outputs of a file, as it was
+    let report = BatchConversion::new(
+        &output,
+        vec![
+            UniformQuantization::new(5, 6, 5)?,
+            UniformQuantization::new(3, 3, 2)?,
+        ],
+    )
+    .run(&[input.join("broken.png")])?;
+    let errors = report
+        .failed()
+        .filter_map(|e| match &e.status {
+            BatchStatus::Failed(error) => Some(error),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2);
+    assert!(Arc::ptr_eq(errors[0], errors[1]));
+    assert!(matches!(**errors[0], GreenfieldError::ImageError(_)));
+
+    // Missing inputs abort the batch
+    let report = BatchConversion::new(&output, vec![]).run(&[input.join("missing")]);
+    assert_eq!(report.unwrap_err().path, input.join("missing"));
+
+    Ok(())
+}
+
+#[test]
+/// Should report inputs converted to the same output, without converting them
+fn batch_convert_collisions() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch-collisions");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    png(&input.join("a.png"))?;
+    png(&input.join("a.bmp"))?;
+    png(&input.join("b.png"))?;
+    png(&dir.0.join("x").join("c.png"))?;
+    png(&dir.0.join("y").join("c.png"))?;
+
+    let quantization = UniformQuantization::new(5, 6, 5)?;
+    let report = BatchConversion::new(&output, vec![quantization]).run(&[
+        input,
+        dir.0.join("x").join("c.png"),
+        dir.0.join("y").join("c.png"),
+    ])?;
+
+    assert_eq!(report.converted().count(), 1);
+    assert!(output.join("b.gfd").exists());
+    let failed = report.failed().map(|e| &e.output).collect::<Vec<_>>();
+    assert_eq!(
+        failed,
+        [
+            &output.join("a.gfd"),
+            &output.join("a.gfd"),
+            &output.join("c.gfd"),
+            &output.join("c.gfd"),
+        ]
+    );
+    assert!(!output.join("a.gfd").exists());
+    assert!(!output.join("c.gfd").exists());
+
+    Ok(())
+}
+
+#[test]
+/// Should name the subdirectories after the whole quantization
+fn batch_convert_policies() -> GreenfieldResult<()> {
+    use crate::quantization::Reconstruction;
+
+    let dir = TempDir::new("batch-policies");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    png(&input.join("a.png"))?;
+
+    let midpoint = UniformQuantization::new(5, 6, 5)?;
+    let replicate = midpoint
+        .clone()
+        .with_reconstruction(Reconstruction::Replicate)?;
+    let report = BatchConversion::new(&output, vec![midpoint, replicate.clone()]).run(&[&input])?;
+
+    assert_eq!(report.converted().count(), 2);
+    assert_eq!(report.entries[0].output, output.join("565").join("a.gfd"));
+    let directory = report.entries[1].output.parent().unwrap();
+    assert!(directory
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("565-")));
+    let converted = Image::from_file(&report.entries[1].output)?;
+    assert_eq!(*converted.quantizer(), replicate);
+
+    Ok(())
+}
+
+#[test]
+/// Should convert again outputs of another quantization, even if they are newer
+fn batch_convert_requantized() -> GreenfieldResult<()> {
+    let dir = TempDir::new("batch-requantized");
+    let input = dir.0.join("input");
+    let output = dir.0.join("output");
+    png(&input.join("a.png"))?;
+
+    let rgb565 = UniformQuantization::new(5, 6, 5)?;
+    BatchConversion::new(&output, vec![rgb565.clone()]).run(&[&input])?;
+    let report = BatchConversion::new(&output, vec![rgb565]).run(&[&input])?;
+    assert_eq!(report.up_to_date().count(), 1);
+
+    let rgb332 = UniformQuantization::new(3, 3, 2)?;
+    let report = BatchConversion::new(&output, vec![rgb332.clone()]).run(&[&input])?;
+    assert_eq!(report.converted().count(), 1);
+    let converted = Image::from_file(&output.join("a.gfd"))?;
+    assert_eq!(*converted.quantizer(), rgb332);
+
+    Ok(())
+}
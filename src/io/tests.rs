@@ -38,3 +38,25 @@ fn io_image() -> GreenfieldResult<()> {
 
     Ok(())
 }
+
+#[test]
+/// Should report the errors of saving an image in a common format
+fn io_save_image_err() -> GreenfieldResult<()> {
+    let image = Image::new(
+        1,
+        1,
+        UniformQuantization::new(8, 8, 8)?,
+        vec![Rgb::new(1, 2, 3)],
+    )?;
+    let missing = env::temp_dir()
+        .join(format!("greenfield-missing-{}", std::process::id()))
+        .join("image.png");
+
+    assert!(matches!(
+        save_image(&image, &missing),
+        Err(GreenfieldError::ImageError(_))
+    ));
+    assert!(!missing.exists());
+
+    Ok(())
+}
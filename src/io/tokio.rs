@@ -0,0 +1,246 @@
+//! ## Asynchronous IO of the Greenfield image format
+//!
+//! Async counterparts of [`Image::from_file`], [`Image::to_file`], [`load_image`](super::load_image)
+//! and [`save_image`](super::save_image), built on top of tokio's [`AsyncRead`] and
+//! [`AsyncWrite`]. Only available with the `tokio` feature.
+//!
+//! The greenfield codec is streamed: [`read_image`] parses the header as soon as its bytes arrive
+//! and then unpacks the color data chunk by chunk, and [`write_image`] packs and writes the color
+//! data in chunks. So, large images never need to be fully buffered in their serialized form.
+//! Conversions from/to other formats are delegated to the blocking functions of [`super`] on
+//! tokio's blocking thread pool.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use greenfield::io::tokio::{read_image, write_image};
+//! use greenfield::prelude::*;
+//!
+//! #[tokio::main]
+//! async fn main() -> GreenfieldResult<()> {
+//!     let image = Image::new(
+//!         2,
+//!         1,
+//!         UniformQuantization::new(5, 6, 5)?,
+//!         vec![Rgb::new(255, 0, 0), Rgb::new(0, 0, 255)],
+//!     )?;
+//!
+//!     let mut serialized = Vec::new();
+//!     write_image(&image, &mut serialized).await?;
+//!     let deserialized = read_image(&mut serialized.as_slice()).await?;
+//!
+//!     assert_eq!(image, deserialized);
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::image::Header;
+use crate::prelude::*;
+use deku::bitvec::{BitVec, Msb0};
+use deku::prelude::*;
+use std::path::Path;
+
+use ::tokio::fs::File;
+use ::tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+
+#[cfg(test)]
+mod tests;
+
+/// Size of the buffers used when streaming the color data, in bytes.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// ## Decodes a Greenfield image from an async reader
+///
+/// The header is parsed first, and then the color data is unpacked as it arrives. Any data after
+/// the last color is left unread.
+///
+/// ## Arguments
+/// * `reader` - The source of the serialized image
+///
+/// ## Returns
+/// The decoded Greenfield image
+///
+/// ## Errors
+/// * If the reader fails or ends before the whole image is read
+/// * If the data is not a valid Greenfield image
+pub async fn read_image<R: AsyncRead + Unpin>(reader: &mut R) -> GreenfieldResult<Image> {
+    // The header is read at least to its minimum size, and then until it's complete
+    let mut bytes = vec![0u8; Header::BITS.div_ceil(8)];
+    reader.read_exact(&mut bytes).await?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let ((rest, offset), header) = loop {
+        match Header::from_bytes((&bytes, 0)) {
+            Err(DekuError::Incomplete(_)) => {
+                let read = reader.read(&mut buffer).await?;
+                if read == 0 {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                }
+                bytes.extend_from_slice(&buffer[..read]);
+            }
+            res => break res?,
+        }
+    };
+    let bits_per_color = header.quantization.bits_per_pixel();
+    let count = header
+        .width
+        .checked_mul(header.height)
+        .ok_or(GreenfieldError::ImageTooLarge(header.width, header.height))?;
+
+    // Bits that were read along with the header but already belong to the color data
+    let mut pending = BitVec::<u8, Msb0>::from_slice(rest);
+    pending.drain(..offset);
+
+    // The dimensions aren't trusted until the data arrives, so the colors grow with the chunks
+    let mut data = Vec::with_capacity(count.min(CHUNK_SIZE * 8));
+    while data.len() < count {
+        let available = (pending.len() / bits_per_color).min(count - data.len());
+
+        if available == 0 {
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            pending.extend_from_raw_slice(&buffer[..read]);
+            continue;
+        }
+
+        let bits = available * bits_per_color;
+        data.extend(header.quantization.decompress(&pending[..bits]));
+        pending.drain(..bits);
+    }
+
+    Ok(Image::from_raw_parts(header, data))
+}
+
+/// ## Encodes a Greenfield image into an async writer
+///
+/// The output is byte for byte the same as [`Image::serialize`]. The writer is flushed once the
+/// whole image is written.
+///
+/// ## Arguments
+/// * `image` - The Greenfield image
+/// * `writer` - The destination of the serialized image
+///
+/// ## Errors
+/// * If the writer fails
+/// * If the image cannot be serialized
+pub async fn write_image<W: AsyncWrite + Unpin>(
+    image: &Image,
+    writer: &mut W,
+) -> GreenfieldResult<()> {
+    let header = image.header();
+    let colors_per_chunk = (CHUNK_SIZE * 8) / header.quantization.bits_per_pixel();
+
+    let mut pending = header.to_bits()?;
+    let mut colors = image.colors();
+    loop {
+        let chunk = colors
+            .by_ref()
+            .take(colors_per_chunk)
+            .cloned()
+            .collect::<Vec<Rgb>>();
+        if chunk.is_empty() {
+            break;
+        }
+        pending.extend(header.quantization.compress(&chunk));
+
+        // Only whole bytes can be written, the remaining bits wait for the next chunk
+        let bytes = pending.len() / 8;
+        writer.write_all(&pending.as_raw_slice()[..bytes]).await?;
+        pending.drain(..bytes * 8);
+    }
+
+    pending.set_uninitialized(false);
+    writer.write_all(pending.as_raw_slice()).await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// ## Reads a Greenfield image from a file
+///
+/// Async version of [`Image::from_file`].
+///
+/// ## Errors
+/// * If the file cannot be read
+/// * If the file is not a valid Greenfield image
+pub async fn from_file(path: &Path) -> GreenfieldResult<Image> {
+    let mut reader = BufReader::new(File::open(path).await?);
+    read_image(&mut reader).await
+}
+
+/// ## Writes a Greenfield image to a file
+///
+/// Async version of [`Image::to_file`].
+///
+/// ## Errors
+/// * If the file cannot be written
+/// * If the image cannot be serialized
+pub async fn to_file(image: &Image, path: &Path) -> GreenfieldResult<()> {
+    let mut writer = BufWriter::new(File::create(path).await?);
+    write_image(image, &mut writer).await
+}
+
+/// ## Convert a image to a Greenfield image and returns it
+///
+/// Async version of [`load_image`](super::load_image). Greenfield images are streamed from disk,
+/// while the other formats are decoded on tokio's blocking thread pool.
+///
+/// ## Arguments
+/// * `path` - The path to the image file
+/// * `quantization` - The quantization used when the image is not a Greenfield image
+///
+/// ## Errors
+/// * If the image cannot be read
+/// * If the image cannot be converted to a Greenfield image
+#[cfg(feature = "image")]
+pub async fn load_image(
+    path: &Path,
+    quantization: impl Into<Quantization>,
+) -> GreenfieldResult<Image> {
+    let mut reader = BufReader::new(File::open(path).await?);
+
+    let mut magic = [0u8; 8];
+    let is_greenfield = match reader.read_exact(&mut magic).await {
+        Ok(_) => &magic == b"grnfld42",
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+        Err(e) => return Err(e.into()),
+    };
+
+    match is_greenfield {
+        true => read_image(&mut magic.as_slice().chain(reader)).await,
+        false => {
+            let (path, quantization) = (path.to_path_buf(), quantization.into());
+            ::tokio::task::spawn_blocking(move || super::load_image(&path, quantization))
+                .await
+                .map_err(std::io::Error::from)?
+        }
+    }
+}
+
+/// ## Convert a Greenfield image to a image and save it to a file
+///
+/// Async version of [`save_image`](super::save_image). As there, the format is inferred from the
+/// file extension, and unknown extensions are saved as Greenfield images, which are streamed to
+/// disk.
+///
+/// ## Arguments
+/// * `image` - The Greenfield image
+/// * `path` - The path to the image file
+///
+/// ## Errors
+/// * If the image cannot be converted to a image
+/// * If the image cannot be saved to a file
+#[cfg(feature = "image")]
+pub async fn save_image(image: &Image, path: &Path) -> GreenfieldResult<()> {
+    match ::image::ImageFormat::from_path(path) {
+        Ok(_) => {
+            let (image, path) = (image.clone(), path.to_path_buf());
+            ::tokio::task::spawn_blocking(move || super::save_image(&image, &path))
+                .await
+                .map_err(std::io::Error::from)?
+        }
+        Err(_) => to_file(image, path).await,
+    }
+}
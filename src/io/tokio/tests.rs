@@ -0,0 +1,151 @@
+use super::*;
+use crate::quantization::Palette;
+use std::env;
+
+/// Builds a image big enough to be streamed in several chunks
+fn big_image() -> GreenfieldResult<Image> {
+    let data = (0..256 * 300)
+        .map(|i| Rgb::new(i as u8, (i / 256) as u8, (i * 7) as u8))
+        .collect::<Vec<Rgb>>();
+
+    Image::new(256, 300, UniformQuantization::new(5, 6, 3)?, data)
+}
+
+#[tokio::test]
+/// Should encode the same bytes as the blocking serialization
+async fn tokio_write_image() -> GreenfieldResult<()> {
+    let image = big_image()?;
+
+    let mut serialized = Vec::new();
+    write_image(&image, &mut serialized).await?;
+
+    assert_eq!(serialized, image.serialize()?);
+
+    Ok(())
+}
+
+#[tokio::test]
+/// Should decode the same image as the blocking deserialization
+async fn tokio_read_image() -> GreenfieldResult<()> {
+    let image = big_image()?;
+    let serialized = image.clone().serialize()?;
+
+    let deserialized = read_image(&mut serialized.as_slice()).await?;
+    assert_eq!(image, deserialized);
+
+    // Ok: additional data will be ignored
+    let mut serialized = serialized.clone();
+    serialized.push(0);
+    let deserialized = read_image(&mut serialized.as_slice()).await?;
+    assert_eq!(image, deserialized);
+
+    Ok(())
+}
+
+#[tokio::test]
+/// Should decode an image arriving in small pieces
+async fn tokio_read_image_chunked() -> GreenfieldResult<()> {
+    let image = big_image()?;
+    let serialized = image.clone().serialize()?;
+
+    let (mut client, mut server) = ::tokio::io::duplex(7);
+    let writer = ::tokio::spawn(async move { server.write_all(&serialized).await });
+
+    let deserialized = read_image(&mut client).await?;
+    writer.await.map_err(std::io::Error::from)??;
+
+    assert_eq!(image, deserialized);
+
+    Ok(())
+}
+
+#[tokio::test]
+/// Should fail on invalid or truncated data
+async fn tokio_read_image_err() -> GreenfieldResult<()> {
+    // Invalid data: lower colors than expected
+    let serialized = vec![
+        103, 114, 110, 102, 108, 100, 52, 50, 0, 0, 0, 1, 0, 0, 0, 1, 136, 128,
+    ];
+    assert!(read_image(&mut serialized.as_slice()).await.is_err());
+
+    // Invalid data: invalid magic number
+    let serialized = vec![
+        103, 114, 110, 102, 108, 100, 52, 51, 0, 0, 0, 1, 0, 0, 0, 1, 136, 128, 0, 0, 0,
+    ];
+    assert!(read_image(&mut serialized.as_slice()).await.is_err());
+
+    // Invalid data: truncated header
+    let serialized = vec![103, 114, 110, 102, 108, 100, 52, 50, 0, 0];
+    assert!(read_image(&mut serialized.as_slice()).await.is_err());
+
+    // Invalid data: huge dimensions without their colors, which shouldn't be allocated up front
+    let serialized = vec![
+        103, 114, 110, 102, 108, 100, 52, 50, 0, 0, 255, 255, 0, 0, 255, 255, 136, 128,
+    ];
+    assert!(read_image(&mut serialized.as_slice()).await.is_err());
+    let serialized = vec![
+        103, 114, 110, 102, 108, 100, 52, 50, 255, 255, 255, 255, 255, 255, 255, 255, 136, 128,
+    ];
+    assert!(read_image(&mut serialized.as_slice()).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+/// Should correctly IO an image
+async fn tokio_file_io() -> GreenfieldResult<()> {
+    let image = big_image()?;
+    let path = env::temp_dir().join("greenfield_tokio_file_io.gfd");
+
+    to_file(&image, &path).await?;
+    assert_eq!(Image::from_file(&path)?, image);
+    assert_eq!(from_file(&path).await?, image);
+
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[tokio::test]
+/// Should convert images between formats
+async fn tokio_io_image() -> GreenfieldResult<()> {
+    let image = big_image()?;
+    let path = env::temp_dir().join("greenfield_tokio_io_image.gfd");
+    to_file(&image, &path).await?;
+
+    // Greenfield images are detected by their magic value, not their extension
+    let quantization = UniformQuantization::new(1, 1, 1)?;
+    assert_eq!(load_image(&path, quantization).await?, image);
+
+    let png = env::temp_dir().join("greenfield_tokio_io_image.png");
+    save_image(&image, &png).await?;
+    let loaded = load_image(&png, UniformQuantization::default()).await?;
+    assert_eq!(loaded.dimensions(), image.dimensions());
+    assert!(loaded.colors().eq(image.colors()));
+
+    std::fs::remove_file(&path)?;
+    std::fs::remove_file(&png)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+/// Should decode images whose palette doesn't fit in the first read
+async fn tokio_read_image_palette() -> GreenfieldResult<()> {
+    let image = big_image()?;
+    let palette = Palette::median_cut(&image, 64)?;
+    let colors = image.colors().cloned().collect::<Vec<Rgb>>();
+    let image = Image::new(256, 300, palette, colors)?;
+    let serialized = image.clone().serialize()?;
+
+    let (mut client, mut server) = ::tokio::io::duplex(7);
+    let writer = ::tokio::spawn(async move { server.write_all(&serialized).await });
+
+    let deserialized = read_image(&mut client).await?;
+    writer.await.map_err(std::io::Error::from)??;
+
+    assert_eq!(image, deserialized);
+
+    Ok(())
+}
@@ -5,14 +5,41 @@
 //!
 //! Also, it includes some utilities for manipulating images in the greenfield format, like
 //! quantization, dithering and conversion to other formats.
+//!
+//! ## Features
+//!
+//! The color, quantization and image encoding/decoding core is `no_std` and only needs `alloc`.
+//! Everything else is behind features, enabled by default:
+//!
+//! - `std`: File IO ([`image::Image::from_file`], [`image::Image::to_file`] and [`io`]),
+//!   `std::error::Error` support and the quantization optimizer ([`quantization::Optimizer`]).
+//! - `colored`: Truecolor terminal output when displaying colors and pixels.
+//! - `image`: Conversion between greenfield images and common formats ([`io::load_image`] and
+//!   [`io::save_image`]).
+//! - `rand`: Random colors ([`color::Rgb::random`]) and k-means palettes
+//!   ([`quantization::KMeans`]).
+//! - `rayon`: Parallel processing (see [`parallel`]).
+//!
+//! And some optional ones:
+//!
+//! - `serde`: Serde support for colors, quantizations and images.
+//! - `tokio`: Async IO (see `io::tokio`).
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 mod core;
 mod error;
 
 pub use crate::core::color;
+pub use crate::core::dithering;
 pub use crate::core::image;
+pub use crate::core::metrics;
+pub use crate::core::parallel;
 pub use crate::core::pixel;
 pub use crate::core::quantization;
+pub use crate::core::statistics;
+#[cfg(feature = "std")]
 pub mod io;
 pub use crate::error::{GreenfieldError, GreenfieldResult};
 
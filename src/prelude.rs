@@ -3,9 +3,12 @@
 //! [What is a prelude?](std::prelude)
 pub use crate::{
     color::Rgb,
+    dithering::Dithering,
     error::{GreenfieldError, GreenfieldResult},
     image::Image,
-    io::{load_image, save_image},
     pixel::Pixel,
-    quantization::UniformQuantization,
+    quantization::{Quantization, Quantizer, UniformQuantization},
 };
+
+#[cfg(feature = "image")]
+pub use crate::io::{load_image, load_image_dithered, save_image};